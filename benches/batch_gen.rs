@@ -0,0 +1,56 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use dsp_seed_finder::{generate_galaxies, generate_galaxy, GameDesc};
+use std::hint::black_box;
+
+fn games() -> Vec<GameDesc> {
+    (0..64)
+        .map(|seed| GameDesc {
+            seed,
+            star_count: 64,
+            resource_multiplier: 1.0,
+        })
+        .collect()
+}
+
+/// Star-level data only: the luminosity needs every lazy star property.
+fn touch_stars(galaxy: &dsp_seed_finder::Galaxy) -> f32 {
+    galaxy.stars.iter().map(|sp| sp.star.get_luminosity()).sum()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let games = games();
+    let mut group = c.benchmark_group("star generation");
+    group.throughput(Throughput::Elements(games.len() as u64));
+    group.bench_function("scalar", |b| {
+        b.iter(|| {
+            for game in &games {
+                black_box(touch_stars(&generate_galaxy(game)));
+            }
+        })
+    });
+    group.bench_function(BenchmarkId::new("batched", 4), |b| {
+        b.iter(|| {
+            for galaxy in generate_galaxies::<4>(&games) {
+                black_box(touch_stars(&galaxy));
+            }
+        })
+    });
+    group.bench_function(BenchmarkId::new("batched", 8), |b| {
+        b.iter(|| {
+            for galaxy in generate_galaxies::<8>(&games) {
+                black_box(touch_stars(&galaxy));
+            }
+        })
+    });
+    group.bench_function(BenchmarkId::new("batched", 16), |b| {
+        b.iter(|| {
+            for galaxy in generate_galaxies::<16>(&games) {
+                black_box(touch_stars(&galaxy));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -1,6 +1,6 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use dsp_seed_finder::{create_galaxy, GameDesc};
-use std::{cell::Cell, hint::black_box};
+use std::hint::black_box;
 
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("create galaxy", |b| {
@@ -10,7 +10,6 @@ fn criterion_benchmark(c: &mut Criterion) {
                     seed,
                     star_count: 64,
                     resource_multiplier: 1.0,
-                    habitable_count: Cell::new(0),
                 };
                 black_box(create_galaxy(&desc));
             }
@@ -0,0 +1,69 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use dsp_seed_finder::{generate_galaxy, Galaxy, GameDesc};
+use std::hint::black_box;
+
+fn game() -> GameDesc {
+    GameDesc {
+        seed: 42,
+        star_count: 64,
+        resource_multiplier: 1.0,
+    }
+}
+
+/// Planets are generated in star order, as the rules do.
+fn with_planets(galaxy: Galaxy) -> Galaxy {
+    for sp in &galaxy.stars {
+        sp.get_planets().count();
+    }
+    galaxy
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let game = game();
+    let mut group = c.benchmark_group("planets");
+    group.bench_function("get_planets", |b| {
+        b.iter_batched(
+            || generate_galaxy(&game),
+            |galaxy| with_planets(galaxy),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("get_theme", |b| {
+        b.iter_batched(
+            || with_planets(generate_galaxy(&game)),
+            |galaxy| {
+                for sp in &galaxy.stars {
+                    for planet in sp.get_planets() {
+                        black_box(planet.get_theme());
+                    }
+                }
+                galaxy
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("get_veins", |b| {
+        b.iter_batched(
+            || {
+                let galaxy = generate_galaxy(&game);
+                for sp in &galaxy.stars {
+                    sp.load_planets();
+                }
+                galaxy
+            },
+            |galaxy| {
+                for sp in &galaxy.stars {
+                    for planet in sp.get_planets() {
+                        black_box(planet.get_veins().len());
+                    }
+                }
+                galaxy
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,45 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use dsp_seed_finder::{DspRandom, DspRandomBatch};
+use std::hint::black_box;
+
+const DRAWS: u64 = 1000;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("random");
+    group.throughput(Throughput::Elements(DRAWS));
+    group.bench_function("new", |b| {
+        b.iter(|| {
+            for seed in 0..DRAWS as i32 {
+                black_box(DspRandom::new(black_box(seed)));
+            }
+        })
+    });
+    group.bench_function("next_f64", |b| {
+        let mut rand = DspRandom::new(42);
+        b.iter(|| {
+            for _ in 0..DRAWS {
+                black_box(rand.next_f64());
+            }
+        })
+    });
+    group.bench_function("next_seed", |b| {
+        let mut rand = DspRandom::new(42);
+        b.iter(|| {
+            for _ in 0..DRAWS {
+                black_box(rand.next_seed());
+            }
+        })
+    });
+    group.bench_function("next_f64 x8", |b| {
+        let mut rand = DspRandomBatch::<8>::new(std::array::from_fn(|lane| lane as i32));
+        b.iter(|| {
+            for _ in 0..DRAWS / 8 {
+                black_box(rand.next_f64());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use dsp_seed_finder::{find_stars, parse_query, transform_rules, GameDesc};
+use std::hint::black_box;
+
+const SEEDS: i32 = 32;
+
+/// One rule of each kind the search spends its time on.
+const RULES: [(&str, &str); 6] = [
+    (
+        "star filter",
+        "star where luminosity > 1.5 and spectr in (O, B)",
+    ),
+    (
+        "birth distance",
+        "star where dist(birth) < 10 and dyson_radius > 1",
+    ),
+    ("x distance", "star where dist(X, count >= 2) < 10"),
+    ("planets", "star where planets >= 4 and tidal_locked >= 2"),
+    ("theme", "star where theme in (8, 9) and gas >= 2"),
+    (
+        "veins",
+        "star where vein(Mag) >= 0.5 or vein(Grat) >= 20 and vein(Fractal) >= 20",
+    ),
+];
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find stars");
+    group.throughput(Throughput::Elements(SEEDS as u64));
+    for (name, query) in RULES {
+        let mut rule = transform_rules(parse_query(query).unwrap());
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for seed in 0..SEEDS {
+                    let game = GameDesc {
+                        seed,
+                        star_count: 64,
+                        resource_multiplier: 1.0,
+                    };
+                    black_box(find_stars(&game, &mut rule));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,64 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use dsp_seed_finder::{parse_query, search, GameDesc, SearchEvent, SeedSelection, Workers};
+use std::hint::black_box;
+use std::sync::atomic::AtomicBool;
+
+const SEEDS: i32 = 256;
+
+/// Seeds per second of the native search loop, as `find` runs it.
+fn criterion_benchmark(c: &mut Criterion) {
+    let game = GameDesc {
+        seed: 0,
+        star_count: 64,
+        resource_multiplier: 1.0,
+    };
+    let seeds = SeedSelection::range(0, SEEDS);
+    let max_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut threads = vec![1];
+    if max_threads > 1 {
+        threads.push(max_threads);
+    }
+    for (name, query) in [
+        (
+            "search stars",
+            "star where luminosity > 1.5 and dist(birth) < 20",
+        ),
+        (
+            "search veins",
+            "star where vein(Mag) >= 0.5 and dist(birth) < 20",
+        ),
+    ] {
+        let rule = parse_query(query).unwrap();
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Elements(SEEDS as u64));
+        group.sample_size(10);
+        for &threads in &threads {
+            group.bench_with_input(
+                BenchmarkId::new("threads", threads),
+                &threads,
+                |b, &threads| {
+                    b.iter(|| {
+                        search(
+                            &game,
+                            &rule,
+                            &seeds,
+                            Workers::new(threads),
+                            0,
+                            false,
+                            &AtomicBool::new(false),
+                            |event| {
+                                if let SearchEvent::Result { indexes, .. } = event {
+                                    black_box(indexes);
+                                }
+                            },
+                        )
+                    })
+                },
+            );
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,42 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use dsp_seed_finder::{find_stars, parse_query, transform_rules, GameDesc, Workspace};
+use std::hint::black_box;
+
+const SEEDS: i32 = 64;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let game = GameDesc {
+        seed: 0,
+        star_count: 64,
+        resource_multiplier: 1.0,
+    };
+    for (name, query) in [
+        ("stars", "star where luminosity > 1.5 and dist(birth) < 20"),
+        ("planets", "star where planets >= 4 and tidal_locked >= 2"),
+    ] {
+        let mut rule = transform_rules(parse_query(query).unwrap());
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Elements(SEEDS as u64));
+        group.bench_function("fresh", |b| {
+            b.iter(|| {
+                for seed in 0..SEEDS {
+                    let game = GameDesc { seed, ..game };
+                    black_box(find_stars(&game, &mut rule));
+                }
+            })
+        });
+        let mut workspace = Workspace::new(&game);
+        group.bench_function("workspace", |b| {
+            b.iter(|| {
+                for seed in 0..SEEDS {
+                    workspace.generate(seed);
+                    black_box(workspace.find_stars(0, &*rule));
+                }
+            })
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
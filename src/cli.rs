@@ -0,0 +1,799 @@
+use dsp_seed_finder::{
+    compare_galaxies, create_galaxy, find_planets, find_stars, format_query, generate_galaxy,
+    normalize_rules, parse_query, rank_seeds, rank_x_stars, render_svg, search, sweep,
+    transform_rules, Alignment, CompareOptions, CoordinatorOptions, GameDesc, Locale, Metric,
+    NameMatch, RuleStarName, Rules, SearchEvent, SeedSelection, SeedSource, StarNamePattern,
+    SvgOptions, SvgView, Workers, XStarWeights,
+};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+
+pub const USAGE: &str = "\
+Usage:
+    dsp_seed [serve]
+        Start the native mode server for the web UI.
+    dsp_seed coordinator [options]
+        Start the native mode server, distributing searches to workers.
+        --listen <addr>         Address workers connect to (default: 127.0.0.1:62880,
+                                use 0.0.0.0:62880 for workers on other machines)
+        --chunk <count>         Seeds handed to a worker at once (default: 10000)
+        --lease <seconds>       Reassign a chunk after this long without
+                                hearing from its worker (default: 60)
+    dsp_seed worker --connect <addr> [options]
+        Search chunks handed out by a coordinator.
+        --threads <count>       Worker threads (default: all cores)
+    dsp_seed svg --seed <seed> [options]
+        Render a map of the galaxy as SVG.
+        --view <top|side|both>  Projection to draw (default: both)
+        --size <px>             Size of each projection (default: 480)
+        --rings <ly,...>        Draw distance rings around the birth star
+        --rule <file>           Highlight stars matching the rule
+        --query <query>         Same, with the rule given as a query
+        --labels                Draw star names
+        --out <file.svg>        Write to a file instead of stdout
+    dsp_seed compare --seeds <seed,seed,...> [options]
+        Compare galaxies star by star.
+        --align <rank|type>     Align stars by distance rank or by type (default: rank)
+        --within <ly>           Radius for counting O/B stars near birth (default: 10)
+        --rows <count>          Only report the first rows
+        --format <text|json>    Output format (default: text)
+        --out <file>            Write to a file instead of stdout
+    dsp_seed find --rule <file> <seeds> [options]
+    dsp_seed find --query <query> <seeds> [options]
+        Search seeds for stars matching the rule, printing one line per seed.
+        --threads <count>       Worker threads (default: all cores)
+        --planets               Also list the matching planets of each star
+        --ordered               Write results in the order of the seeds
+                                given rather than as they are found
+        --format <text|json>    Output format (default: text)
+        --out <file>            Write to a file instead of stdout
+    dsp_seed name --name <name> <seeds> [options]
+        Search seeds for a star with the given name.
+        --match <exact|prefix|regex>
+                                How to compare names (default: exact)
+        --ignore-case           Ignore upper and lower case
+        --threads <count>       Worker threads (default: all cores)
+        --ordered               Write results in the order of the seeds
+                                given rather than as they are found
+        --format <text|json>    Output format (default: text)
+        --out <file>            Write to a file instead of stdout
+    dsp_seed xstars --seed <seed> [options]
+    dsp_seed xstars <seeds> --top <count> [options]
+        Rank X stars (neutron stars, black holes) and white dwarfs by their
+        expected Mag, Grat and Fractal yields and distances, either for one
+        seed or keeping the seeds whose best star scores highest.
+        --weights <mag,grat,fractal,birth,giant>
+                                Score weights; the distances to birth and to
+                                the nearest blue giant count negatively
+                                (default: 1,0.5,0.5,50000,0)
+        --threads <count>       Worker threads (default: all cores)
+        --format <text|json>    Output format (default: text)
+        --out <file>            Write to a file instead of stdout
+    dsp_seed stats --range <start,end> [options]
+        Measure the distribution of metrics over a range of seeds.
+        --metrics <metric,...>  Metrics to collect:
+                                  max-luminosity, max-dyson-radius,
+                                  vein-stars:<vein>, max-vein:<vein>,
+                                  nearest:<star type>, spectr-count:<spectr>[:<ly>],
+                                  x-star-score
+        --rule <file>           Estimate how often the rule matches
+        --query <query>         Same, with the rule given as a query
+        --confidence <level>    Confidence level of the hit rate (default: 0.95)
+        --bins <count>          Histogram bins (default: 10)
+        --threads <count>       Worker threads (default: all cores)
+        --format <text|json>    Output format (default: text)
+        --out <file>            Write to a file instead of stdout
+    dsp_seed query (--query <query> | --rule <file>) [options]
+        Convert a rule between its query and JSON forms.
+        --normalize             Simplify the rule first
+        --format <json|query>   Output format (default: json)
+        --out <file>            Write to a file instead of stdout
+
+Rules are JSON files, or text queries such as
+    star where luminosity > 2 and gas(ice=true) >= 2 and dist(birth) < 10
+    galaxy where count(spectr = O and dist(birth) < 10) >= 2
+A --rule file that does not start with `{` is read as a query.
+
+Seeds for find, name and xstars:
+    --range <start,end>         Seeds from start to end (exclusive)
+    --step <n>                  Only every n-th seed of the range
+    --seeds <seed,...>          An explicit list of seeds
+    --seeds-file <file>         Seeds from the first column of a text/CSV file
+    --shard <k/n>               Only the k-th of n interleaved shards
+
+Search threads of serve, worker, find and name:
+    --pin                       Pin each thread to a core
+    --nice <n>                  Niceness of the threads, from -20 to 19 (Linux only)
+
+Common options:
+    --stars <count>             Star count (default: 64)
+    --resource <multiplier>     Resource multiplier (default: 1.0)
+    --locale <en|zh>            Language of game names in reports (default: en)";
+
+/// Every option, and whether it takes a value. `USAGE` shows those that do
+/// with a `<placeholder>`.
+const OPTIONS: &[(&str, bool)] = &[
+    ("align", true),
+    ("bins", true),
+    ("chunk", true),
+    ("confidence", true),
+    ("connect", true),
+    ("format", true),
+    ("ignore-case", false),
+    ("labels", false),
+    ("lease", true),
+    ("listen", true),
+    ("locale", true),
+    ("match", true),
+    ("metrics", true),
+    ("name", true),
+    ("nice", true),
+    ("normalize", false),
+    ("ordered", false),
+    ("out", true),
+    ("pin", false),
+    ("planets", false),
+    ("query", true),
+    ("range", true),
+    ("resource", true),
+    ("rings", true),
+    ("rows", true),
+    ("rule", true),
+    ("seed", true),
+    ("seeds", true),
+    ("seeds-file", true),
+    ("shard", true),
+    ("size", true),
+    ("stars", true),
+    ("step", true),
+    ("threads", true),
+    ("top", true),
+    ("view", true),
+    ("weights", true),
+    ("within", true),
+];
+
+/// Whether the option `--key` takes a value, or `None` if there is no such
+/// option.
+fn takes_value(key: &str) -> Option<bool> {
+    OPTIONS
+        .iter()
+        .find(|(name, _)| *name == key)
+        .map(|&(_, value)| value)
+}
+
+pub struct Args {
+    pub command: Option<String>,
+    options: HashMap<String, String>,
+    flags: HashSet<String>,
+}
+
+impl Args {
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut command = None;
+        let mut options = HashMap::new();
+        let mut flags = HashSet::new();
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            if let Some(key) = arg.strip_prefix("--") {
+                match takes_value(key) {
+                    None => return Err(format!("Unknown option: {}", arg)),
+                    Some(true) => match args.next_if(|value| !value.starts_with("--")) {
+                        Some(value) => {
+                            options.insert(key.to_owned(), value);
+                        }
+                        None => return Err(format!("Missing value for {}", arg)),
+                    },
+                    Some(false) => {
+                        flags.insert(key.to_owned());
+                    }
+                }
+            } else if command.is_none() {
+                command = Some(arg);
+            } else {
+                return Err(format!("Unexpected argument: {}", arg));
+            }
+        }
+        Ok(Self {
+            command,
+            options,
+            flags,
+        })
+    }
+
+    pub fn get<T: FromStr>(&self, key: &str) -> Result<Option<T>, String> {
+        match self.options.get(key) {
+            Some(value) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("Invalid value for --{}: {}", key, value)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn require<T: FromStr>(&self, key: &str) -> Result<T, String> {
+        self.get(key)?
+            .ok_or_else(|| format!("Missing required option --{}", key))
+    }
+
+    pub fn get_list<T: FromStr>(&self, key: &str) -> Result<Vec<T>, String> {
+        match self.options.get(key) {
+            Some(value) => value
+                .split(',')
+                .filter(|s| !s.trim().is_empty())
+                .map(|s| {
+                    s.trim()
+                        .parse()
+                        .map_err(|_| format!("Invalid value for --{}: {}", key, s))
+                })
+                .collect(),
+            None => Ok(vec![]),
+        }
+    }
+
+    pub fn flag(&self, key: &str) -> bool {
+        self.flags.contains(key)
+    }
+
+    pub fn locale(&self) -> Result<Locale, String> {
+        Ok(self.get("locale")?.unwrap_or_default())
+    }
+
+    pub fn game_desc(&self, seed: i32) -> Result<GameDesc, String> {
+        Ok(GameDesc {
+            seed,
+            star_count: self
+                .get("stars")?
+                .unwrap_or_else(GameDesc::default_star_count),
+            resource_multiplier: self
+                .get("resource")?
+                .unwrap_or_else(GameDesc::default_resource_multiplier),
+        })
+    }
+
+    /// The rule of `--query`, or of the `--rule` file, which holds either a
+    /// JSON rule or a query.
+    pub fn rule(&self) -> Result<Option<Rules>, String> {
+        if let Some(query) = self.options.get("query") {
+            return parse_query(query)
+                .map(Some)
+                .map_err(|err| format!("Invalid query: {}", err.render(query)));
+        }
+        match self.options.get("rule") {
+            Some(path) => {
+                let text = std::fs::read_to_string(path)
+                    .map_err(|err| format!("Cannot read {}: {}", path, err))?;
+                if !text.trim_start().starts_with('{') {
+                    return parse_query(&text).map(Some).map_err(|err| {
+                        format!("Invalid query in {}: {}", path, err.render(&text))
+                    });
+                }
+                serde_json::from_str(&text)
+                    .map(Some)
+                    .map_err(|err| format!("Invalid rule in {}: {}", path, err))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn range(&self) -> Result<(i32, i32), String> {
+        match self.get_list::<i32>("range")?[..] {
+            [start, end] if start < end => Ok((start, end)),
+            _ => Err("--range expects <start,end> with start < end".to_owned()),
+        }
+    }
+
+    pub fn seed_selection(&self) -> Result<SeedSelection, String> {
+        let source = if self.options.contains_key("seeds") {
+            SeedSource::List {
+                seeds: self.get_list("seeds")?,
+            }
+        } else if let Some(path) = self.options.get("seeds-file") {
+            SeedSource::File { path: path.clone() }
+        } else {
+            let (start, end) = self.range()?;
+            SeedSource::Range {
+                start,
+                end,
+                step: self.get("step")?.unwrap_or_else(SeedSource::default_step),
+            }
+        };
+        SeedSelection {
+            source,
+            shard: self.get("shard")?.unwrap_or_default(),
+        }
+        .resolve()
+    }
+
+    pub fn threads(&self) -> Result<usize, String> {
+        match self.get("threads")? {
+            Some(threads) => Ok(threads),
+            None => Ok(std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)),
+        }
+    }
+
+    /// The search threads of `--threads`, `--pin` and `--nice`.
+    pub fn workers(&self) -> Result<Workers, String> {
+        let nice = self.get::<i32>("nice")?;
+        if let Some(nice) = nice.filter(|nice| !(-20..=19).contains(nice)) {
+            return Err(format!("Invalid value for --nice: {}", nice));
+        }
+        Ok(Workers {
+            threads: self.threads()?,
+            pin: self.flag("pin"),
+            nice,
+        })
+    }
+
+    pub fn coordinator_options(&self) -> Result<CoordinatorOptions, String> {
+        let mut options = CoordinatorOptions::default();
+        if let Some(chunk_size) = self.get::<usize>("chunk")? {
+            if chunk_size == 0 {
+                return Err("Invalid value for --chunk: 0".to_owned());
+            }
+            options.chunk_size = chunk_size;
+        }
+        if let Some(lease) = self.get("lease")? {
+            options.lease = std::time::Duration::from_secs(lease);
+        }
+        Ok(options)
+    }
+
+    pub fn open_output(&self) -> Result<Box<dyn Write + Send>, String> {
+        match self.options.get("out") {
+            Some(path) => std::fs::File::create(path)
+                .map(|file| Box::new(std::io::BufWriter::new(file)) as Box<dyn Write + Send>)
+                .map_err(|err| format!("Cannot write {}: {}", path, err)),
+            None => Ok(Box::new(std::io::stdout())),
+        }
+    }
+
+    pub fn write_output(&self, output: &str) -> Result<(), String> {
+        match self.options.get("out") {
+            Some(path) => std::fs::write(path, output)
+                .map_err(|err| format!("Cannot write {}: {}", path, err)),
+            None => {
+                print!("{}", output);
+                Ok(())
+            }
+        }
+    }
+}
+
+pub fn run(command: &str, args: &Args) -> Result<(), String> {
+    match command {
+        "svg" => run_svg(args),
+        "compare" => run_compare(args),
+        "stats" => run_stats(args),
+        "find" => run_find(args),
+        "name" => run_name(args),
+        "xstars" => run_xstars(args),
+        "query" => run_query(args),
+        "help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(format!("Unknown command: {}\n{}", command, USAGE)),
+    }
+}
+
+fn run_svg(args: &Args) -> Result<(), String> {
+    let seed = args.require("seed")?;
+    let view = match args.get::<String>("view")?.as_deref() {
+        None | Some("both") => SvgView::Both,
+        Some("top") => SvgView::Top,
+        Some("side") => SvgView::Side,
+        Some(other) => return Err(format!("Invalid value for --view: {}", other)),
+    };
+    let highlights = match args.rule()? {
+        Some(rule) => find_stars(&args.game_desc(seed)?, &mut transform_rules(rule)),
+        None => vec![],
+    };
+    let game = args.game_desc(seed)?;
+    let galaxy = create_galaxy(&game);
+    let options = SvgOptions {
+        view,
+        size: args.get("size")?.unwrap_or(480.0),
+        rings: args.get_list("rings")?,
+        highlights,
+        labels: args.flag("labels"),
+        locale: args.locale()?,
+    };
+    args.write_output(&render_svg(&galaxy, &options))
+}
+
+fn run_compare(args: &Args) -> Result<(), String> {
+    let seeds: Vec<i32> = args.get_list("seeds")?;
+    if seeds.len() < 2 {
+        return Err("--seeds needs at least two seeds".to_owned());
+    }
+    let alignment = match args.get::<String>("align")?.as_deref() {
+        None | Some("rank") => Alignment::DistanceRank,
+        Some("type") => Alignment::StarType,
+        Some(other) => return Err(format!("Invalid value for --align: {}", other)),
+    };
+    let options = CompareOptions {
+        alignment,
+        nearby_distance: args.get("within")?.unwrap_or(10.0),
+        max_rows: args.get("rows")?.unwrap_or(0),
+    };
+    let games = seeds
+        .iter()
+        .map(|seed| args.game_desc(*seed))
+        .collect::<Result<Vec<_>, _>>()?;
+    let galaxies: Vec<_> = games.iter().map(create_galaxy).collect();
+    let comparison = compare_galaxies(&galaxies.iter().collect::<Vec<_>>(), &options);
+    let output = match args.get::<String>("format")?.as_deref() {
+        None | Some("text") => comparison.to_text(args.locale()?),
+        Some("json") => serde_json::to_string_pretty(&comparison).unwrap(),
+        Some(other) => return Err(format!("Invalid value for --format: {}", other)),
+    };
+    args.write_output(&output)
+}
+
+fn run_xstars(args: &Args) -> Result<(), String> {
+    let weights = match args.get_list::<f64>("weights")?[..] {
+        [] => XStarWeights::default(),
+        [mag, grat, fractal, birth_distance, giant_distance] => XStarWeights {
+            mag,
+            grat,
+            fractal,
+            birth_distance,
+            giant_distance,
+        },
+        _ => return Err("--weights expects <mag,grat,fractal,birth,giant>".to_owned()),
+    };
+    let json = match args.get::<String>("format")?.as_deref() {
+        None | Some("text") => false,
+        Some("json") => true,
+        Some(other) => return Err(format!("Invalid value for --format: {}", other)),
+    };
+    let rankings = match args.get::<i32>("seed")? {
+        Some(seed) => vec![rank_x_stars(&args.game_desc(seed)?, &weights)],
+        None => {
+            let top = args.require::<usize>("top")?;
+            let game = args.game_desc(0)?;
+            rank_seeds(
+                &game,
+                &args.seed_selection()?,
+                &weights,
+                top,
+                args.threads()?,
+            )
+        }
+    };
+    let output = if json {
+        serde_json::to_string_pretty(&rankings).unwrap()
+    } else {
+        let locale = args.locale()?;
+        rankings
+            .iter()
+            .map(|ranking| ranking.to_text(locale))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    args.write_output(&output)
+}
+
+fn never_matches(reason: String) -> String {
+    format!("The rule can never match: {}", reason)
+}
+
+fn run_query(args: &Args) -> Result<(), String> {
+    let rule = args
+        .rule()?
+        .ok_or("Nothing to convert, pass --query or --rule")?;
+    let rule = if args.flag("normalize") {
+        normalize_rules(rule).map_err(never_matches)?
+    } else {
+        rule
+    };
+    let output = match args.get::<String>("format")?.as_deref() {
+        None | Some("json") => serde_json::to_string_pretty(&rule).unwrap(),
+        Some("query") => format_query(&rule),
+        Some(other) => return Err(format!("Invalid value for --format: {}", other)),
+    };
+    args.write_output(&format!("{}\n", output))
+}
+
+fn run_stats(args: &Args) -> Result<(), String> {
+    let (start, end) = args.range()?;
+    let metrics: Vec<Metric> = args.get_list("metrics")?;
+    let rule = args
+        .rule()?
+        .map(normalize_rules)
+        .transpose()
+        .map_err(never_matches)?;
+    if metrics.is_empty() && rule.is_none() {
+        return Err("Nothing to measure, pass --metrics and/or --rule".to_owned());
+    }
+    let confidence = args.get("confidence")?.unwrap_or(0.95);
+    if !(0.0..1.0).contains(&confidence) {
+        return Err(format!("Invalid value for --confidence: {}", confidence));
+    }
+    let game = args.game_desc(start)?;
+    let locale = args.locale()?;
+    let stats = sweep(&game, start..end, &metrics, rule.as_ref(), args.threads()?);
+    let report = stats.report(
+        end.abs_diff(start) as usize,
+        args.get("bins")?.unwrap_or(10),
+        rule.as_ref().map(|_| confidence),
+        locale,
+    );
+    let output = match args.get::<String>("format")?.as_deref() {
+        None | Some("text") => report.to_text(locale),
+        Some("json") => serde_json::to_string_pretty(&report).unwrap(),
+        Some(other) => return Err(format!("Invalid value for --format: {}", other)),
+    };
+    args.write_output(&output)
+}
+
+fn run_find(args: &Args) -> Result<(), String> {
+    let rule = args
+        .rule()?
+        .ok_or_else(|| "Missing required option --rule".to_owned())?;
+    let planets = args.flag("planets");
+    run_search(args, &rule, |game, seed, indexes, json| {
+        let planets = if planets {
+            let game = game.with_seed(seed);
+            find_planets(&game, transform_rules(rule.clone()).as_ref(), indexes)
+        } else {
+            vec![]
+        };
+        if json {
+            if planets.is_empty() {
+                format!(r#"{{"seed":{},"indexes":{:?}}}"#, seed, indexes)
+            } else {
+                format!(
+                    r#"{{"seed":{},"indexes":{:?},"planets":{:?}}}"#,
+                    seed, indexes, planets
+                )
+            }
+        } else {
+            let stars: Vec<String> = indexes
+                .iter()
+                .enumerate()
+                .map(|(i, index)| match planets.get(i) {
+                    Some(planets) if !planets.is_empty() => {
+                        let planets: Vec<String> = planets.iter().map(usize::to_string).collect();
+                        format!("{}[{}]", index, planets.join(","))
+                    }
+                    _ => index.to_string(),
+                })
+                .collect();
+            format!("{}: {}", seed, stars.join(","))
+        }
+    })
+}
+
+fn run_name(args: &Args) -> Result<(), String> {
+    let mode = match args.get::<String>("match")?.as_deref() {
+        None | Some("exact") => NameMatch::Exact,
+        Some("prefix") => NameMatch::Prefix,
+        Some("regex") => NameMatch::Regex,
+        Some(other) => return Err(format!("Invalid value for --match: {}", other)),
+    };
+    let rule = RuleStarName::try_from(StarNamePattern {
+        name: args.require("name")?,
+        mode,
+        ignore_case: args.flag("ignore-case"),
+    })?;
+    run_search(args, &Rules::StarName(rule), |game, seed, indexes, json| {
+        let game = game.with_seed(seed);
+        let galaxy = generate_galaxy(&game);
+        let names: Vec<String> = indexes.iter().map(|&i| galaxy.get_star_name(i)).collect();
+        if json {
+            let stars: Vec<String> = indexes
+                .iter()
+                .zip(&names)
+                .map(|(index, name)| {
+                    format!(
+                        r#"{{"index":{},"name":{}}}"#,
+                        index,
+                        serde_json::to_string(name).unwrap()
+                    )
+                })
+                .collect();
+            format!(r#"{{"seed":{},"stars":[{}]}}"#, seed, stars.join(","))
+        } else {
+            format!("{}: {}", seed, names.join(", "))
+        }
+    })
+}
+
+/// Searches the seeds given on the command line, writing one line per match.
+fn run_search(
+    args: &Args,
+    rule: &Rules,
+    format_line: impl Fn(&GameDesc, i32, &[usize], bool) -> String + Sync,
+) -> Result<(), String> {
+    let rule = &normalize_rules(rule.clone()).map_err(never_matches)?;
+    let seeds = args.seed_selection()?;
+    let json = match args.get::<String>("format")?.as_deref() {
+        None | Some("text") => false,
+        Some("json") => true,
+        Some(other) => return Err(format!("Invalid value for --format: {}", other)),
+    };
+    let game = args.game_desc(0)?;
+    let output = Mutex::new(args.open_output()?);
+    let total = seeds.len();
+    let stop = AtomicBool::new(false);
+    search(
+        &game,
+        rule,
+        &seeds,
+        args.workers()?,
+        5,
+        args.flag("ordered"),
+        &stop,
+        |event| match event {
+            SearchEvent::Result { seed, indexes } => {
+                let line = format_line(&game, seed, &indexes, json);
+                let _ = writeln!(output.lock().unwrap(), "{}", line);
+            }
+            SearchEvent::Progress { end, .. } => eprintln!("Progress: {}/{}.", end, total),
+            SearchEvent::Done { end, .. } => eprintln!("Completed: {}/{}.", end, total),
+        },
+    );
+    output
+        .into_inner()
+        .unwrap()
+        .flush()
+        .map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Args, String> {
+        Args::parse(args.split_whitespace().map(str::to_owned))
+    }
+
+    #[test]
+    fn usage_shows_every_option_as_the_parser_takes_it() {
+        // Options show up as `--key <value>` in the commands and on lines of
+        // their own, but not in the prose around them.
+        let mut shown = HashMap::new();
+        for line in USAGE.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let words = match words.first() {
+                Some(&"dsp_seed") => &words[..],
+                Some(word) if word.starts_with("--") => &words[..words.len().min(2)],
+                _ => continue,
+            };
+            for (at, word) in words.iter().enumerate() {
+                let key = match word.trim_start_matches(['(', '[']).strip_prefix("--") {
+                    Some(key) => key,
+                    None => continue,
+                };
+                let value = words.get(at + 1).is_some_and(|word| word.starts_with('<'));
+                assert_eq!(
+                    *shown.entry(key).or_insert(value),
+                    value,
+                    "--{} is shown both with and without a value",
+                    key
+                );
+            }
+        }
+        let options: HashMap<&str, bool> = OPTIONS.iter().copied().collect();
+        assert_eq!(shown, options);
+    }
+
+    #[test]
+    fn options_take_values_and_flags_do_not() {
+        let args = parse("find --pin --range -10,10 --planets --threads 4").unwrap();
+        assert_eq!(args.command.as_deref(), Some("find"));
+        assert_eq!(args.range(), Ok((-10, 10)));
+        assert_eq!(args.get::<usize>("threads"), Ok(Some(4)));
+        assert!(args.flag("pin") && args.flag("planets"));
+        assert!(!args.flag("ordered"));
+        assert_eq!(args.get::<i32>("seed"), Ok(None));
+
+        // A flag before the command doesn't take it as its value.
+        let args = parse("--pin worker --connect host:1").unwrap();
+        assert_eq!(args.command.as_deref(), Some("worker"));
+        assert_eq!(args.require::<String>("connect"), Ok("host:1".to_owned()));
+        assert_eq!(parse("").unwrap().command, None);
+    }
+
+    #[test]
+    fn unknown_options_and_extra_arguments_are_refused() {
+        assert_eq!(
+            parse("find --rnage 0,10").err(),
+            Some("Unknown option: --rnage".to_owned())
+        );
+        assert_eq!(
+            parse("svg --help").err(),
+            Some("Unknown option: --help".to_owned())
+        );
+        assert_eq!(
+            parse("find 0 10").err(),
+            Some("Unexpected argument: 0".to_owned())
+        );
+        // `-5` is a value, not an option.
+        assert_eq!(parse("find --nice -5").unwrap().get("nice"), Ok(Some(-5)));
+    }
+
+    #[test]
+    fn options_without_their_value_are_refused() {
+        assert_eq!(
+            parse("svg --seed").err(),
+            Some("Missing value for --seed".to_owned())
+        );
+        assert_eq!(
+            parse("find --range --planets").err(),
+            Some("Missing value for --range".to_owned())
+        );
+        assert_eq!(
+            parse("svg").unwrap().require::<i32>("seed"),
+            Err("Missing required option --seed".to_owned())
+        );
+        assert_eq!(
+            parse("stats").unwrap().range(),
+            Err("--range expects <start,end> with start < end".to_owned())
+        );
+    }
+
+    #[test]
+    fn invalid_numbers_are_refused() {
+        let args = parse("svg --seed 12x --size -1 --stars 1e3 --resource fast").unwrap();
+        assert_eq!(
+            args.require::<i32>("seed"),
+            Err("Invalid value for --seed: 12x".to_owned())
+        );
+        assert_eq!(
+            args.get::<u32>("size"),
+            Err("Invalid value for --size: -1".to_owned())
+        );
+        assert_eq!(
+            args.game_desc(0).err(),
+            Some("Invalid value for --stars: 1e3".to_owned())
+        );
+        assert_eq!(
+            parse("svg --seed 99999999999")
+                .unwrap()
+                .require::<i32>("seed"),
+            Err("Invalid value for --seed: 99999999999".to_owned())
+        );
+
+        let range = |value: &str| parse(&format!("find --range {}", value)).unwrap().range();
+        assert_eq!(range("0,x"), Err("Invalid value for --range: x".to_owned()));
+        assert!(range("5,5").is_err());
+        assert!(range("5,1").is_err());
+        assert!(range("1,2,3").is_err());
+
+        assert_eq!(
+            parse("worker --nice 20").unwrap().workers(),
+            Err("Invalid value for --nice: 20".to_owned())
+        );
+        assert_eq!(
+            parse("coordinator --chunk 0")
+                .unwrap()
+                .coordinator_options()
+                .err(),
+            Some("Invalid value for --chunk: 0".to_owned())
+        );
+        assert_eq!(
+            parse("find --range 0,10 --shard 3/2")
+                .unwrap()
+                .seed_selection(),
+            Err("Invalid value for --shard: 3/2".to_owned())
+        );
+    }
+
+    #[test]
+    fn seed_lists_win_over_ranges() {
+        let args = parse("find --seeds 7,3 --range 0,10 --shard 2/2").unwrap();
+        let seeds = args.seed_selection().unwrap();
+        assert_eq!(seeds.iter().collect::<Vec<_>>(), [3]);
+        let args = parse("find --range 0,10 --step 3").unwrap();
+        let seeds = args.seed_selection().unwrap();
+        assert_eq!(seeds.iter().collect::<Vec<_>>(), [0, 3, 6, 9]);
+    }
+}
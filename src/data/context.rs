@@ -0,0 +1,66 @@
+use super::theme_proto::ThemeIdSet;
+use std::cell::{Cell, Ref, RefCell};
+
+/// Mutable state shared by everything generated for one galaxy. The game
+/// threads it through generation implicitly, so results depend on the order
+/// stars and planets are generated in, but never on earlier galaxies.
+#[derive(Debug, Default)]
+pub struct GenerationContext {
+    habitable_count: Cell<i32>,
+    used_theme_ids: Vec<Cell<ThemeIdSet>>,
+    names: RefCell<Vec<String>>,
+}
+
+impl GenerationContext {
+    pub fn new(star_count: usize) -> Self {
+        Self {
+            habitable_count: Cell::new(0),
+            used_theme_ids: vec![Cell::default(); star_count],
+            names: RefCell::new(vec![]),
+        }
+    }
+
+    /// Stars this context has room for.
+    pub fn star_capacity(&self) -> usize {
+        self.used_theme_ids.len()
+    }
+
+    /// Forgets everything generated, so the context can be used for another
+    /// galaxy without allocating again.
+    pub fn reset(&self) {
+        self.habitable_count.set(0);
+        for used in &self.used_theme_ids {
+            used.take();
+        }
+        self.names.borrow_mut().clear();
+    }
+
+    pub fn habitable_count(&self) -> i32 {
+        self.habitable_count.get()
+    }
+
+    pub fn increment_habitable_count(&self) {
+        self.habitable_count.set(self.habitable_count.get() + 1);
+    }
+
+    /// Themes already taken by planets of the star.
+    pub fn used_theme_ids(&self, star_index: usize) -> ThemeIdSet {
+        self.used_theme_ids[star_index].get()
+    }
+
+    pub fn use_theme_id(&self, star_index: usize, id: i32) {
+        let used = &self.used_theme_ids[star_index];
+        let mut ids = used.get();
+        ids.insert(id);
+        used.set(ids);
+    }
+
+    /// Names of the stars named so far, in order.
+    pub fn names(&self) -> Ref<'_, Vec<String>> {
+        self.names.borrow()
+    }
+
+    pub fn add_name(&self, name: String) {
+        self.names.borrow_mut().push(name);
+    }
+}
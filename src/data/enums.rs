@@ -11,6 +11,7 @@ pub enum StarType {
     BlackHole,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for StarType {
     fn default() -> Self {
         Self::MainSeqStar
@@ -43,6 +44,7 @@ pub enum PlanetType {
     Gas,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for PlanetType {
     fn default() -> Self {
         Self::None
@@ -59,6 +61,7 @@ pub enum ThemeDistribute {
     Rare,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for ThemeDistribute {
     fn default() -> Self {
         Self::Default
@@ -87,6 +90,7 @@ pub enum VeinType {
     Max,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for VeinType {
     fn default() -> Self {
         Self::None
@@ -106,3 +110,97 @@ impl VeinType {
         )
     }
 }
+
+/// An item id in rule JSON, either the number the game uses or its name.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum ItemRef {
+    Id(i32),
+    Name(String),
+}
+
+/// Items collected from gas giants. Serialized by name; the game's item id is
+/// also accepted when deserializing.
+#[repr(i32)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "ItemRef")]
+pub enum GasType {
+    Fireice = 1011,
+    Hydrogen = 1120,
+    Deuterium = 1121,
+}
+
+impl GasType {
+    pub fn id(&self) -> i32 {
+        *self as i32
+    }
+}
+
+impl TryFrom<ItemRef> for GasType {
+    type Error = String;
+
+    fn try_from(item: ItemRef) -> Result<Self, Self::Error> {
+        match item {
+            ItemRef::Id(1011) => Ok(GasType::Fireice),
+            ItemRef::Id(1120) => Ok(GasType::Hydrogen),
+            ItemRef::Id(1121) => Ok(GasType::Deuterium),
+            ItemRef::Name(name) => match name.as_str() {
+                "Fireice" => Ok(GasType::Fireice),
+                "Hydrogen" => Ok(GasType::Hydrogen),
+                "Deuterium" => Ok(GasType::Deuterium),
+                _ => Err(format!("Unknown gas: {}", name)),
+            },
+            ItemRef::Id(id) => Err(format!("Unknown gas item id: {}", id)),
+        }
+    }
+}
+
+/// What the oceans of a planet are made of. Water and sulfuric acid are the
+/// items pumped from them; ice and lava cannot be pumped. Serialized by name;
+/// the game's item id is also accepted when deserializing.
+#[repr(i32)]
+#[derive(
+    Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize,
+)]
+#[serde(try_from = "ItemRef")]
+pub enum OceanType {
+    Ice = -2,
+    Lava = -1,
+    #[default]
+    None = 0,
+    Water = 1000,
+    Sulfur = 1116,
+}
+
+impl OceanType {
+    pub fn id(&self) -> i32 {
+        *self as i32
+    }
+
+    pub fn is_pumpable(&self) -> bool {
+        matches!(self, OceanType::Water | OceanType::Sulfur)
+    }
+}
+
+impl TryFrom<ItemRef> for OceanType {
+    type Error = String;
+
+    fn try_from(item: ItemRef) -> Result<Self, Self::Error> {
+        match item {
+            ItemRef::Id(-2) => Ok(OceanType::Ice),
+            ItemRef::Id(-1) => Ok(OceanType::Lava),
+            ItemRef::Id(0) => Ok(OceanType::None),
+            ItemRef::Id(1000) => Ok(OceanType::Water),
+            ItemRef::Id(1116) => Ok(OceanType::Sulfur),
+            ItemRef::Name(name) => match name.as_str() {
+                "Ice" => Ok(OceanType::Ice),
+                "Lava" => Ok(OceanType::Lava),
+                "None" => Ok(OceanType::None),
+                "Water" => Ok(OceanType::Water),
+                "Sulfur" => Ok(OceanType::Sulfur),
+                _ => Err(format!("Unknown ocean type: {}", name)),
+            },
+            ItemRef::Id(id) => Err(format!("Unknown ocean item id: {}", id)),
+        }
+    }
+}
@@ -1,4 +1,7 @@
+use super::enums::StarType;
 use super::star_planets::StarWithPlanets;
+use super::vector3::Vector3;
+use crate::worldgen::name_gen::star_name;
 use serde::Serialize;
 
 #[derive(Debug, Serialize)]
@@ -6,3 +9,17 @@ pub struct Galaxy<'a> {
     pub seed: i32,
     pub stars: Vec<StarWithPlanets<'a>>,
 }
+
+impl Galaxy<'_> {
+    pub fn get_star_name(&self, index: usize) -> String {
+        star_name(&self.stars, index)
+    }
+}
+
+/// What is known of a galaxy once its stars are placed, before any of them is
+/// generated: the position of each star and the type the game gives it.
+#[derive(Debug, Clone, Copy)]
+pub struct GalaxyLayout<'a> {
+    pub positions: &'a [Vector3],
+    pub star_types: &'a [StarType],
+}
@@ -1,5 +1,3 @@
-use std::cell::Cell;
-
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,8 +9,6 @@ pub struct GameDesc {
     pub star_count: usize,
     #[serde(default = "GameDesc::default_resource_multiplier")]
     pub resource_multiplier: f32,
-    #[serde(skip)]
-    pub habitable_count: Cell<i32>,
 }
 
 impl GameDesc {
@@ -23,6 +19,14 @@ impl GameDesc {
         1.0
     }
 
+    /// Same settings for another seed.
+    pub fn with_seed(&self, seed: i32) -> Self {
+        Self {
+            seed,
+            ..self.clone()
+        }
+    }
+
     pub fn is_infinite_resource(&self) -> bool {
         self.resource_multiplier >= 99.5
     }
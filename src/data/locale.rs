@@ -0,0 +1,266 @@
+use super::enums::{GasType, OceanType, SpectrType, StarType, VeinType};
+use super::star::Star;
+use super::theme_proto::ThemeProto;
+use serde::{Deserialize, Serialize};
+
+/// Language of the names shown to players. The strings are the ones the game
+/// itself uses, so reports can be compared with what is seen in game.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Locale {
+    #[default]
+    En,
+    Zh,
+}
+
+impl std::str::FromStr for Locale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "en" | "en-us" => Ok(Locale::En),
+            "zh" | "zh-cn" | "zh-hans" => Ok(Locale::Zh),
+            _ => Err(format!("Unknown locale: {}", s)),
+        }
+    }
+}
+
+/// Name of a game value as shown in game.
+pub trait Localize {
+    fn localized(&self, locale: Locale) -> &'static str;
+}
+
+fn pick(locale: Locale, en: &'static str, zh: &'static str) -> &'static str {
+    match locale {
+        Locale::En => en,
+        Locale::Zh => zh,
+    }
+}
+
+impl Localize for VeinType {
+    fn localized(&self, locale: Locale) -> &'static str {
+        match self {
+            VeinType::None | VeinType::Max => "",
+            VeinType::Iron => pick(locale, "Iron Ore", "铁矿"),
+            VeinType::Copper => pick(locale, "Copper Ore", "铜矿"),
+            VeinType::Silicium => pick(locale, "Silicon Ore", "硅石"),
+            VeinType::Titanium => pick(locale, "Titanium Ore", "钛石"),
+            VeinType::Stone => pick(locale, "Stone", "石矿"),
+            VeinType::Coal => pick(locale, "Coal", "煤矿"),
+            VeinType::Oil => pick(locale, "Crude Oil", "原油"),
+            VeinType::Fireice => pick(locale, "Fire Ice", "可燃冰"),
+            VeinType::Diamond => pick(locale, "Kimberlite Ore", "金伯利矿石"),
+            VeinType::Fractal => pick(locale, "Fractal Silicon", "分形硅石"),
+            VeinType::Crysrub => pick(locale, "Organic Crystal", "有机晶体"),
+            VeinType::Grat => pick(locale, "Grating Crystal", "光栅石"),
+            VeinType::Bamboo => pick(locale, "Stalagmite Crystal", "刺笋结晶"),
+            VeinType::Mag => pick(locale, "Unipolar Magnet", "单极磁石"),
+        }
+    }
+}
+
+impl Localize for GasType {
+    fn localized(&self, locale: Locale) -> &'static str {
+        match self {
+            GasType::Fireice => pick(locale, "Fire Ice", "可燃冰"),
+            GasType::Hydrogen => pick(locale, "Hydrogen", "氢"),
+            GasType::Deuterium => pick(locale, "Deuterium", "重氢"),
+        }
+    }
+}
+
+impl Localize for OceanType {
+    fn localized(&self, locale: Locale) -> &'static str {
+        match self {
+            OceanType::None => pick(locale, "None", "无"),
+            OceanType::Ice => pick(locale, "Ice", "冰"),
+            OceanType::Lava => pick(locale, "Lava", "熔岩"),
+            OceanType::Water => pick(locale, "Water", "水"),
+            OceanType::Sulfur => pick(locale, "Sulfuric Acid", "硫酸"),
+        }
+    }
+}
+
+impl Localize for SpectrType {
+    fn localized(&self, locale: Locale) -> &'static str {
+        match self {
+            SpectrType::M => pick(locale, "M type star", "M型恒星"),
+            SpectrType::K => pick(locale, "K type star", "K型恒星"),
+            SpectrType::G => pick(locale, "G type star", "G型恒星"),
+            SpectrType::F => pick(locale, "F type star", "F型恒星"),
+            SpectrType::A => pick(locale, "A type star", "A型恒星"),
+            SpectrType::B => pick(locale, "B type star", "B型恒星"),
+            SpectrType::O => pick(locale, "O type star", "O型恒星"),
+            SpectrType::X => pick(locale, "X type star", "X型恒星"),
+        }
+    }
+}
+
+impl Localize for StarType {
+    fn localized(&self, locale: Locale) -> &'static str {
+        match self {
+            StarType::MainSeqStar => pick(locale, "Main Sequence Star", "主序星"),
+            StarType::GiantStar => pick(locale, "Giant Star", "巨星"),
+            StarType::WhiteDwarf => pick(locale, "White Dwarf", "白矮星"),
+            StarType::NeutronStar => pick(locale, "Neutron Star", "中子星"),
+            StarType::BlackHole => pick(locale, "Black Hole", "黑洞"),
+        }
+    }
+}
+
+/// What the game calls the star, e.g. "Blue Giant" or "G type star".
+pub fn star_kind_name(star_type: &StarType, spectr: &SpectrType, locale: Locale) -> &'static str {
+    match star_type {
+        StarType::MainSeqStar => spectr.localized(locale),
+        StarType::GiantStar => match spectr {
+            SpectrType::M | SpectrType::K => pick(locale, "Red Giant", "红巨星"),
+            SpectrType::G | SpectrType::F => pick(locale, "Yellow Giant", "黄巨星"),
+            SpectrType::A => pick(locale, "White Giant", "白巨星"),
+            _ => pick(locale, "Blue Giant", "蓝巨星"),
+        },
+        _ => star_type.localized(locale),
+    }
+}
+
+impl Star<'_> {
+    pub fn kind_name(&self, locale: Locale) -> &'static str {
+        star_kind_name(&self.star_type, &self.get_spectr(), locale)
+    }
+}
+
+/// Words of the text reports. Values go in place of `{0}`, `{1}`, ..., see
+/// `fill`, as their order differs between languages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Label {
+    MaxLuminosity,
+    MaxDysonRadius,
+    StarsWith,
+    MaxAverage,
+    Nearest,
+    SpectrWithin,
+    XStarScore,
+    Galaxies,
+    NoValues,
+    Distribution,
+    HitRate,
+    GalaxySummary,
+    Differs,
+    StarSummary,
+    XStarSummary,
+    XStar,
+    LightYears,
+    /// Names of the differences of `ComparisonRow`, by their JSON key.
+    Difference(&'static str),
+}
+
+impl Localize for Label {
+    fn localized(&self, locale: Locale) -> &'static str {
+        match self {
+            Label::MaxLuminosity => pick(locale, "Max luminosity", "最大光度"),
+            Label::MaxDysonRadius => pick(locale, "Max Dyson radius", "最大戴森球半径"),
+            Label::StarsWith => pick(locale, "Stars with {0}", "有{0}的恒星"),
+            Label::MaxAverage => pick(locale, "Max average {0}", "最大平均{0}"),
+            Label::Nearest => pick(locale, "Nearest {0}", "最近的{0}"),
+            Label::SpectrWithin => pick(locale, "{0} within {1} ly", "{1} 光年内的{0}"),
+            Label::XStarScore => pick(locale, "X star score", "X 星评分"),
+            Label::Galaxies => pick(locale, "Galaxies: {0}", "星系：{0}"),
+            Label::NoValues => pick(locale, "no values ({0} missing)", "无数值（缺失 {0}）"),
+            Label::Distribution => pick(
+                locale,
+                "count {0}, missing {1}, min {2}, mean {3}, max {4}",
+                "数量 {0}，缺失 {1}，最小 {2}，平均 {3}，最大 {4}",
+            ),
+            Label::HitRate => pick(
+                locale,
+                "Rule hit rate: {0} / {1} = {2}% ({3}% CI {4}% - {5}%)",
+                "规则命中率：{0} / {1} = {2}%（{3}% 置信区间 {4}% - {5}%）",
+            ),
+            Label::GalaxySummary => pick(
+                locale,
+                "Seed {0}: {1} stars, {2} O/B within {3} ly, {4} tidally locked, max Dyson radius {5}, rare veins [{6}]",
+                "种子 {0}：{1} 颗恒星，{3} 光年内 {2} 颗 O/B，{4} 颗潮汐锁定，最大戴森球半径 {5}，稀有矿脉 [{6}]",
+            ),
+            Label::Differs => pick(locale, "{0} (differs: {1})", "{0}（不同：{1}）"),
+            Label::StarSummary => pick(
+                locale,
+                "{0}: {1} {2} {3} ly, luminosity {4}, Dyson radius {5}, {6} planets ({7} tidally locked), rare veins [{8}], oceans [{9}], gases [{10}]",
+                "{0}：{1} {2} {3} 光年，光度 {4}，戴森球半径 {5}，{6} 颗行星（{7} 颗潮汐锁定），稀有矿脉 [{8}]，海洋 [{9}]，气体 [{10}]",
+            ),
+            Label::XStarSummary => pick(
+                locale,
+                "Seed {0}: {1} X stars and white dwarfs",
+                "种子 {0}：{1} 颗 X 星和白矮星",
+            ),
+            Label::XStar => pick(
+                locale,
+                "{0} {1} ({2}) {3}: {4}, {5} from birth, nearest blue giant {6}",
+                "{0} {1} ({2}) {3}：{4}，距出生点 {5}，最近的蓝巨星 {6}",
+            ),
+            Label::LightYears => pick(locale, "{0} ly", "{0} 光年"),
+            // The keys themselves in English, as in JSON.
+            Label::Difference(key) => match (locale, *key) {
+                (Locale::En, _) => key,
+                (Locale::Zh, "missing") => "缺失",
+                (Locale::Zh, "starType") => "恒星类型",
+                (Locale::Zh, "spectr") => "光谱",
+                (Locale::Zh, "luminosity") => "光度",
+                (Locale::Zh, "dysonRadius") => "戴森球半径",
+                (Locale::Zh, "planetCount") => "行星数",
+                (Locale::Zh, "tidalLocked") => "潮汐锁定",
+                (Locale::Zh, "rareVeins") => "稀有矿脉",
+                (Locale::Zh, "oceanTypes") => "海洋",
+                (Locale::Zh, "gasRates") => "气体",
+                (Locale::Zh, _) => key,
+            },
+        }
+    }
+}
+
+/// `label` in `locale`, with `values` in place of `{0}`, `{1}`, ...
+pub fn fill(label: Label, locale: Locale, values: &[&dyn std::fmt::Display]) -> String {
+    let mut text = label.localized(locale).to_owned();
+    for (n, value) in values.iter().enumerate() {
+        text = text.replace(&format!("{{{}}}", n), &value.to_string());
+    }
+    text
+}
+
+/// Theme names by theme id, as `(English, Chinese)`.
+const THEME_NAMES: &[(&str, &str)] = &[
+    ("", ""),
+    ("Mariterra", "地中海"),
+    ("Gas Giant", "气态巨星"),
+    ("Gas Giant", "气态巨星"),
+    ("Ice Giant", "冰巨星"),
+    ("Ice Giant", "冰巨星"),
+    ("Scorchedia", "干旱荒漠"),
+    ("Geloterra", "灰烬冻土"),
+    ("Tropicana", "海洋丛林"),
+    ("Lava", "熔岩"),
+    ("Glacieon", "冰原冻土"),
+    ("Desolus", "贫瘠荒漠"),
+    ("Gobi", "戈壁"),
+    ("Sulfuria", "火山灰"),
+    ("Crimsonis", "红石"),
+    ("Prairiea", "草原"),
+    ("Aquatica", "水世界"),
+    ("Halitum", "黑石盐滩"),
+    ("Sakura Ocean", "樱林海"),
+    ("Cyclonius", "飓风石林"),
+    ("Maroonfrost", "猩红冰湖"),
+    ("Gas Giant", "气态巨星"),
+    ("Savanna", "热带草原"),
+    ("Onyxtopia", "橙晶荒漠"),
+    ("Icefrostia", "极寒冻土"),
+    ("Pandora Swamp", "潘多拉沼泽"),
+];
+
+impl Localize for ThemeProto {
+    /// `name` is the internal name of the theme; this is the one shown in game.
+    fn localized(&self, locale: Locale) -> &'static str {
+        match THEME_NAMES.get(self.id as usize) {
+            Some((en, zh)) => pick(locale, en, zh),
+            None => self.name,
+        }
+    }
+}
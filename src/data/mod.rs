@@ -1,6 +1,8 @@
+pub mod context;
 pub mod enums;
 pub mod galaxy;
 pub mod game_desc;
+pub mod locale;
 pub mod planet;
 pub mod random;
 pub mod rule;
@@ -1,7 +1,7 @@
-use super::enums::{PlanetType, SpectrType, StarType, ThemeDistribute, VeinType};
+use super::enums::{GasType, PlanetType, SpectrType, StarType, ThemeDistribute, VeinType};
 use super::random::DspRandom;
 use super::star::Star;
-use super::theme_proto::{ThemeProto, THEME_PROTOS};
+use super::theme_proto::{ThemeProto, THEME_CANDIDATES};
 use super::vein::Vein;
 use serde::ser::{Serialize, SerializeStruct, Serializer};
 use std::cell::{OnceCell, RefCell};
@@ -46,14 +46,38 @@ pub struct Planet<'a> {
     eligible_for_resonance: OnceCell<bool>,
     rotation_period: OnceCell<f64>,
     theme: OnceCell<&'static ThemeProto>,
-    gases: OnceCell<Vec<(i32, f32)>>,
+    gases: OnceCell<Vec<(GasType, f32)>>,
     veins: OnceCell<Vec<Vein>>,
+    // Memory of the gases and veins of the planet generated before in its place.
+    spare_gases: RefCell<Vec<(GasType, f32)>>,
+    spare_veins: RefCell<Vec<Vein>>,
 }
 
+/// Largest habitable bias at which generation can still make a planet habitable.
+pub const HABITABLE_ZONE_MAX_BIAS: f32 = 0.8;
+
 const ORBIT_RADIUS: &[f32] = &[
     0.0, 0.4, 0.7, 1.0, 1.4, 1.9, 2.5, 3.3, 4.3, 5.5, 6.9, 8.4, 10.0, 11.7, 13.5, 15.4, 17.5,
 ];
 
+/// Picks among the `candidates` passing `filter` as the game picks from the
+/// list of them, without building the list.
+fn pick_theme(
+    candidates: &[&'static ThemeProto],
+    filter: impl Fn(&ThemeProto) -> bool,
+    rand: f64,
+) -> Option<&'static ThemeProto> {
+    let count = candidates.iter().filter(|theme| filter(theme)).count();
+    if count == 0 {
+        return None;
+    }
+    candidates
+        .iter()
+        .filter(|theme| filter(theme))
+        .nth(((rand * (count as f64)) as usize) % count)
+        .copied()
+}
+
 impl<'a> Planet<'a> {
     pub fn new(
         star: Rc<Star<'a>>,
@@ -131,9 +155,28 @@ impl<'a> Planet<'a> {
             theme: OnceCell::new(),
             gases: OnceCell::new(),
             veins: OnceCell::new(),
+            spare_gases: RefCell::default(),
+            spare_veins: RefCell::default(),
         }
     }
 
+    /// Replaces this planet by `planet`, keeping the memory of its gases and
+    /// veins for the new ones.
+    pub(crate) fn recycle(&mut self, planet: Planet<'a>) {
+        let gases = self.gases.take().unwrap_or_else(|| self.spare_gases.take());
+        let veins = self.veins.take().unwrap_or_else(|| self.spare_veins.take());
+        *self = planet;
+        *self.spare_gases.get_mut() = gases;
+        *self.spare_veins.get_mut() = veins;
+    }
+
+    /// Lets go of the star and the planet orbited, so the star can be reused
+    /// while this planet waits to be recycled.
+    pub(crate) fn release(&mut self, placeholder: &Rc<Star<'a>>) {
+        self.star = placeholder.clone();
+        self.orbit_around = None;
+    }
+
     pub fn real_radius(&self) -> f32 {
         self.radius * self.scale
     }
@@ -177,6 +220,11 @@ impl<'a> Planet<'a> {
         })
     }
 
+    /// Gas giants are never in the habitable zone.
+    pub fn is_in_habitable_zone(&self) -> bool {
+        self.get_habitable_bias() < HABITABLE_ZONE_MAX_BIAS
+    }
+
     pub fn get_temperature_factor(&self) -> f32 {
         *self.temperature_factor.get_or_init(|| {
             if self.is_gas_giant() {
@@ -233,26 +281,19 @@ impl<'a> Planet<'a> {
         })
     }
 
-    fn increment_habitable_count(&self) {
-        self.star
-            .game_desc
-            .habitable_count
-            .set(self.star.game_desc.habitable_count.get() + 1);
-    }
-
     pub fn get_unmodified_planet_type(&self) -> &PlanetType {
         self.unmodified_planet_type.get_or_init(|| {
             if self.is_gas_giant() {
                 PlanetType::Gas
             } else if self.is_birth() {
-                self.increment_habitable_count();
+                self.star.context.increment_habitable_count();
                 PlanetType::Ocean
             } else {
                 let f2 = self.get_temperature_factor();
                 if !self.star.is_birth() {
                     let star_count = self.star.game_desc.star_count;
                     let num18 = ((star_count as f32) * 0.29).ceil().max(11.0);
-                    let num19 = (num18 as f64) - (self.star.game_desc.habitable_count.get() as f64);
+                    let num19 = (num18 as f64) - (self.star.context.habitable_count() as f64);
                     let num20 = (star_count - self.star.index) as f32;
                     let num23 = num20 as f64;
                     let a = (num19 / num23) as f32;
@@ -261,7 +302,7 @@ impl<'a> Planet<'a> {
                         .clamp(0.0, 1.1)
                         .powf(num24 * 10.0);
                     if self.habitable_factor > (num25 as f64) {
-                        self.increment_habitable_count();
+                        self.star.context.increment_habitable_count();
                         return PlanetType::Ocean;
                     }
                 }
@@ -290,6 +331,20 @@ impl<'a> Planet<'a> {
         self.get_rotation_period() == self.get_orbital_period()
     }
 
+    pub fn is_retrograde(&self) -> bool {
+        self.get_rotation_period() < 0.0
+    }
+
+    /// Rotations per orbit (1, 2 or 4) if the rotation is locked to the orbit.
+    pub fn get_resonance(&self) -> Option<u32> {
+        let rotation_period = self.get_rotation_period();
+        let orbital_period = self.get_orbital_period();
+        [(1, 1.0), (2, 0.5), (4, 0.25)]
+            .into_iter()
+            .find(|(_, factor)| orbital_period * factor == rotation_period)
+            .map(|(rotations, _)| rotations)
+    }
+
     pub fn get_orbit_inclination(&self) -> f32 {
         *self.orbit_inclination.get_or_init(|| {
             let mut orbit_inclination = (self.orbit_inclination_factor * 16.0 - 8.0) as f32;
@@ -409,58 +464,39 @@ impl<'a> Planet<'a> {
 
     pub fn get_theme(&self) -> &'static ThemeProto {
         self.theme.get_or_init(|| {
-            let mut potential_themes: Vec<&'static ThemeProto> = Vec::new();
-            let mut used_theme_ids = self.star.used_theme_ids.borrow_mut();
-            let unused_themes: Vec<&'static ThemeProto> = THEME_PROTOS
-                .iter()
-                .filter(|&theme| !used_theme_ids.contains(&theme.id))
-                .collect();
+            let used_theme_ids = self.star.context.used_theme_ids(self.star.index);
+            let unused = |theme: &ThemeProto| !used_theme_ids.contains(theme.id);
             let planet_type = self.get_unmodified_planet_type();
             let temperature_bias = self.get_temperature_bias();
-            for theme in &unused_themes {
-                if self.star.is_birth() && planet_type == &PlanetType::Ocean {
-                    if theme.distribute == ThemeDistribute::Birth {
-                        potential_themes.push(theme);
-                    }
+            let fits_temperature = |theme: &ThemeProto| {
+                if theme.temperature.abs() < 0.5 && theme.planet_type == PlanetType::Desert {
+                    (temperature_bias.abs() as f64) < (theme.temperature.abs() as f64) + 0.1
                 } else {
-                    let flag2 = if theme.temperature.abs() < 0.5
-                        && theme.planet_type == PlanetType::Desert
-                    {
-                        (temperature_bias.abs() as f64) < (theme.temperature.abs() as f64) + 0.1
-                    } else {
-                        (theme.temperature as f64) * (temperature_bias as f64) >= -0.1
-                    };
-                    if (theme.planet_type == *planet_type) && flag2 {
-                        if self.star.is_birth() {
-                            if theme.distribute == ThemeDistribute::Default {
-                                potential_themes.push(theme);
-                            }
-                        } else if theme.distribute == ThemeDistribute::Default
-                            || theme.distribute == ThemeDistribute::Interstellar
-                        {
-                            potential_themes.push(theme);
-                        }
-                    }
+                    (theme.temperature as f64) * (temperature_bias as f64) >= -0.1
                 }
-            }
-            if potential_themes.is_empty() {
-                for theme in &unused_themes {
-                    if theme.planet_type == PlanetType::Desert {
-                        potential_themes.push(theme);
-                    }
-                }
-            }
-            if potential_themes.is_empty() {
-                for theme in &*THEME_PROTOS {
-                    if theme.planet_type == PlanetType::Desert {
-                        potential_themes.push(theme);
-                    }
-                }
-            }
-            let theme_proto = potential_themes[((self.theme_rand1 * (potential_themes.len() as f64))
-                as usize)
-                % potential_themes.len()];
-            used_theme_ids.push(theme_proto.id);
+            };
+            let candidates = &*THEME_CANDIDATES;
+            let potential_themes = if self.star.is_birth() && planet_type == &PlanetType::Ocean {
+                pick_theme(&candidates.birth, unused, self.theme_rand1)
+            } else {
+                let by_type = if self.star.is_birth() {
+                    &candidates.birth_star
+                } else {
+                    &candidates.other_stars
+                };
+                pick_theme(
+                    &by_type[planet_type.clone() as usize],
+                    |theme| unused(theme) && fits_temperature(theme),
+                    self.theme_rand1,
+                )
+            };
+            let theme_proto = potential_themes
+                .or_else(|| pick_theme(&candidates.desert, unused, self.theme_rand1))
+                .or_else(|| pick_theme(&candidates.desert, |_| true, self.theme_rand1))
+                .expect("there are desert themes");
+            self.star
+                .context
+                .use_theme_id(self.star.index, theme_proto.id);
             theme_proto
         })
     }
@@ -468,9 +504,10 @@ impl<'a> Planet<'a> {
     pub fn get_type(&self) -> &PlanetType {
         &self.get_theme().planet_type
     }
-    pub fn get_gases(&self) -> &Vec<(i32, f32)> {
+    pub fn get_gases(&self) -> &Vec<(GasType, f32)> {
         self.gases.get_or_init(|| {
-            let mut gases: Vec<(i32, f32)> = vec![];
+            let mut gases = self.spare_gases.take();
+            gases.clear();
             if !self.is_gas_giant() {
                 return gases;
             }
@@ -492,7 +529,8 @@ impl<'a> Planet<'a> {
 
     pub fn get_veins(&self) -> &Vec<Vein> {
         self.veins.get_or_init(|| {
-            let mut output: Vec<Vein> = vec![];
+            let mut output = self.spare_veins.take();
+            output.clear();
             if self.is_gas_giant() {
                 return output;
             }
@@ -504,20 +542,24 @@ impl<'a> Planet<'a> {
             rand1.next_f64();
             rand1.next_f64();
             let theme_proto = self.get_theme();
-            let mut num_array_1: Vec<i32> = (0..15_i32)
-                .map(|i| *theme_proto.vein_spot.get((i - 1) as usize).unwrap_or(&0))
-                .collect();
-            let mut num_array_2: Vec<f32> = (0..15_i32)
-                .map(|i| *theme_proto.vein_count.get((i - 1) as usize).unwrap_or(&0.0))
-                .collect();
-            let mut num_array_3: Vec<f32> = (0..15_i32)
-                .map(|i| {
-                    *theme_proto
-                        .vein_opacity
-                        .get((i - 1) as usize)
-                        .unwrap_or(&0.0)
-                })
-                .collect();
+            let mut num_array_1: [i32; 15] = std::array::from_fn(|i| {
+                *theme_proto
+                    .vein_spot
+                    .get((i as i32 - 1) as usize)
+                    .unwrap_or(&0)
+            });
+            let mut num_array_2: [f32; 15] = std::array::from_fn(|i| {
+                *theme_proto
+                    .vein_count
+                    .get((i as i32 - 1) as usize)
+                    .unwrap_or(&0.0)
+            });
+            let mut num_array_3: [f32; 15] = std::array::from_fn(|i| {
+                *theme_proto
+                    .vein_opacity
+                    .get((i as i32 - 1) as usize)
+                    .unwrap_or(&0.0)
+            });
             let mut add_until = |i: &mut i32, t: f64| {
                 for _ in 1..12 {
                     if rand1.next_f64() >= t {
@@ -93,3 +93,84 @@ impl DspRandom {
         (self.sample() * (i32::MAX as f64)) as i32
     }
 }
+
+/// `N` independent `DspRandom` streams advanced in lockstep. Each row of the
+/// seed array holds one value per stream, so every step is the same
+/// operation on `N` lanes, which the compiler turns into SIMD. Lane `i` yields
+/// exactly what `DspRandom::new(seeds[i])` does.
+pub struct DspRandomBatch<const N: usize> {
+    inext: usize,
+    inextp: usize,
+    seed_array: [[i32; N]; 56],
+}
+
+#[inline(always)]
+fn sub_wrapping_max<const N: usize>(lhs: &mut [i32; N], rhs: &[i32; N]) {
+    for (l, r) in lhs.iter_mut().zip(rhs) {
+        *l = l.wrapping_sub(*r);
+        if l.is_negative() {
+            *l += i32::MAX;
+        }
+    }
+}
+
+impl<const N: usize> DspRandomBatch<N> {
+    pub fn new(seeds: [i32; N]) -> Self {
+        let mut seed_array = [[0; N]; 56];
+        let mut num1 = seeds.map(|seed| 161803398 - seed.abs());
+        seed_array[55] = num1;
+        let mut num2 = [1; N];
+        for index1 in 1..55 {
+            let index2 = (21 * index1) % 55;
+            seed_array[index2] = num2;
+            for lane in 0..N {
+                num2[lane] = num1[lane] - num2[lane];
+                if num2[lane] < 0 {
+                    num2[lane] += i32::MAX;
+                }
+            }
+            num1 = seed_array[index2];
+        }
+        for _ in 1..5 {
+            for index in 1..56 {
+                let other = seed_array[1 + (index + 30) % 55];
+                sub_wrapping_max(&mut seed_array[index], &other);
+            }
+        }
+        Self {
+            inext: 0,
+            inextp: 31,
+            seed_array,
+        }
+    }
+
+    fn sample(&mut self) -> [f64; N] {
+        self.inext += 1;
+        if self.inext >= 56 {
+            self.inext = 1
+        }
+        self.inextp += 1;
+        if self.inextp >= 56 {
+            self.inextp = 1
+        }
+        let other = self.seed_array[self.inextp];
+        let num = &mut self.seed_array[self.inext];
+        sub_wrapping_max(num, &other);
+        num.map(|n| (n as f64) * (1.0 / (i32::MAX as f64)))
+    }
+
+    #[inline]
+    pub fn next_f64(&mut self) -> [f64; N] {
+        self.sample()
+    }
+
+    #[inline]
+    pub fn next_f32(&mut self) -> [f32; N] {
+        self.sample().map(|f| f as f32)
+    }
+
+    #[inline]
+    pub fn next_seed(&mut self) -> [i32; N] {
+        self.sample().map(|f| (f * (i32::MAX as f64)) as i32)
+    }
+}
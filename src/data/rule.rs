@@ -1,4 +1,5 @@
-use super::galaxy::Galaxy;
+use super::galaxy::{Galaxy, GalaxyLayout};
+use super::star_planets::StarWithPlanets;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -12,6 +13,22 @@ pub enum Condition {
     Gte(f32),
     Between(f32, f32),
     NotBetween(f32, f32),
+    /// One of the values, for discrete values such as counts or ids.
+    In(Vec<f32>),
+    NotIn(Vec<f32>),
+    /// Within a tolerance of the value, for measured values like luminosity
+    /// where exact equality is too strict.
+    Approx(f32, f32),
+    /// `Between` with either bound optionally excluded.
+    #[serde(rename_all = "camelCase")]
+    Interval {
+        min: f32,
+        max: f32,
+        #[serde(default)]
+        min_open: bool,
+        #[serde(default)]
+        max_open: bool,
+    },
 }
 
 impl Condition {
@@ -25,6 +42,41 @@ impl Condition {
             Condition::Gte(f) => value >= *f,
             Condition::Between(f1, f2) => *f1 <= value && value <= *f2,
             Condition::NotBetween(f1, f2) => *f1 > value || value > *f2,
+            Condition::In(values) => values.contains(&value),
+            Condition::NotIn(values) => !values.contains(&value),
+            Condition::Approx(f, tolerance) => (value - *f).abs() <= *tolerance,
+            Condition::Interval {
+                min,
+                max,
+                min_open,
+                max_open,
+            } => {
+                (if *min_open {
+                    *min < value
+                } else {
+                    *min <= value
+                }) && (if *max_open {
+                    value < *max
+                } else {
+                    value <= *max
+                })
+            }
+        }
+    }
+
+    /// Pushes to `result` the stars not yet known to `evaluation` whose
+    /// `value` meets the condition, for rules comparing one number per star.
+    pub fn eval_stars(
+        &self,
+        galaxy: &Galaxy,
+        evaluation: &Evaluaton,
+        result: &mut Vec<usize>,
+        value: impl Fn(&StarWithPlanets) -> f32,
+    ) {
+        for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
+            if evaluation.is_unknown(index) && self.eval(value(sp)) {
+                result.push(index)
+            }
         }
     }
 }
@@ -35,17 +87,53 @@ pub trait Rule {
         0
     }
 
-    fn evaluate(&self, galaxy: &Galaxy, evaluation: &Evaluaton) -> Vec<usize> {
+    /// Pushes the indexes of the stars matching the rule to `result`, which
+    /// is empty. Writing to the caller's buffer lets searches evaluate seed
+    /// after seed without allocating.
+    fn evaluate(&self, galaxy: &Galaxy, evaluation: &Evaluaton, result: &mut Vec<usize>) {}
+
+    /// Whether `evaluate_layout` finds exactly the stars `evaluate` would,
+    /// the rule only looking at where stars are and at their types.
+    fn is_layout_decidable(&self) -> bool {
+        false
+    }
+
+    /// Pushes to `result`, which is empty, the indexes of the stars that may
+    /// match the rule judging by `layout` alone, and returns true. Returns
+    /// false when the layout tells nothing about the rule, leaving `result`
+    /// empty. Searches skip generating the stars of galaxies where nothing
+    /// may match.
+    fn evaluate_layout(&self, layout: &GalaxyLayout, result: &mut Vec<usize>) -> bool {
+        false
+    }
+
+    /// Indexes of the planets of `star` this rule is about, so matches can be
+    /// reported per planet. Rules not about single planets return nothing.
+    fn matching_planets(&self, star: &StarWithPlanets) -> Vec<usize> {
         vec![]
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Default)]
 pub struct Evaluaton {
     items: Vec<Option<bool>>,
     max_len: usize,
 }
 
+impl Clone for Evaluaton {
+    fn clone(&self) -> Self {
+        Self {
+            items: self.items.clone(),
+            max_len: self.max_len,
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.items.clone_from(&source.items);
+        self.max_len = source.max_len;
+    }
+}
+
 impl Evaluaton {
     pub fn new(size: usize) -> Self {
         Self {
@@ -54,6 +142,13 @@ impl Evaluaton {
         }
     }
 
+    /// Same as `new`, reusing the memory of this evaluation.
+    pub fn reset(&mut self, size: usize) {
+        self.items.clear();
+        self.items.resize(size, None);
+        self.max_len = size;
+    }
+
     pub fn get_result(&self, index: usize) -> Option<bool> {
         self.items[index]
     }
@@ -66,28 +161,21 @@ impl Evaluaton {
         self.get_result(index).is_some()
     }
 
-    /// Returns the minium number of stars that still needs to be evaluated
-    /// Useful for unsafe rules
+    /// Returns the minimum number of stars that still need to be evaluated:
+    /// stars after the last unknown one are skipped, so rules, unsafe ones in
+    /// particular, only ask the galaxy for the stars they can still change.
     pub fn get_len(&self) -> usize {
         self.max_len
     }
 
     fn load_max_len(&mut self) {
-        let mut x = self.max_len - 1;
-        loop {
-            if self.items[x].is_none() {
-                self.max_len = x + 1;
-                return;
-            }
-            if x == 0 {
-                self.max_len = 0;
-                return;
-            }
-            x -= 1;
-        }
+        self.max_len = self.items[..self.max_len]
+            .iter()
+            .rposition(Option::is_none)
+            .map_or(0, |x| x + 1);
     }
 
-    pub fn accept_many(&mut self, indices: &Vec<usize>) {
+    pub fn accept_many(&mut self, indices: &[usize]) {
         for index in indices {
             let item = self.items.get_mut(*index).unwrap();
             if item.is_none() {
@@ -106,25 +194,52 @@ impl Evaluaton {
         self.load_max_len();
     }
 
-    pub fn collect_known(&self) -> Vec<usize> {
-        self.items
-            .iter()
-            .enumerate()
-            .filter(|(_, &item)| item == Some(true))
-            .map(|(index, _)| index)
-            .collect()
+    pub fn collect_known(&self, result: &mut Vec<usize>) {
+        result.extend(
+            self.items
+                .iter()
+                .enumerate()
+                .filter(|(_, &item)| item == Some(true))
+                .map(|(index, _)| index),
+        )
     }
 
-    pub fn collect_unknown(&self) -> Vec<usize> {
-        self.items
-            .iter()
-            .enumerate()
-            .filter(|(_, &item)| item != Some(false))
-            .map(|(index, _)| index)
-            .collect()
+    pub fn collect_unknown(&self, result: &mut Vec<usize>) {
+        result.extend(
+            self.items
+                .iter()
+                .enumerate()
+                .filter(|(_, &item)| item != Some(false))
+                .map(|(index, _)| index),
+        )
     }
 
     pub fn is_done(&self) -> bool {
         self.max_len == 0
     }
 }
+
+/// Buffers kept by rules combining others, reused from one evaluation to the
+/// next.
+#[derive(Debug, Default)]
+pub struct Scratch {
+    pub evaluation: Evaluaton,
+    pub matches: Vec<usize>,
+}
+
+/// Planets matched by any of `rules`, in order.
+pub fn matching_planets_of_any(
+    rules: &[Box<dyn Rule + Send>],
+    star: &StarWithPlanets,
+) -> Vec<usize> {
+    let mut result: Vec<usize> = vec![];
+    for rule in rules {
+        for index in rule.matching_planets(star) {
+            if !result.contains(&index) {
+                result.push(index);
+            }
+        }
+    }
+    result.sort_unstable();
+    result
+}
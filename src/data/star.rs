@@ -1,29 +1,25 @@
+use super::context::GenerationContext;
 use super::enums::{SpectrType, StarType};
 use super::game_desc::GameDesc;
 use super::random::DspRandom;
 use super::vector3::Vector3;
 use serde::ser::{Serialize, SerializeStruct, Serializer};
-use std::cell::{OnceCell, RefCell};
+use std::cell::OnceCell;
 use std::f64::consts::PI;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct Star<'a> {
     pub game_desc: &'a GameDesc,
-    pub used_theme_ids: RefCell<Vec<i32>>,
+    pub context: Rc<GenerationContext>,
     pub index: usize,
 
-    pub name_seed: i32,
     pub position: Vector3,
     pub level: f32,
     pub star_type: StarType,
-    age_factor: f64,
-    age_num1: f32,
-    age_num2: f32,
-    age_num3: f32,
-    lifetime_factor: f64,
-    radius_factor: f64,
-    pub planets_seed: i32,
-    mass_params: (f64, f64, f64, f64, f32),
+    seed: i32,
+    spectr_factor: f32,
+    params: OnceCell<StarParams>,
     unmodified_mass: OnceCell<f32>,
     resource_coef: OnceCell<f32>,
     lifetime: OnceCell<f32>,
@@ -43,60 +39,116 @@ pub struct Star<'a> {
     dyson_radius: OnceCell<i32>,
 }
 
-impl<'a> Star<'a> {
-    pub fn new(
-        game_desc: &'a GameDesc,
-        index: usize,
-        seed: i32,
-        position: Vector3,
-        need_type: StarType,
-        need_spectr: &SpectrType,
-    ) -> Self {
+/// The random numbers a star is generated from, drawn in the game's order.
+/// Drawn separately so several stars can be drawn at once, see
+/// `worldgen::batch_gen`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StarDraws {
+    pub name_seed: i32,
+    pub planets_seed: i32,
+    pub r1_1: f64,
+    pub r2_1: f64,
+    pub age_factor: f64,
+    pub rn: f64,
+    pub rt: f64,
+    /// Not drawn for the birth star.
+    pub mass_factor: f64,
+    pub lifetime_factor: f64,
+    pub ry: f64,
+}
+
+impl StarDraws {
+    pub fn new(seed: i32, index: usize) -> Self {
         let mut rand1 = DspRandom::new(seed);
         let name_seed = rand1.next_seed();
         let mut rand2 = DspRandom::new(rand1.next_seed());
         rand1.next_f64();
         let planets_seed = rand1.next_seed();
-        let r1_1 = rand2.next_f64();
-        let r2_1 = rand2.next_f64();
-        let age_factor = rand2.next_f64();
-        let rn = rand2.next_f64();
-        let rt = rand2.next_f64();
-        let age_num1 = (rn * 0.1 + 0.95) as f32;
-        let age_num2 = (rt * 0.4 + 0.8) as f32;
-        let age_num3 = (rt * 9.0 + 1.0) as f32;
-        let mass_factor = if index == 0 { 0.0 } else { rand2.next_f64() };
-        let lifetime_factor = rand2.next_f64();
-        let y = rand2.next_f64() * 0.4 - 0.2;
-        let radius_factor = 2_f64.powf(y);
-        let mass_params = (
+        Self {
+            name_seed,
+            planets_seed,
+            r1_1: rand2.next_f64(),
+            r2_1: rand2.next_f64(),
+            age_factor: rand2.next_f64(),
+            rn: rand2.next_f64(),
+            rt: rand2.next_f64(),
+            mass_factor: if index == 0 { 0.0 } else { rand2.next_f64() },
+            lifetime_factor: rand2.next_f64(),
+            ry: rand2.next_f64(),
+        }
+    }
+}
+
+/// What a star's properties are computed from, drawn the first time one of
+/// them is needed.
+#[derive(Debug)]
+struct StarParams {
+    name_seed: i32,
+    planets_seed: i32,
+    age_factor: f64,
+    age_num1: f32,
+    age_num2: f32,
+    age_num3: f32,
+    lifetime_factor: f64,
+    radius_factor: f64,
+    mass_params: (f64, f64, f64, f64),
+}
+
+impl StarParams {
+    fn new(draws: StarDraws) -> Self {
+        let StarDraws {
+            name_seed,
+            planets_seed,
             r1_1,
             r2_1,
-            y,
+            age_factor,
+            rn,
+            rt,
             mass_factor,
-            match need_spectr {
-                SpectrType::M => -3_f32,
-                SpectrType::O => 4.65_f32,
-                _ => 0.0,
-            },
-        );
+            lifetime_factor,
+            ry,
+        } = draws;
+        let y = ry * 0.4 - 0.2;
+        Self {
+            name_seed,
+            planets_seed,
+            age_factor,
+            age_num1: (rn * 0.1 + 0.95) as f32,
+            age_num2: (rt * 0.4 + 0.8) as f32,
+            age_num3: (rt * 9.0 + 1.0) as f32,
+            lifetime_factor,
+            radius_factor: 2_f64.powf(y),
+            mass_params: (r1_1, r2_1, y, mass_factor),
+        }
+    }
+}
 
+impl<'a> Star<'a> {
+    /// Stores what the star is generated from; its random numbers are only
+    /// drawn once one of its properties is asked for.
+    pub fn new(
+        game_desc: &'a GameDesc,
+        context: Rc<GenerationContext>,
+        index: usize,
+        seed: i32,
+        position: Vector3,
+        need_type: StarType,
+        need_spectr: &SpectrType,
+    ) -> Self {
         Self {
             game_desc,
-            used_theme_ids: RefCell::new(vec![]),
+            context,
             index,
-            name_seed,
             position,
             level: (index as f32) / ((game_desc.star_count - 1) as f32),
             star_type: need_type,
-            age_factor,
-            age_num1,
-            age_num2,
-            age_num3,
-            lifetime_factor,
-            radius_factor,
-            planets_seed,
-            mass_params,
+            seed,
+            spectr_factor: match need_spectr {
+                SpectrType::M => -3_f32,
+                SpectrType::O => 4.65_f32,
+                _ => 0.0,
+            },
+            params: OnceCell::new(),
             unmodified_mass: OnceCell::new(),
             resource_coef: OnceCell::new(),
             lifetime: OnceCell::new(),
@@ -117,13 +169,50 @@ impl<'a> Star<'a> {
         }
     }
 
+    /// Same as `new` with the random numbers already drawn.
+    pub fn from_draws(
+        game_desc: &'a GameDesc,
+        context: Rc<GenerationContext>,
+        index: usize,
+        draws: StarDraws,
+        position: Vector3,
+        need_type: StarType,
+        need_spectr: &SpectrType,
+    ) -> Self {
+        let star = Self::new(
+            game_desc,
+            context,
+            index,
+            0,
+            position,
+            need_type,
+            need_spectr,
+        );
+        let _ = star.params.set(StarParams::new(draws));
+        star
+    }
+
+    fn params(&self) -> &StarParams {
+        self.params
+            .get_or_init(|| StarParams::new(StarDraws::new(self.seed, self.index)))
+    }
+
+    pub fn get_name_seed(&self) -> i32 {
+        self.params().name_seed
+    }
+
+    pub fn get_planets_seed(&self) -> i32 {
+        self.params().planets_seed
+    }
+
     pub fn is_birth(&self) -> bool {
         self.index == 0
     }
 
     pub fn get_unmodified_mass(&self) -> f32 {
         *self.unmodified_mass.get_or_init(|| {
-            let (r1_1, r2_1, y, mass_factor, spectr_factor) = self.mass_params;
+            let (r1_1, r2_1, y, mass_factor) = self.params().mass_params;
+            let spectr_factor = self.spectr_factor;
             if self.is_birth() {
                 let p1 = rand_normal(0.0, 0.08, r1_1, r2_1).clamp(-0.2, 0.2);
                 2_f32.powf(p1)
@@ -198,7 +287,7 @@ impl<'a> Star<'a> {
             let lifetime = (10000.0
                 * 0.1_f64
                     .powf(((self.get_unmodified_mass() as f64) * mass_multiplier).log(d) + 1.0)
-                * (self.lifetime_factor * 0.2 + 0.9))
+                * (self.params().lifetime_factor * 0.2 + 0.9))
                 + lifetime_delta;
 
             if self.is_birth() {
@@ -221,21 +310,21 @@ impl<'a> Star<'a> {
     pub fn get_age(&self) -> f32 {
         *self.age.get_or_init(|| {
             (if self.is_birth() {
-                self.age_factor * 0.4 + 0.3
+                self.params().age_factor * 0.4 + 0.3
             } else {
                 match self.star_type {
-                    StarType::GiantStar => self.age_factor * 0.04 + 0.96,
+                    StarType::GiantStar => self.params().age_factor * 0.04 + 0.96,
                     StarType::WhiteDwarf | StarType::NeutronStar | StarType::BlackHole => {
-                        self.age_factor * 0.4 + 1.0
+                        self.params().age_factor * 0.4 + 1.0
                     }
                     _ => {
                         let unmodified_mass = self.get_unmodified_mass();
                         if unmodified_mass >= 0.8 {
-                            self.age_factor * 0.7 + 0.2
+                            self.params().age_factor * 0.7 + 0.2
                         } else if unmodified_mass >= 0.5 {
-                            self.age_factor * 0.4 + 0.1
+                            self.params().age_factor * 0.4 + 0.1
                         } else {
-                            self.age_factor * 0.12 + 0.02
+                            self.params().age_factor * 0.12 + 0.02
                         }
                     }
                 }
@@ -260,8 +349,8 @@ impl<'a> Star<'a> {
     pub fn get_temperature(&self) -> f32 {
         *self.temperature.get_or_init(|| match self.star_type {
             StarType::BlackHole => 0.0,
-            StarType::NeutronStar => self.age_num3 * 1e+7,
-            StarType::WhiteDwarf => self.age_num2 * 150000.0,
+            StarType::NeutronStar => self.params().age_num3 * 1e+7,
+            StarType::WhiteDwarf => self.params().age_num2 * 150000.0,
             _ => {
                 let temperature = self.get_unmodified_temperature();
                 if self.star_type == StarType::GiantStar {
@@ -312,9 +401,9 @@ impl<'a> Star<'a> {
         *self.luminosity.get_or_init(|| {
             let base = self.get_temperature_factor().powf(0.7);
             let factor = match self.star_type {
-                StarType::BlackHole => 1.0 / 1000.0 * self.age_num1,
-                StarType::NeutronStar => 0.1 * self.age_num1,
-                StarType::WhiteDwarf => 0.04 * self.age_num1,
+                StarType::BlackHole => 1.0 / 1000.0 * self.params().age_num1,
+                StarType::NeutronStar => 0.1 * self.params().age_num1,
+                StarType::WhiteDwarf => 0.04 * self.params().age_num1,
                 StarType::GiantStar => 1.6,
                 _ => 1.0,
             };
@@ -333,9 +422,10 @@ impl<'a> Star<'a> {
                 if num4 > 10.0 {
                     num4 = ((num4 * 0.1).ln() + 1.0) * 10.0;
                 }
-                num4 * self.age_num2
+                num4 * self.params().age_num2
             } else {
-                (((self.get_unmodified_mass() as f64).powf(0.4) * self.radius_factor) as f32)
+                (((self.get_unmodified_mass() as f64).powf(0.4) * self.params().radius_factor)
+                    as f32)
                     * (match self.star_type {
                         StarType::NeutronStar => 0.15,
                         StarType::WhiteDwarf => 0.2,
@@ -352,9 +442,9 @@ impl<'a> Star<'a> {
             } else {
                 let r = 1.7_f32.powf((self.get_class_factor() as f32) + 2.0);
                 let factor = match self.star_type {
-                    StarType::BlackHole => 0.4 * self.age_num1,
-                    StarType::NeutronStar => 3.0 * self.age_num1,
-                    StarType::WhiteDwarf => 0.2 * self.age_num1,
+                    StarType::BlackHole => 0.4 * self.params().age_num1,
+                    StarType::NeutronStar => 3.0 * self.params().age_num1,
+                    StarType::WhiteDwarf => 0.2 * self.params().age_num1,
                     _ => 1.0,
                 };
                 r * factor
@@ -366,7 +456,7 @@ impl<'a> Star<'a> {
         *self.habitable_radius.get_or_init(|| {
             let factor = match self.star_type {
                 StarType::BlackHole | StarType::NeutronStar => 0.0,
-                StarType::WhiteDwarf => 0.15 * self.age_num2,
+                StarType::WhiteDwarf => 0.15 * self.params().age_num2,
                 StarType::GiantStar => 9.0,
                 _ => 1.0,
             };
@@ -382,9 +472,9 @@ impl<'a> Star<'a> {
 
     pub fn get_mass(&self) -> f32 {
         *self.mass.get_or_init(|| match self.star_type {
-            StarType::BlackHole => self.get_unmodified_mass() * 2.5 * self.age_num2,
+            StarType::BlackHole => self.get_unmodified_mass() * 2.5 * self.params().age_num2,
             StarType::NeutronStar | StarType::WhiteDwarf => {
-                self.get_unmodified_mass() * 0.2 * self.age_num1
+                self.get_unmodified_mass() * 0.2 * self.params().age_num1
             }
             StarType::GiantStar => {
                 let num5 = 1.0 - self.get_age().powf(30.0) * 0.5;
@@ -402,7 +492,7 @@ impl<'a> Star<'a> {
             }
             orbit_scaler
                 * (match self.star_type {
-                    StarType::NeutronStar => 1.5 * self.age_num1,
+                    StarType::NeutronStar => 1.5 * self.params().age_num1,
                     StarType::GiantStar => 3.3,
                     _ => 1.0,
                 })
@@ -423,7 +513,7 @@ impl Serialize for Star<'_> {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Star", 11)?;
+        let mut state = serializer.serialize_struct("Star", 14)?;
         state.serialize_field("index", &self.index)?;
         state.serialize_field("position", &self.position)?;
         state.serialize_field("mass", &self.get_mass())?;
@@ -435,6 +525,9 @@ impl Serialize for Star<'_> {
         state.serialize_field("luminosity", &self.get_luminosity())?;
         state.serialize_field("radius", &self.get_radius())?;
         state.serialize_field("dysonRadius", &self.get_dyson_radius())?;
+        state.serialize_field("habitableRadius", &self.get_habitable_radius())?;
+        state.serialize_field("lightBalanceRadius", &self.get_light_balance_radius())?;
+        state.serialize_field("orbitScaler", &self.get_orbit_scaler())?;
         state.end()
     }
 }
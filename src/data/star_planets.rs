@@ -8,17 +8,34 @@ use super::random::DspRandom;
 use super::star::Star;
 use serde::Serialize;
 
-pub fn serialize_planets<S>(
-    planets: &OnceCell<Vec<Rc<RefCell<Planet<'_>>>>>,
-    serializer: S,
-) -> Result<S::Ok, S::Error>
+fn serialize_planets<S>(planets: &OnceCell<Planets<'_>>, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
 {
-    planets
-        .get()
-        .expect("planets must be initialized")
-        .serialize(serializer)
+    let planets = planets.get().expect("planets must be initialized");
+    planets.all[..planets.count].serialize(serializer)
+}
+
+/// The planets of a star. Those past `count` belonged to stars generated
+/// before in its place and are kept to be recycled.
+#[derive(Debug, Default)]
+struct Planets<'a> {
+    all: Vec<Rc<RefCell<Planet<'a>>>>,
+    count: usize,
+}
+
+impl<'a> Planets<'a> {
+    fn push(&mut self, planet: Planet<'a>) {
+        match self.all.get(self.count) {
+            Some(slot) => slot.borrow_mut().recycle(planet),
+            None => self.all.push(Rc::new(RefCell::new(planet))),
+        }
+        self.count += 1;
+    }
+
+    fn iter(&self) -> impl Iterator<Item = Ref<'_, Planet<'a>>> {
+        self.all[..self.count].iter().map(|p| p.borrow())
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -26,7 +43,9 @@ pub struct StarWithPlanets<'a> {
     #[serde(flatten)]
     pub star: Rc<Star<'a>>,
     #[serde(serialize_with = "serialize_planets")]
-    planets: OnceCell<Vec<Rc<RefCell<Planet<'a>>>>>,
+    planets: OnceCell<Planets<'a>>,
+    #[serde(skip)]
+    spare_planets: RefCell<Vec<Rc<RefCell<Planet<'a>>>>>,
     #[serde(skip)]
     safe: Cell<bool>,
     #[serde(skip)]
@@ -39,12 +58,32 @@ impl<'a> StarWithPlanets<'a> {
         Self {
             star,
             planets: OnceCell::new(),
+            spare_planets: RefCell::default(),
             safe: Cell::new(false),
             avg_veins: RefCell::new(HashMap::new()),
             name: Default::default(),
         }
     }
 
+    /// Replaces the star by `star`, keeping the memory of its planets for the
+    /// new ones. `placeholder` stands in for the star in the planets waiting to
+    /// be recycled, so that the star itself can be reused.
+    pub(crate) fn recycle(&mut self, star: Star<'a>, placeholder: &Rc<Star<'a>>) {
+        if let Some(planets) = self.planets.take() {
+            for planet in &planets.all {
+                planet.borrow_mut().release(placeholder);
+            }
+            *self.spare_planets.get_mut() = planets.all;
+        }
+        match Rc::get_mut(&mut self.star) {
+            Some(old) => *old = star,
+            None => self.star = Rc::new(star),
+        }
+        self.safe.set(false);
+        self.avg_veins.get_mut().clear();
+        self.name.clear();
+    }
+
     pub fn is_safe(&self) -> bool {
         self.safe.get()
     }
@@ -104,11 +143,14 @@ impl<'a> StarWithPlanets<'a> {
         count
     }
 
-    pub fn get_planets(&self) -> impl Iterator<Item = Ref<Planet<'a>>> {
+    pub fn get_planets(&self) -> impl Iterator<Item = Ref<'_, Planet<'a>>> {
         self.planets
             .get_or_init(|| {
-                let mut planets: Vec<Rc<RefCell<Planet>>> = vec![];
-                let mut rand2 = DspRandom::new(self.star.planets_seed);
+                let mut planets = Planets {
+                    all: self.spare_planets.take(),
+                    count: 0,
+                };
+                let mut rand2 = DspRandom::new(self.star.get_planets_seed());
                 let num1 = rand2.next_f64();
                 let num2 = rand2.next_f64();
                 let num3 = if rand2.next_f64() > 0.5 { 1 } else { 0 };
@@ -120,14 +162,14 @@ impl<'a> StarWithPlanets<'a> {
                 let mut make_planet = |index: usize, orbit_index: usize, gas_giant: bool| {
                     let info_seed = rand2.next_seed();
                     let gen_seed = rand2.next_seed();
-                    Rc::new(RefCell::new(Planet::new(
+                    Planet::new(
                         self.star.clone(),
                         index,
                         orbit_index,
                         gas_giant,
                         info_seed,
                         gen_seed,
-                    )))
+                    )
                 };
 
                 let star_type = &self.star.star_type;
@@ -143,8 +185,8 @@ impl<'a> StarWithPlanets<'a> {
                     } else {
                         planets.push(make_planet(0, 4, true));
                         planets.push(make_planet(1, 1, false));
-                        let planet1 = &planets[0];
-                        let planet2 = &planets[1];
+                        let planet1 = &planets.all[0];
+                        let planet2 = &planets.all[1];
                         planet2.borrow_mut().orbit_around = Some(planet1.clone());
                     }
                 } else if star_type == &StarType::GiantStar {
@@ -157,8 +199,8 @@ impl<'a> StarWithPlanets<'a> {
                         } else {
                             planets.push(make_planet(0, 3, true));
                             planets.push(make_planet(1, 1, false));
-                            let planet1 = &planets[0];
-                            let planet2 = &planets[1];
+                            let planet1 = &planets.all[0];
+                            let planet2 = &planets.all[1];
                             planet2.borrow_mut().orbit_around = Some(planet1.clone());
                         }
                     } else if num2 < 0.15 {
@@ -169,16 +211,16 @@ impl<'a> StarWithPlanets<'a> {
                         planets.push(make_planet(0, 2 + num3, false));
                         planets.push(make_planet(1, 4, true));
                         planets.push(make_planet(2, 1, false));
-                        let planet2 = &planets[1];
-                        let planet3 = &planets[2];
+                        let planet2 = &planets.all[1];
+                        let planet3 = &planets.all[2];
                         planet3.borrow_mut().orbit_around = Some(planet2.clone());
                     } else {
                         planets.push(make_planet(0, 3 + num3, true));
                         planets.push(make_planet(1, 1, false));
                         planets.push(make_planet(2, 2, false));
-                        let planet1 = &planets[0];
-                        let planet2 = &planets[1];
-                        let planet3 = &planets[2];
+                        let planet1 = &planets.all[0];
+                        let planet2 = &planets.all[1];
+                        let planet3 = &planets.all[2];
                         planet2.borrow_mut().orbit_around = Some(planet1.clone());
                         planet3.borrow_mut().orbit_around = Some(planet1.clone());
                     }
@@ -305,7 +347,6 @@ impl<'a> StarWithPlanets<'a> {
                     let mut satellite_count = 0;
                     let mut orbit_around: Option<usize> = None;
                     let mut num10: usize = 1;
-                    let mut orbits: Vec<(usize, usize)> = vec![];
                     for (index, _) in p_gas.iter().enumerate().take(planet_count) {
                         let info_seed = rand2.next_seed();
                         let gen_seed = rand2.next_seed();
@@ -356,9 +397,7 @@ impl<'a> StarWithPlanets<'a> {
                             info_seed,
                             gen_seed,
                         );
-                        if let Some(around) = orbit_around {
-                            orbits.push((index, around))
-                        }
+                        let around = orbit_around;
                         num10 += 1;
                         if gas_giant {
                             orbit_around = Some(index);
@@ -368,18 +407,16 @@ impl<'a> StarWithPlanets<'a> {
                             orbit_around = None;
                             satellite_count = 0;
                         }
-                        planets.push(Rc::new(RefCell::new(planet)));
-                    }
-                    for (index, orbit_index) in orbits {
-                        let planet = &planets[index];
-                        let orbit_planet = &planets[orbit_index];
-                        planet.borrow_mut().orbit_around = Some(orbit_planet.clone());
+                        planets.push(planet);
+                        if let Some(around) = around {
+                            let orbit_planet = planets.all[around].clone();
+                            planets.all[index].borrow_mut().orbit_around = Some(orbit_planet);
+                        }
                     }
                 }
                 planets
             })
             .iter()
-            .map(|p| p.borrow())
     }
 }
 
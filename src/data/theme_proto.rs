@@ -1,4 +1,4 @@
-use super::enums::{PlanetType, ThemeDistribute, VeinType};
+use super::enums::{GasType, OceanType, PlanetType, ThemeDistribute, VeinType};
 use once_cell::sync::Lazy;
 use serde::Serialize;
 
@@ -8,7 +8,7 @@ pub struct ThemeProto {
     pub id: i32,
     pub name: &'static str,
     pub wind: f32,
-    pub water_item_id: i32,
+    pub water_item_id: OceanType,
     #[serde(skip)]
     pub distribute: ThemeDistribute,
     #[serde(skip)]
@@ -26,7 +26,7 @@ pub struct ThemeProto {
     #[serde(skip)]
     pub rare_settings: Vec<f32>,
     #[serde(skip)]
-    pub gas_items: Vec<i32>,
+    pub gas_items: Vec<GasType>,
     #[serde(skip)]
     pub gas_speeds: Vec<f32>,
 }
@@ -34,7 +34,7 @@ pub struct ThemeProto {
 pub const DEFAULT_THEME_PROTO: &ThemeProto = &ThemeProto {
     id: 0,
     name: "",
-    water_item_id: 0,
+    water_item_id: OceanType::None,
     wind: 0.0,
     distribute: ThemeDistribute::Default,
     temperature: 0.0,
@@ -59,7 +59,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 1,
             name: "Ocean 1",
-            water_item_id: 1000,
+            water_item_id: OceanType::Water,
             wind: 1.0,
             distribute: ThemeDistribute::Birth,
             temperature: 0.0,
@@ -75,7 +75,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 2,
             name: "Gas 1",
-            water_item_id: 0,
+            water_item_id: OceanType::None,
             wind: 0.0,
             distribute: ThemeDistribute::Default,
             temperature: 2.0,
@@ -85,13 +85,13 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
             vein_opacity: vec![],
             rare_veins: vec![],
             rare_settings: vec![],
-            gas_items: vec![1120, 1121],
+            gas_items: vec![GasType::Hydrogen, GasType::Deuterium],
             gas_speeds: vec![0.96, 0.04],
         },
         ThemeProto {
             id: 3,
             name: "Gas 2",
-            water_item_id: 0,
+            water_item_id: OceanType::None,
             wind: 0.0,
             distribute: ThemeDistribute::Default,
             temperature: 1.0,
@@ -101,13 +101,13 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
             vein_opacity: vec![],
             rare_veins: vec![],
             rare_settings: vec![],
-            gas_items: vec![1120, 1121],
+            gas_items: vec![GasType::Hydrogen, GasType::Deuterium],
             gas_speeds: vec![0.96, 0.04],
         },
         ThemeProto {
             id: 4,
             name: "Gas 3",
-            water_item_id: 0,
+            water_item_id: OceanType::None,
             wind: 0.0,
             distribute: ThemeDistribute::Default,
             temperature: -1.0,
@@ -117,13 +117,13 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
             vein_opacity: vec![],
             rare_veins: vec![],
             rare_settings: vec![],
-            gas_items: vec![1011, 1120],
+            gas_items: vec![GasType::Fireice, GasType::Hydrogen],
             gas_speeds: vec![0.7, 0.3],
         },
         ThemeProto {
             id: 5,
             name: "Gas 4",
-            water_item_id: 0,
+            water_item_id: OceanType::None,
             wind: 0.0,
             distribute: ThemeDistribute::Default,
             temperature: -2.0,
@@ -133,13 +133,13 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
             vein_opacity: vec![],
             rare_veins: vec![],
             rare_settings: vec![],
-            gas_items: vec![1011, 1120],
+            gas_items: vec![GasType::Fireice, GasType::Hydrogen],
             gas_speeds: vec![0.7, 0.3],
         },
         ThemeProto {
             id: 6,
             name: "Desert 1",
-            water_item_id: 0,
+            water_item_id: OceanType::None,
             wind: 1.5,
             distribute: ThemeDistribute::Default,
             temperature: 2.0,
@@ -155,7 +155,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 7,
             name: "Desert 2",
-            water_item_id: 0,
+            water_item_id: OceanType::None,
             wind: 0.4,
             distribute: ThemeDistribute::Default,
             temperature: -1.0,
@@ -171,7 +171,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 8,
             name: "Ocean 2",
-            water_item_id: 1000,
+            water_item_id: OceanType::Water,
             wind: 1.0,
             distribute: ThemeDistribute::Interstellar,
             temperature: 0.0,
@@ -187,7 +187,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 9,
             name: "Lava 1",
-            water_item_id: -1,
+            water_item_id: OceanType::Lava,
             wind: 0.7,
             distribute: ThemeDistribute::Default,
             temperature: 5.0,
@@ -203,7 +203,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 10,
             name: "Ice 1",
-            water_item_id: 1000,
+            water_item_id: OceanType::Water,
             wind: 0.7,
             distribute: ThemeDistribute::Default,
             temperature: -5.0,
@@ -219,7 +219,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 11,
             name: "Desert 3",
-            water_item_id: 0,
+            water_item_id: OceanType::None,
             wind: 0.0,
             distribute: ThemeDistribute::Default,
             temperature: -2.0,
@@ -235,7 +235,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 12,
             name: "Desert 4",
-            water_item_id: 0,
+            water_item_id: OceanType::None,
             wind: 0.8,
             distribute: ThemeDistribute::Default,
             temperature: 1.0,
@@ -251,7 +251,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 13,
             name: "Volcanic 1",
-            water_item_id: 1116,
+            water_item_id: OceanType::Sulfur,
             wind: 0.8,
             distribute: ThemeDistribute::Interstellar,
             temperature: 4.0,
@@ -267,7 +267,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 14,
             name: "Ocean 3",
-            water_item_id: 1000,
+            water_item_id: OceanType::Water,
             wind: 1.0,
             distribute: ThemeDistribute::Interstellar,
             temperature: 0.0,
@@ -283,7 +283,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 15,
             name: "Ocean 4",
-            water_item_id: 1000,
+            water_item_id: OceanType::Water,
             wind: 1.1,
             distribute: ThemeDistribute::Interstellar,
             temperature: 0.0,
@@ -299,7 +299,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 16,
             name: "Ocean 5",
-            water_item_id: 1000,
+            water_item_id: OceanType::Water,
             wind: 1.1,
             distribute: ThemeDistribute::Interstellar,
             temperature: 0.0,
@@ -315,7 +315,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 17,
             name: "Desert 5",
-            water_item_id: 0,
+            water_item_id: OceanType::None,
             wind: 1.1,
             distribute: ThemeDistribute::Default,
             temperature: 1.0,
@@ -331,7 +331,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 18,
             name: "Ocean 6",
-            water_item_id: 1000,
+            water_item_id: OceanType::Water,
             wind: 1.0,
             distribute: ThemeDistribute::Interstellar,
             temperature: 0.0,
@@ -347,7 +347,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 19,
             name: "Desert 6",
-            water_item_id: 0,
+            water_item_id: OceanType::None,
             wind: 1.6,
             distribute: ThemeDistribute::Interstellar,
             temperature: 1.0,
@@ -363,7 +363,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 20,
             name: "Desert 7",
-            water_item_id: -2,
+            water_item_id: OceanType::Ice,
             wind: 0.7,
             distribute: ThemeDistribute::Default,
             temperature: -2.0,
@@ -379,7 +379,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 21,
             name: "Gas 5",
-            water_item_id: 0,
+            water_item_id: OceanType::None,
             wind: 0.0,
             distribute: ThemeDistribute::Interstellar,
             temperature: 1.0,
@@ -389,13 +389,13 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
             vein_opacity: vec![],
             rare_veins: vec![],
             rare_settings: vec![],
-            gas_items: vec![1120, 1121],
+            gas_items: vec![GasType::Hydrogen, GasType::Deuterium],
             gas_speeds: vec![0.84, 0.16],
         },
         ThemeProto {
             id: 22,
             name: "Desert 8",
-            water_item_id: 1000,
+            water_item_id: OceanType::Water,
             wind: 1.1,
             distribute: ThemeDistribute::Interstellar,
             temperature: 0.0,
@@ -411,7 +411,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 23,
             name: "Desert 9",
-            water_item_id: 0,
+            water_item_id: OceanType::None,
             wind: 1.5,
             distribute: ThemeDistribute::Interstellar,
             temperature: 0.08,
@@ -427,7 +427,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 24,
             name: "Desert 10",
-            water_item_id: 0,
+            water_item_id: OceanType::None,
             wind: 1.3,
             distribute: ThemeDistribute::Default,
             temperature: -4.0,
@@ -443,7 +443,7 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         ThemeProto {
             id: 25,
             name: "Desert 11",
-            water_item_id: -2,
+            water_item_id: OceanType::Ice,
             wind: 1.0,
             distribute: ThemeDistribute::Interstellar,
             temperature: 0.0,
@@ -458,3 +458,55 @@ pub static THEME_PROTOS: Lazy<Vec<ThemeProto>> = Lazy::new(|| {
         },
     ]
 });
+
+/// A set of theme IDs, which are all below 64.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ThemeIdSet(u64);
+
+impl ThemeIdSet {
+    pub fn contains(self, id: i32) -> bool {
+        self.0 & (1 << id) != 0
+    }
+
+    pub fn insert(&mut self, id: i32) {
+        self.0 |= 1 << id;
+    }
+}
+
+/// The themes a planet may be given, by planet type and by whether its star is
+/// the birth star, each in the order of `THEME_PROTOS` so that picking among
+/// them matches the game. The temperature is checked per planet, since the
+/// game compares it with the magnitude of the planet's bias.
+pub struct ThemeCandidates {
+    /// Ocean planets of the birth star.
+    pub birth: Vec<&'static ThemeProto>,
+    /// Other planets of the birth star, by planet type.
+    pub birth_star: [Vec<&'static ThemeProto>; 6],
+    /// Planets of other stars, by planet type.
+    pub other_stars: [Vec<&'static ThemeProto>; 6],
+    /// The fallback when no theme fits.
+    pub desert: Vec<&'static ThemeProto>,
+}
+
+pub static THEME_CANDIDATES: Lazy<ThemeCandidates> = Lazy::new(|| {
+    let themes = |filter: &dyn Fn(&ThemeProto) -> bool| -> Vec<&'static ThemeProto> {
+        THEME_PROTOS.iter().filter(|theme| filter(theme)).collect()
+    };
+    ThemeCandidates {
+        birth: themes(&|theme| theme.distribute == ThemeDistribute::Birth),
+        birth_star: std::array::from_fn(|planet_type| {
+            themes(&|theme| {
+                theme.planet_type.clone() as usize == planet_type
+                    && theme.distribute == ThemeDistribute::Default
+            })
+        }),
+        other_stars: std::array::from_fn(|planet_type| {
+            themes(&|theme| {
+                theme.planet_type.clone() as usize == planet_type
+                    && (theme.distribute == ThemeDistribute::Default
+                        || theme.distribute == ThemeDistribute::Interstellar)
+            })
+        }),
+        desert: themes(&|theme| theme.planet_type == PlanetType::Desert),
+    }
+});
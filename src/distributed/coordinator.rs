@@ -0,0 +1,369 @@
+use super::{write_message, CoordinatorMessage, WorkerMessage};
+use crate::data::game_desc::GameDesc;
+use crate::search::SearchEvent;
+use crate::seeds::SeedSelection;
+use crate::transform_rules::Rules;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::runtime::Handle;
+use tokio::sync::mpsc::{channel, unbounded_channel, Receiver, Sender, UnboundedSender};
+
+/// Search events waiting to be read by the caller of `Coordinator::start`.
+/// While more wait, chunks are not leased, so a slow client holds workers
+/// back instead of piling up results.
+const EVENT_QUEUE: usize = 4096;
+
+#[derive(Debug, Clone)]
+pub struct CoordinatorOptions {
+    /// Number of seeds handed out at once.
+    pub chunk_size: usize,
+    /// A chunk is reassigned if its worker sends nothing for this long.
+    pub lease: Duration,
+}
+
+impl Default for CoordinatorOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 10000,
+            lease: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ChunkState {
+    Pending,
+    Leased { worker: u64, expires: Instant },
+    Done,
+}
+
+struct Job {
+    id: u64,
+    message: CoordinatorMessage,
+    seeds: SeedSelection,
+    len: usize,
+    chunk_size: usize,
+    chunks: Vec<ChunkState>,
+    /// Index of the first chunk that is not done.
+    frontier: usize,
+    reported: usize,
+    /// Whether results are reported in seed order, holding those of chunks
+    /// done past `frontier` in `held`.
+    ordered: bool,
+    held: BTreeMap<usize, Vec<(i32, Vec<usize>)>>,
+    events: Events,
+}
+
+/// Forwards events to the bounded channel of a job, in order, without
+/// blocking while the state is locked.
+struct Events {
+    queue: UnboundedSender<SearchEvent>,
+    /// Events sent but not yet in the bounded channel.
+    backlog: Arc<AtomicUsize>,
+}
+
+impl Events {
+    fn forward(sender: Sender<SearchEvent>, runtime: &Handle) -> Self {
+        let (queue, mut queued) = unbounded_channel();
+        let backlog = Arc::new(AtomicUsize::new(0));
+        let forwarded = backlog.clone();
+        runtime.spawn(async move {
+            while let Some(event) = queued.recv().await {
+                if sender.send(event).await.is_err() {
+                    break;
+                }
+                forwarded.fetch_sub(1, Ordering::Relaxed);
+            }
+        });
+        Self { queue, backlog }
+    }
+
+    fn send(&self, event: SearchEvent) {
+        self.backlog.fetch_add(1, Ordering::Relaxed);
+        let _ = self.queue.send(event);
+    }
+
+    fn is_full(&self) -> bool {
+        self.backlog.load(Ordering::Relaxed) >= EVENT_QUEUE
+    }
+}
+
+impl Job {
+    fn position(&self, chunk: usize) -> usize {
+        (chunk * self.chunk_size).min(self.len)
+    }
+}
+
+#[derive(Default)]
+struct State {
+    next_worker: u64,
+    next_job: u64,
+    workers: HashMap<u64, UnboundedSender<CoordinatorMessage>>,
+    job: Option<Job>,
+}
+
+pub struct Coordinator {
+    options: CoordinatorOptions,
+    state: Mutex<State>,
+    runtime: Handle,
+}
+
+impl Coordinator {
+    /// Listens for workers on `addr`. Must be called within a tokio runtime.
+    pub async fn bind(
+        addr: impl ToSocketAddrs,
+        options: CoordinatorOptions,
+    ) -> std::io::Result<(Arc<Self>, std::net::SocketAddr)> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let coordinator = Arc::new(Self {
+            options,
+            state: Mutex::new(State::default()),
+            runtime: Handle::current(),
+        });
+        let c = coordinator.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, peer)) = listener.accept().await {
+                println!("Worker connected from {}.", peer);
+                tokio::spawn(c.clone().handle_worker(stream));
+            }
+        });
+        Ok((coordinator, local_addr))
+    }
+
+    pub fn worker_count(&self) -> usize {
+        self.state.lock().unwrap().workers.len()
+    }
+
+    /// Starts distributing a search, replacing the current one. Events use the
+    /// same positions as `search`, ending with `SearchEvent::Done`, and results
+    /// are ordered as with `search` when `ordered` is set. Workers wait while
+    /// the events aren't read.
+    pub fn start(
+        &self,
+        game: GameDesc,
+        rule: Rules,
+        seeds: SeedSelection,
+        ordered: bool,
+    ) -> Receiver<SearchEvent> {
+        let (events, receiver) = channel(EVENT_QUEUE);
+        let mut state = self.state.lock().unwrap();
+        Self::finish(&mut state);
+        state.next_job += 1;
+        let len = seeds.len();
+        let message = CoordinatorMessage::Job {
+            id: state.next_job,
+            game,
+            rule,
+            seeds: seeds.clone(),
+        };
+        for worker in state.workers.values() {
+            let _ = worker.send(message.clone());
+        }
+        let job = Job {
+            id: state.next_job,
+            message,
+            seeds,
+            len,
+            chunk_size: self.options.chunk_size,
+            chunks: vec![ChunkState::Pending; len.div_ceil(self.options.chunk_size)],
+            frontier: 0,
+            reported: 0,
+            ordered,
+            held: BTreeMap::new(),
+            events: Events::forward(events, &self.runtime),
+        };
+        if job.chunks.is_empty() {
+            job.events.send(SearchEvent::Done { start: 0, end: 0 });
+        } else {
+            state.job = Some(job);
+        }
+        receiver
+    }
+
+    pub fn stop(&self) {
+        Self::finish(&mut self.state.lock().unwrap());
+    }
+
+    fn finish(state: &mut State) {
+        if let Some(job) = state.job.take() {
+            let end = job.position(job.frontier);
+            job.events.send(SearchEvent::Done {
+                start: job.reported,
+                end: end.max(job.reported),
+            });
+            for worker in state.workers.values() {
+                let _ = worker.send(CoordinatorMessage::Cancel { job: job.id });
+            }
+        }
+    }
+
+    fn register(&self, sender: UnboundedSender<CoordinatorMessage>) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        state.next_worker += 1;
+        let id = state.next_worker;
+        if let Some(job) = &state.job {
+            let _ = sender.send(job.message.clone());
+        }
+        state.workers.insert(id, sender);
+        id
+    }
+
+    fn unregister(&self, worker: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.workers.remove(&worker);
+        if let Some(job) = state.job.as_mut() {
+            for chunk in job.chunks.iter_mut() {
+                if matches!(chunk, ChunkState::Leased { worker: w, .. } if *w == worker) {
+                    *chunk = ChunkState::Pending;
+                }
+            }
+        }
+    }
+
+    fn handle_message(&self, worker: u64, message: WorkerMessage) -> Option<CoordinatorMessage> {
+        let now = Instant::now();
+        let expires = now + self.options.lease;
+        let mut state = self.state.lock().unwrap();
+        match message {
+            WorkerMessage::Hello { threads } => {
+                println!("Worker {} has {} threads.", worker, threads);
+                None
+            }
+            WorkerMessage::Heartbeat => {
+                if let Some(job) = state.job.as_mut() {
+                    for chunk in job.chunks.iter_mut() {
+                        if let ChunkState::Leased {
+                            worker: w,
+                            expires: e,
+                        } = chunk
+                        {
+                            if *w == worker {
+                                *e = expires;
+                            }
+                        }
+                    }
+                }
+                None
+            }
+            WorkerMessage::Request { job: job_id } => {
+                let job = match state.job.as_mut() {
+                    Some(job) if job.id == job_id => job,
+                    _ => return Some(CoordinatorMessage::Cancel { job: job_id }),
+                };
+                if job.events.is_full() {
+                    return Some(CoordinatorMessage::Wait);
+                }
+                let free = job
+                    .chunks
+                    .iter()
+                    .position(|c| *c == ChunkState::Pending)
+                    .or_else(|| {
+                        job.chunks.iter().position(
+                            |c| matches!(c, ChunkState::Leased { expires: e, .. } if *e <= now),
+                        )
+                    });
+                match free {
+                    Some(chunk) => {
+                        job.chunks[chunk] = ChunkState::Leased { worker, expires };
+                        Some(CoordinatorMessage::Chunk {
+                            job: job.id,
+                            chunk,
+                            start: job.position(chunk),
+                            end: job.position(chunk + 1),
+                        })
+                    }
+                    None => Some(CoordinatorMessage::Wait),
+                }
+            }
+            WorkerMessage::Complete {
+                job: job_id,
+                chunk,
+                results,
+            } => {
+                let job = match state.job.as_mut() {
+                    Some(job) if job.id == job_id && chunk < job.chunks.len() => job,
+                    _ => return None,
+                };
+                // Only the worker holding the lease may complete a chunk, even
+                // once it expired, as long as nobody else took it over.
+                if !matches!(job.chunks[chunk], ChunkState::Leased { worker: w, .. } if w == worker)
+                {
+                    println!(
+                        "Worker {} completed chunk {} it doesn't hold.",
+                        worker, chunk
+                    );
+                    return None;
+                }
+                job.chunks[chunk] = ChunkState::Done;
+                let seeds: HashSet<i32> = (job.position(chunk)..job.position(chunk + 1))
+                    .map(|position| job.seeds.get(position))
+                    .collect();
+                let mut results = results;
+                results.retain(|(seed, _)| seeds.contains(seed));
+                let send_results = |results: Vec<(i32, Vec<usize>)>| {
+                    for (seed, indexes) in results {
+                        job.events.send(SearchEvent::Result { seed, indexes });
+                    }
+                };
+                if job.ordered {
+                    job.held.insert(chunk, results);
+                } else {
+                    send_results(results);
+                }
+                while job.frontier < job.chunks.len()
+                    && job.chunks[job.frontier] == ChunkState::Done
+                {
+                    if let Some(results) = job.held.remove(&job.frontier) {
+                        send_results(results);
+                    }
+                    job.frontier += 1;
+                }
+                let end = job.position(job.frontier);
+                if end > job.reported {
+                    job.events.send(SearchEvent::Progress {
+                        start: job.reported,
+                        end,
+                    });
+                    job.reported = end;
+                }
+                if job.frontier == job.chunks.len() {
+                    println!("Job {} completed.", job.id);
+                    Self::finish(&mut state);
+                }
+                None
+            }
+        }
+    }
+
+    async fn handle_worker(self: Arc<Self>, stream: TcpStream) {
+        let (read, mut write) = stream.into_split();
+        let (sender, mut receiver) = unbounded_channel::<CoordinatorMessage>();
+        let worker = self.register(sender.clone());
+        let writer = tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                if write_message(&mut write, &message).await.is_err() {
+                    break;
+                }
+            }
+        });
+        let mut lines = BufReader::new(read).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            match serde_json::from_str::<WorkerMessage>(&line) {
+                Ok(message) => {
+                    if let Some(reply) = self.handle_message(worker, message) {
+                        let _ = sender.send(reply);
+                    }
+                }
+                Err(err) => println!("Invalid message from worker {}: {}", worker, err),
+            }
+        }
+        println!("Worker {} disconnected.", worker);
+        self.unregister(worker);
+        writer.abort();
+    }
+}
@@ -0,0 +1,65 @@
+//! Splitting one search across several `dsp_seed` processes.
+//!
+//! The coordinator owns the seed selection and leases chunks of it to workers
+//! over TCP. Messages are JSON, one per line. A chunk whose lease expires, or
+//! whose worker disconnects, is handed to the next worker asking for work.
+//! Results of a chunk are only accepted together with its completion, from
+//! the worker holding its lease and for seeds of that chunk, so a reassigned
+//! chunk never reports a seed twice.
+
+pub mod coordinator;
+pub mod worker;
+
+use crate::data::game_desc::GameDesc;
+use crate::seeds::SeedSelection;
+use crate::transform_rules::Rules;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum WorkerMessage {
+    Hello {
+        threads: usize,
+    },
+    Request {
+        job: u64,
+    },
+    Heartbeat,
+    Complete {
+        job: u64,
+        chunk: usize,
+        results: Vec<(i32, Vec<usize>)>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CoordinatorMessage {
+    Job {
+        id: u64,
+        game: GameDesc,
+        rule: Rules,
+        seeds: SeedSelection,
+    },
+    /// Positions `start..end` of the job's seed selection.
+    Chunk {
+        job: u64,
+        chunk: usize,
+        start: usize,
+        end: usize,
+    },
+    /// Everything is leased right now, ask again later.
+    Wait,
+    /// The job is finished or stopped.
+    Cancel { job: u64 },
+}
+
+async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &(impl Serialize + Sync),
+) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await
+}
@@ -0,0 +1,200 @@
+use super::{write_message, CoordinatorMessage, WorkerMessage};
+use crate::data::game_desc::GameDesc;
+use crate::search::{search, SearchEvent, Workers};
+use crate::seeds::{SeedSelection, SeedSource, Shard};
+use crate::transform_rules::Rules;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tokio::sync::oneshot;
+
+const HEARTBEAT: Duration = Duration::from_secs(10);
+const RETRY: Duration = Duration::from_secs(5);
+
+struct Job {
+    id: u64,
+    game: GameDesc,
+    rule: Rules,
+    seeds: SeedSelection,
+}
+
+/// Connects to a coordinator and searches the chunks it hands out on
+/// `workers`. Reconnects whenever the connection is lost.
+pub async fn run_worker(addr: &str, workers: Workers) {
+    loop {
+        match TcpStream::connect(addr).await {
+            Ok(stream) => {
+                println!("Connected to {}.", addr);
+                if let Err(err) = serve_connection(stream, workers).await {
+                    println!("Connection lost: {}", err);
+                }
+            }
+            Err(err) => println!("Cannot connect to {}: {}", addr, err),
+        }
+        tokio::time::sleep(RETRY).await;
+    }
+}
+
+async fn serve_connection(stream: TcpStream, workers: Workers) -> std::io::Result<()> {
+    let (read, mut write) = stream.into_split();
+    let (sender, mut incoming) = unbounded_channel();
+    let reader = tokio::spawn(async move {
+        let mut lines = BufReader::new(read).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            match serde_json::from_str::<CoordinatorMessage>(&line) {
+                Ok(message) => {
+                    if sender.send(message).is_err() {
+                        break;
+                    }
+                }
+                Err(err) => println!("Invalid message from coordinator: {}", err),
+            }
+        }
+    });
+    let result = handle_messages(&mut write, &mut incoming, workers).await;
+    reader.abort();
+    result
+}
+
+async fn handle_messages(
+    write: &mut OwnedWriteHalf,
+    incoming: &mut UnboundedReceiver<CoordinatorMessage>,
+    workers: Workers,
+) -> std::io::Result<()> {
+    let threads = workers.threads;
+    write_message(write, &WorkerMessage::Hello { threads }).await?;
+    let mut current: Option<Job> = None;
+    while let Some(message) = incoming.recv().await {
+        match message {
+            CoordinatorMessage::Job {
+                id,
+                game,
+                rule,
+                seeds,
+            } => {
+                println!("Received job {}.", id);
+                current = Some(Job {
+                    id,
+                    game,
+                    rule,
+                    seeds,
+                });
+                write_message(write, &WorkerMessage::Request { job: id }).await?;
+            }
+            CoordinatorMessage::Chunk {
+                job,
+                chunk,
+                start,
+                end,
+            } => {
+                let Some(current_job) = current.as_ref().filter(|j| j.id == job) else {
+                    continue;
+                };
+                println!("Processing chunk {} ({} seeds).", chunk, end - start);
+                match run_chunk(write, incoming, current_job, start, end, workers).await? {
+                    Some(results) => {
+                        write_message(
+                            write,
+                            &WorkerMessage::Complete {
+                                job,
+                                chunk,
+                                results,
+                            },
+                        )
+                        .await?;
+                        write_message(write, &WorkerMessage::Request { job }).await?;
+                    }
+                    None => current = None,
+                }
+            }
+            CoordinatorMessage::Wait => {
+                if let Some(job) = &current {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    write_message(write, &WorkerMessage::Request { job: job.id }).await?;
+                }
+            }
+            CoordinatorMessage::Cancel { job } => {
+                if current.as_ref().is_some_and(|j| j.id == job) {
+                    println!("Job {} finished.", job);
+                    current = None;
+                }
+            }
+        }
+    }
+    Err(std::io::ErrorKind::UnexpectedEof.into())
+}
+
+/// Searches positions `start..end` of the job, returning `None` if the job was
+/// cancelled meanwhile. Results are in seed order, so the coordinator can keep
+/// them ordered.
+async fn run_chunk(
+    write: &mut OwnedWriteHalf,
+    incoming: &mut UnboundedReceiver<CoordinatorMessage>,
+    job: &Job,
+    start: usize,
+    end: usize,
+    workers: Workers,
+) -> std::io::Result<Option<Vec<(i32, Vec<usize>)>>> {
+    let seeds = SeedSelection {
+        source: SeedSource::List {
+            seeds: (start..end)
+                .map(|position| job.seeds.get(position))
+                .collect(),
+        },
+        shard: Shard::default(),
+    };
+    let (game, rule) = (job.game.clone(), job.rule.clone());
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_search = stop.clone();
+    let (done, mut handle) = oneshot::channel();
+    std::thread::spawn(move || {
+        let results = Mutex::new(vec![]);
+        search(
+            &game,
+            &rule,
+            &seeds,
+            workers,
+            u64::MAX,
+            true,
+            &stop_search,
+            |event| {
+                if let SearchEvent::Result { seed, indexes } = event {
+                    results.lock().unwrap().push((seed, indexes));
+                }
+            },
+        );
+        let _ = done.send(results.into_inner().unwrap());
+    });
+    let mut heartbeat = tokio::time::interval(HEARTBEAT);
+    heartbeat.tick().await;
+    let mut cancelled = false;
+    let mut disconnected = false;
+    let results = loop {
+        tokio::select! {
+            results = &mut handle => break results.expect("search panicked"),
+            _ = heartbeat.tick(), if !cancelled => {
+                write_message(write, &WorkerMessage::Heartbeat).await?;
+            }
+            message = incoming.recv(), if !cancelled => match message {
+                Some(CoordinatorMessage::Cancel { job: id }) if id == job.id => {
+                    stop.store(true, Ordering::SeqCst);
+                    cancelled = true;
+                }
+                Some(_) => {}
+                None => {
+                    stop.store(true, Ordering::SeqCst);
+                    cancelled = true;
+                    disconnected = true;
+                }
+            },
+        }
+    };
+    if disconnected {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok((!cancelled).then_some(results))
+}
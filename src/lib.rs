@@ -1,11 +1,46 @@
 mod data;
+#[cfg(not(target_arch = "wasm32"))]
+mod distributed;
+mod normalize_rules;
+mod query;
+mod report;
 mod rules;
+#[cfg(not(target_arch = "wasm32"))]
+mod search;
+mod seeds;
 mod transform_rules;
 mod worldgen;
 
+pub use data::enums::{GasType, OceanType, SpectrType, StarType, VeinType};
+pub use data::galaxy::{Galaxy, GalaxyLayout};
 pub use data::game_desc::GameDesc;
-pub use transform_rules::Rules;
-pub use worldgen::galaxy_gen::{create_galaxy, find_stars};
+pub use data::locale::{star_kind_name, Locale, Localize};
+pub use data::random::{DspRandom, DspRandomBatch};
+pub use data::rule::Rule;
+#[cfg(not(target_arch = "wasm32"))]
+pub use distributed::coordinator::{Coordinator, CoordinatorOptions};
+#[cfg(not(target_arch = "wasm32"))]
+pub use distributed::worker::run_worker;
+pub use normalize_rules::normalize_rules;
+pub use query::{format_query, parse_query, QueryError};
+pub use report::compare::{
+    compare_galaxies, Alignment, CompareOptions, Comparison, ComparisonRow, GalaxySummary,
+    StarSummary,
+};
+pub use report::stats::{
+    sweep, Distribution, DistributionSummary, HitRate, HitRateEstimate, Metric, Statistics,
+    StatisticsReport,
+};
+pub use report::svg::{render_svg, SvgOptions, SvgView};
+pub use report::xstars::{rank_seeds, rank_x_stars, XStarEntry, XStarRanking, XStarWeights};
+pub use rules::star_name::{NameMatch, RuleStarName, StarNamePattern};
+#[cfg(not(target_arch = "wasm32"))]
+pub use search::{search, SearchEvent, Workers};
+pub use seeds::{parse_seed_list, SeedSelection, SeedSource, Shard};
+pub use transform_rules::{transform_rules, Rules};
+pub use worldgen::batch_gen::generate_galaxies;
+pub use worldgen::galaxy_gen::{create_galaxy, find_planets, find_stars, generate_galaxy};
+pub use worldgen::workspace::Workspace;
 
 #[cfg(target_arch = "wasm32")]
 
@@ -33,13 +68,39 @@ mod wasm {
         indexes: Vec<usize>,
     }
 
+    /// Parses a query into a JSON rule, throwing the rendered error if it is
+    /// invalid.
     #[wasm_bindgen]
     #[allow(non_snake_case)]
-    pub fn findStars(gameDesc: JsValue, rule: JsValue) {
+    pub fn parseQuery(query: &str) -> Result<JsValue, JsValue> {
+        let rule = crate::query::parse_query(query)
+            .map_err(|err| JsValue::from_str(&err.render(query)))?;
+        rule.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen]
+    #[allow(non_snake_case)]
+    pub fn formatQuery(rule: JsValue) -> Result<String, serde_wasm_bindgen::Error> {
+        let rule = serde_wasm_bindgen::from_value(rule)?;
+        Ok(crate::query::format_query(&rule))
+    }
+
+    /// `rule` is either a JSON rule or a query.
+    #[wasm_bindgen]
+    #[allow(non_snake_case)]
+    pub fn findStars(gameDesc: JsValue, rule: JsValue) -> Result<(), JsValue> {
+        let rule: crate::transform_rules::Rules = match rule.as_string() {
+            Some(query) => crate::query::parse_query(&query)
+                .map_err(|err| JsValue::from_str(&err.render(&query)))?,
+            None => serde_wasm_bindgen::from_value(rule)?,
+        };
+        let rule = crate::normalize_rules::normalize_rules(rule).map_err(|reason| {
+            JsValue::from_str(&format!("The rule can never match: {}", reason))
+        })?;
         spawn_local(async {
             let serializer = serde_wasm_bindgen::Serializer::json_compatible();
             let mut game_desc: GameDesc = serde_wasm_bindgen::from_value(gameDesc).unwrap();
-            let rule = serde_wasm_bindgen::from_value(rule).unwrap();
             let mut transformed = transform_rules::transform_rules(rule);
             loop {
                 let star_indexes = find_stars(&game_desc, &mut transformed);
@@ -59,9 +120,10 @@ mod wasm {
                     }
                 }
             }
-        })
+        });
+        Ok(())
     }
 }
 
 #[cfg(target_arch = "wasm32")]
-pub use wasm::{findStars, generate};
+pub use wasm::{findStars, formatQuery, generate, parseQuery};
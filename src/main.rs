@@ -1,33 +1,81 @@
 #![cfg(not(target_arch = "wasm32"))]
 
-mod data;
-mod rules;
-mod transform_rules;
-mod worldgen;
+mod cli;
 
-use data::game_desc::GameDesc;
-use futures_util::lock::Mutex;
+use dsp_seed_finder::{
+    create_galaxy, normalize_rules, parse_query, run_worker, search, Coordinator, GameDesc, Rules,
+    SearchEvent, SeedSelection, Workers,
+};
 use futures_util::{future, SinkExt, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
-use std::sync::Arc;
-use std::time::SystemTime;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, RecvTimeoutError, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::runtime::Handle;
+use tokio::sync::mpsc::channel;
 use tokio_tungstenite::accept_async;
 use tokio_tungstenite::tungstenite::Message;
-use transform_rules::Rules;
-use worldgen::galaxy_gen::{create_galaxy, find_stars};
 
 #[tokio::main]
 async fn main() -> Result<(), std::io::Error> {
+    let args = match cli::Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!("{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    match args.command.as_deref() {
+        None | Some("serve") => serve(None, or_exit(args.workers())).await,
+        Some("coordinator") => {
+            let options = or_exit(args.coordinator_options());
+            let listen = or_exit(args.get::<String>("listen"))
+                .unwrap_or_else(|| "127.0.0.1:62880".to_owned());
+            let (coordinator, addr) = Coordinator::bind(listen, options).await?;
+            println!("Waiting for workers on {}.", addr);
+            serve(Some(coordinator), or_exit(args.workers())).await
+        }
+        Some("worker") => {
+            let addr: String = or_exit(args.require("connect"));
+            run_worker(&addr, or_exit(args.workers())).await;
+            Ok(())
+        }
+        Some(command) => {
+            or_exit(cli::run(command, &args));
+            Ok(())
+        }
+    }
+}
+
+fn or_exit<T>(result: Result<T, String>) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    })
+}
+
+/// Searches run with the `workers` options, but as many threads as the web UI
+/// asks for, up to `workers.threads`. Galaxies of all clients are generated on
+/// a pool of `workers.threads` threads, while each client's searches run on a
+/// thread of its own, so that generation never waits for a search.
+async fn serve(
+    coordinator: Option<Arc<Coordinator>>,
+    workers: Workers,
+) -> Result<(), std::io::Error> {
     println!("Starting...");
     let listener = TcpListener::bind("127.0.0.1:62879").await?;
     println!("Started.");
     println!("You may now turn on native mode to search.");
+    let generation = Pool::new("generate", workers.threads.max(1));
     while let Ok((stream, _)) = listener.accept().await {
-        tokio::spawn(accept_connection(stream));
+        tokio::spawn(accept_connection(
+            stream,
+            coordinator.clone(),
+            workers,
+            generation.clone(),
+        ));
     }
     Ok(())
 }
@@ -40,10 +88,29 @@ enum IncomingMessage {
     },
     Find {
         game: GameDesc,
-        rule: Rules,
+        /// Either a rule or a query; `query` wins if both are given.
+        #[serde(default)]
+        rule: Option<Rules>,
+        #[serde(default)]
+        query: Option<String>,
+        #[serde(default)]
         range: (i32, i32),
+        /// Searches these seeds instead of `range`. Progress is then reported
+        /// as positions in the selection rather than as seeds. Seed files are
+        /// refused, as any web page can send this; the client sends a list.
+        #[serde(default)]
+        seeds: Option<SeedSelection>,
         concurrency: i32,
         autosave: u64,
+        /// Sends at most this many results, stopping the search once they are
+        /// found.
+        #[serde(default, rename = "maxResults")]
+        max_results: Option<usize>,
+        /// Sends results in the order of the seed selection, each once the
+        /// seeds before it are searched, rather than as they are found. For
+        /// seed lists this is the order of the list, not increasing seeds.
+        #[serde(default)]
+        ordered: bool,
     },
     Stop,
 }
@@ -51,53 +118,186 @@ enum IncomingMessage {
 #[derive(Serialize)]
 #[serde(tag = "type")]
 enum OutgoingMessage {
-    Result { seed: i32, indexes: Vec<usize> },
-    Progress { start: i32, end: i32 },
-    Done { start: i32, end: i32 },
+    Results { items: Vec<FoundSeed> },
+    Progress { start: i64, end: i64 },
+    Done { start: i64, end: i64 },
+    Error { message: String },
+}
+
+#[derive(Serialize)]
+struct FoundSeed {
+    seed: i32,
+    indexes: Vec<usize>,
+}
+
+/// Results found within this long of each other are sent together.
+const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+/// Results are sent at the latest once this many are waiting.
+const BATCH_SIZE: usize = 1000;
+/// Search events waiting to be batched. Search threads wait while it is full,
+/// which happens when the client doesn't keep up with reading.
+const EVENT_QUEUE: usize = 4096;
+/// Messages waiting to be written to a client.
+const OUTGOING_QUEUE: usize = 16;
+/// Jobs waiting for a thread of the pool. Clients asking for more are told
+/// that the server is busy.
+const POOL_QUEUE: usize = 64;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed number of threads running jobs from a queue, outside of the async
+/// runtime, so that clients can't start threads without bound.
+#[derive(Clone)]
+struct Pool {
+    jobs: SyncSender<Job>,
+}
+
+impl Pool {
+    fn new(name: &str, threads: usize) -> Self {
+        let (jobs, queued) = sync_channel::<Job>(POOL_QUEUE);
+        let queued = Arc::new(Mutex::new(queued));
+        for index in 0..threads {
+            let queued = queued.clone();
+            std::thread::Builder::new()
+                .name(format!("{}-{}", name, index))
+                .spawn(move || loop {
+                    let job = queued.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                })
+                .expect("Cannot start a pool thread");
+        }
+        Self { jobs }
+    }
+
+    /// Queues `job`, or returns false if the queue is full.
+    fn submit(&self, job: impl FnOnce() + Send + 'static) -> bool {
+        self.jobs.try_send(Box::new(job)).is_ok()
+    }
 }
 
-struct FindState {
-    pub progress_start: i32,
-    pub progress_end: i32,
-    pub pending_seeds: HashSet<i32>,
-    pub running: i32,
-    pub autosave: u64,
-    pub last_notify: SystemTime,
+/// Runs a search with `run`, sending its results to the client in batches,
+/// each before the progress covering it. At most `max_results` results are
+/// sent. `halt` is called to stop the search once they are found or `send`
+/// fails because the client is gone.
+fn send_batched(
+    run: impl FnOnce(&(dyn Fn(SearchEvent) + Sync)) + Send,
+    max_results: Option<usize>,
+    offset: i64,
+    halt: impl Fn(),
+    send: impl Fn(&OutgoingMessage) -> bool,
+) {
+    let (events, received) = sync_channel(EVENT_QUEUE);
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            run(&|event| {
+                let _ = events.send(event);
+            })
+        });
+        let mut items = vec![];
+        let mut oldest: Option<Instant> = None;
+        let mut found = 0;
+        let mut halted = false;
+        let mut connected = true;
+        loop {
+            let event = match oldest {
+                Some(oldest) => received.recv_timeout(
+                    (oldest + BATCH_INTERVAL).saturating_duration_since(Instant::now()),
+                ),
+                None => received.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            let finished = event == Err(RecvTimeoutError::Disconnected);
+            let (flush, next) = match event {
+                // Threads still running past the cap find more, which are
+                // dropped.
+                Ok(SearchEvent::Result { .. }) if max_results.is_some_and(|max| found >= max) => {
+                    (false, None)
+                }
+                Ok(SearchEvent::Result { seed, indexes }) => {
+                    items.push(FoundSeed { seed, indexes });
+                    oldest.get_or_insert_with(Instant::now);
+                    found += 1;
+                    let capped = max_results.is_some_and(|max| found >= max);
+                    if capped && !halted {
+                        halted = true;
+                        halt();
+                    }
+                    (capped || items.len() >= BATCH_SIZE, None)
+                }
+                Ok(event) => (true, Some(outgoing_event(event, offset))),
+                Err(_) => (true, None),
+            };
+            if flush && !items.is_empty() {
+                oldest = None;
+                let items = std::mem::take(&mut items);
+                connected = connected && send(&OutgoingMessage::Results { items });
+            }
+            if let Some(message) = next {
+                connected = connected && send(&message);
+            }
+            if !connected && !halted {
+                halted = true;
+                halt();
+            }
+            if finished {
+                break;
+            }
+        }
+    });
 }
 
-impl FindState {
-    pub fn add(&mut self, seed: i32) -> Option<(i32, i32)> {
-        if self.progress_end == seed {
-            self.progress_end += 1;
-            let mut e = self.progress_end;
-            while self.pending_seeds.remove(&e) {
-                e += 1;
+/// Converts a search event to a message for the web UI, offsetting positions
+/// of plain ranges so progress is reported in seeds, as the web UI expects.
+fn outgoing_event(event: SearchEvent, offset: i64) -> OutgoingMessage {
+    match event {
+        SearchEvent::Result { seed, indexes } => OutgoingMessage::Results {
+            items: vec![FoundSeed { seed, indexes }],
+        },
+        SearchEvent::Progress { start, end } => {
+            let end = offset + end as i64;
+            println!("Processing: {}.", end);
+            OutgoingMessage::Progress {
+                start: offset + start as i64,
+                end,
             }
-            self.progress_end = e;
-            let now = SystemTime::now();
-            if now.duration_since(self.last_notify).unwrap().as_secs() >= self.autosave {
-                self.last_notify = now;
-                let start = self.progress_start;
-                self.progress_start = self.progress_end;
-                Some((start, self.progress_end))
-            } else {
-                None
+        }
+        SearchEvent::Done { start, end } => {
+            let end = offset + end as i64;
+            println!("Completed: {}.", end);
+            OutgoingMessage::Done {
+                start: offset + start as i64,
+                end,
             }
-        } else {
-            self.pending_seeds.insert(seed);
-            None
         }
     }
 }
 
-async fn accept_connection(stream: TcpStream) {
+async fn accept_connection(
+    stream: TcpStream,
+    coordinator: Option<Arc<Coordinator>>,
+    workers: Workers,
+    generation: Pool,
+) {
     let ws_stream = accept_async(stream)
         .await
         .expect("Error during websocket handshake");
-    let (write, read) = ws_stream.split();
+    let (mut write, read) = ws_stream.split();
 
-    let boxed_write = Arc::new(Mutex::new(write));
-    let stopped = Arc::new(AtomicBool::new(false));
+    // Generation and searches run on pools and queue messages for this task,
+    // so a slow client never holds them up. Searches of this client take turns
+    // on a thread of its own, which ends with the connection.
+    let searches = Pool::new("find", 1);
+    let (outgoing, mut queued) = channel::<Message>(OUTGOING_QUEUE);
+    tokio::spawn(async move {
+        while let Some(message) = queued.recv().await {
+            if write.send(message).await.is_err() {
+                break;
+            }
+        }
+    });
+    let mut stopped = Arc::new(AtomicBool::new(false));
 
     let _ = read
         .try_for_each(|msg| {
@@ -106,112 +306,107 @@ async fn accept_connection(stream: TcpStream) {
                 match msg {
                     IncomingMessage::Stop => {
                         println!("Stopping");
-                        stopped.store(true, Ordering::SeqCst);
+                        stop_search(&stopped, &coordinator);
                     }
                     IncomingMessage::Generate { game } => {
-                        let w = boxed_write.clone();
-                        tokio::task::spawn_blocking(move || {
+                        let sender = outgoing.clone();
+                        let queued = generation.submit(move || {
                             let galaxy = create_galaxy(&game);
                             let output = serde_json::to_string(&galaxy).unwrap();
-                            let runtime = Handle::current();
-                            runtime.block_on(async move {
-                                w.lock().await.send(Message::Text(output)).await.unwrap();
-                            })
+                            let _ = sender.blocking_send(Message::Text(output));
                         });
+                        if !queued {
+                            busy(&outgoing);
+                        }
                     }
                     IncomingMessage::Find {
                         game,
                         rule,
+                        query,
                         range: (start, end),
+                        seeds,
                         concurrency,
                         autosave,
+                        max_results,
+                        ordered,
                     } => {
                         println!("Receive search request.");
                         println!("Concurrency: {}.", concurrency);
-                        let threads = concurrency.min(end - start);
-                        let current_seed = Arc::new(AtomicI32::new(start));
-                        let state = Arc::new(std::sync::Mutex::new(FindState {
-                            progress_end: start,
-                            progress_start: start,
-                            running: threads,
-                            pending_seeds: HashSet::new(),
-                            autosave,
-                            last_notify: SystemTime::now(),
-                        }));
-                        stopped.store(false, Ordering::SeqCst);
-                        for _ in 0..threads {
-                            let w = boxed_write.clone();
-                            let mut transformed = transform_rules::transform_rules(rule.clone());
-                            let mut g = game.clone();
-                            let s = state.clone();
-                            let cs = current_seed.clone();
-                            let stop = stopped.clone();
-                            tokio::task::spawn_blocking(move || {
-                                let runtime = Handle::current();
-                                loop {
-                                    let seed = cs
-                                        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |x| {
-                                            Some(x + 1)
-                                        })
-                                        .unwrap();
-                                    if seed >= end {
-                                        break;
-                                    }
-                                    g.seed = seed;
-                                    let star_indexes = find_stars(&g, &mut transformed);
-                                    let notify_progress = {
-                                        let mut x = s.lock().unwrap();
-                                        x.add(seed)
-                                    };
-                                    if notify_progress.is_some() || !star_indexes.is_empty() {
-                                        let w2 = w.clone();
-                                        runtime.block_on(async move {
-                                            let mut stream = w2.lock().await;
-                                            if !star_indexes.is_empty() {
-                                                let output = serde_json::to_string(
-                                                    &OutgoingMessage::Result {
-                                                        seed,
-                                                        indexes: star_indexes,
-                                                    },
-                                                )
-                                                .unwrap();
-                                                stream.send(Message::Text(output)).await.unwrap();
-                                            }
-                                            if let Some((start, end)) = notify_progress {
-                                                println!("Processing: {}.", end);
-                                                let output = serde_json::to_string(
-                                                    &OutgoingMessage::Progress { start, end },
-                                                )
-                                                .unwrap();
-                                                stream.send(Message::Text(output)).await.unwrap();
-                                            }
-                                        });
-                                    }
-                                    if stop.load(Ordering::SeqCst) {
-                                        break;
-                                    }
+                        // The new search replaces the running one, which would
+                        // otherwise hold up the searches after it.
+                        stop_search(&stopped, &coordinator);
+                        stopped = Arc::new(AtomicBool::new(false));
+                        let sender = outgoing.clone();
+                        let stop = stopped.clone();
+                        let coordinator = coordinator.clone();
+                        let queued = searches.submit(move || {
+                            let send = |message: &OutgoingMessage| {
+                                let output = serde_json::to_string(message).unwrap();
+                                sender.blocking_send(Message::Text(output)).is_ok()
+                            };
+                            let rule = match (query, rule) {
+                                (Some(query), _) => parse_query(&query).map_err(|err| {
+                                    format!("Invalid query: {}", err.render(&query))
+                                }),
+                                (None, Some(rule)) => Ok(rule),
+                                (None, None) => Err("Missing rule or query".to_owned()),
+                            }
+                            .and_then(|rule| {
+                                normalize_rules(rule).map_err(|reason| {
+                                    format!("The rule can never match: {}", reason)
+                                })
+                            });
+                            let rule = match rule {
+                                Ok(rule) => rule,
+                                Err(message) => {
+                                    println!("{}", message);
+                                    send(&OutgoingMessage::Error { message });
+                                    return;
                                 }
-                                let mut x = s.lock().unwrap();
-                                x.running -= 1;
-                                if x.running == 0 {
-                                    let progress_start = x.progress_start;
-                                    let progress_end = x.progress_end;
-                                    println!("Completed: {}.", progress_end);
-                                    runtime.block_on(async move {
-                                        w.lock()
-                                            .await
-                                            .send(Message::Text(
-                                                serde_json::to_string(&OutgoingMessage::Done {
-                                                    start: progress_start,
-                                                    end: progress_end,
-                                                })
-                                                .unwrap(),
-                                            ))
-                                            .await
-                                            .unwrap();
-                                    })
+                            };
+                            let seeds = seeds.unwrap_or(SeedSelection::range(start, end));
+                            if let Err(message) = seeds.validate() {
+                                println!("{}", message);
+                                send(&OutgoingMessage::Error { message });
+                                return;
+                            }
+                            let offset = if seeds.is_contiguous() && !seeds.is_empty() {
+                                seeds.get(0) as i64
+                            } else {
+                                0
+                            };
+                            let halt = || {
+                                stop.store(true, Ordering::SeqCst);
+                                if let Some(coordinator) = &coordinator {
+                                    coordinator.stop();
                                 }
-                            });
+                            };
+                            let run = |on_event: &(dyn Fn(SearchEvent) + Sync)| match &coordinator {
+                                Some(coordinator) => {
+                                    let mut events = coordinator.start(game, rule, seeds, ordered);
+                                    while let Some(event) = events.blocking_recv() {
+                                        on_event(event);
+                                    }
+                                }
+                                None => search(
+                                    &game,
+                                    &rule,
+                                    &seeds,
+                                    Workers {
+                                        threads: (concurrency.max(1) as usize)
+                                            .min(workers.threads.max(1)),
+                                        ..workers
+                                    },
+                                    autosave,
+                                    ordered,
+                                    &stop,
+                                    on_event,
+                                ),
+                            };
+                            send_batched(run, max_results, offset, halt, send);
+                        });
+                        if !queued {
+                            busy(&outgoing);
                         }
                     }
                 }
@@ -220,3 +415,194 @@ async fn accept_connection(stream: TcpStream) {
         })
         .await;
 }
+
+fn stop_search(stopped: &AtomicBool, coordinator: &Option<Arc<Coordinator>>) {
+    stopped.store(true, Ordering::SeqCst);
+    if let Some(coordinator) = coordinator {
+        coordinator.stop();
+    }
+}
+
+/// Tells the client that its message was dropped because the pool is full.
+fn busy(outgoing: &tokio::sync::mpsc::Sender<Message>) {
+    let message = OutgoingMessage::Error {
+        message: "The server is busy, try again later".to_owned(),
+    };
+    let output = serde_json::to_string(&message).unwrap();
+    let _ = outgoing.try_send(Message::Text(output));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    fn result(seed: i32) -> SearchEvent {
+        SearchEvent::Result {
+            seed,
+            indexes: vec![0],
+        }
+    }
+
+    /// Sizes of the `Results` messages sent, with `None` for other messages.
+    fn sent_sizes(
+        run: impl FnOnce(&(dyn Fn(SearchEvent) + Sync)) + Send,
+        max_results: Option<usize>,
+    ) -> (Vec<Option<usize>>, usize) {
+        let sent = Mutex::new(vec![]);
+        let halts = AtomicUsize::new(0);
+        send_batched(
+            run,
+            max_results,
+            0,
+            || {
+                halts.fetch_add(1, Ordering::SeqCst);
+            },
+            |message| {
+                sent.lock().unwrap().push(match message {
+                    OutgoingMessage::Results { items } => Some(items.len()),
+                    _ => None,
+                });
+                true
+            },
+        );
+        (sent.into_inner().unwrap(), halts.into_inner())
+    }
+
+    #[test]
+    fn results_are_capped_at_max_results() {
+        let (sent, halts) = sent_sizes(
+            |on_event| {
+                (0..10).for_each(|seed| on_event(result(seed)));
+                on_event(SearchEvent::Done { start: 0, end: 10 });
+            },
+            Some(3),
+        );
+        assert_eq!(sent, [Some(3), None]);
+        assert_eq!(halts, 1);
+    }
+
+    #[test]
+    fn results_are_flushed_by_size_and_before_progress() {
+        let (sent, halts) = sent_sizes(
+            |on_event| {
+                (0..BATCH_SIZE as i32 + 5).for_each(|seed| on_event(result(seed)));
+                on_event(SearchEvent::Progress { start: 0, end: 1 });
+                on_event(result(-1));
+                on_event(SearchEvent::Done { start: 1, end: 2 });
+            },
+            None,
+        );
+        assert_eq!(sent, [Some(BATCH_SIZE), Some(5), None, Some(1), None]);
+        assert_eq!(halts, 0);
+    }
+
+    #[test]
+    fn results_are_flushed_by_interval() {
+        let (sent, _) = sent_sizes(
+            |on_event| {
+                on_event(result(0));
+                on_event(result(1));
+                std::thread::sleep(BATCH_INTERVAL * 3);
+                on_event(result(2));
+                on_event(SearchEvent::Done { start: 0, end: 3 });
+            },
+            None,
+        );
+        assert_eq!(sent, [Some(2), Some(1), None]);
+    }
+
+    #[test]
+    fn searches_wait_while_the_outgoing_queue_is_full() {
+        let total = 10 * (EVENT_QUEUE + (OUTGOING_QUEUE + 2) * BATCH_SIZE);
+        let emitted = AtomicUsize::new(0);
+        let (outgoing, mut queued) = channel::<Message>(OUTGOING_QUEUE);
+        let delivered = std::thread::scope(|scope| {
+            let emitted = &emitted;
+            scope.spawn(move || {
+                send_batched(
+                    |on_event| {
+                        for seed in 0..total {
+                            on_event(result(seed as i32));
+                            emitted.fetch_add(1, Ordering::SeqCst);
+                        }
+                        on_event(SearchEvent::Done { start: 0, end: 0 });
+                    },
+                    None,
+                    0,
+                    || {},
+                    |message| {
+                        let text = serde_json::to_string(message).unwrap();
+                        outgoing.blocking_send(Message::Text(text)).is_ok()
+                    },
+                );
+            });
+            // Nobody reads the queue: the search stops once the queue, a
+            // batch on its way and the event queue are full.
+            std::thread::sleep(Duration::from_millis(500));
+            let stalled = emitted.load(Ordering::SeqCst);
+            assert!(
+                stalled <= EVENT_QUEUE + (OUTGOING_QUEUE + 2) * BATCH_SIZE,
+                "{}",
+                stalled
+            );
+            std::thread::sleep(Duration::from_millis(100));
+            assert_eq!(emitted.load(Ordering::SeqCst), stalled);
+
+            let mut delivered = 0;
+            while let Some(message) = queued.blocking_recv() {
+                let text = message.into_text().unwrap();
+                delivered += text.matches("\"seed\"").count();
+            }
+            delivered
+        });
+        assert_eq!(delivered, total);
+    }
+
+    #[test]
+    fn the_pool_runs_as_many_jobs_at_once_as_it_has_threads() {
+        let pool = Pool::new("test", 2);
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let (done, finished) = sync_channel(8);
+        for _ in 0..8 {
+            let (running, most, done) = (running.clone(), most.clone(), done.clone());
+            assert!(pool.submit(move || {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                most.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(20));
+                running.fetch_sub(1, Ordering::SeqCst);
+                done.send(()).unwrap();
+            }));
+        }
+        for _ in 0..8 {
+            finished.recv_timeout(Duration::from_secs(10)).unwrap();
+        }
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn the_pool_refuses_jobs_once_its_queue_is_full() {
+        let pool = Pool::new("test", 1);
+        let (started, running) = sync_channel(1);
+        let (release, released) = sync_channel::<()>(0);
+        assert!(pool.submit(move || {
+            started.send(()).unwrap();
+            released.recv().unwrap();
+        }));
+        running.recv_timeout(Duration::from_secs(10)).unwrap();
+
+        let (done, finished) = sync_channel(POOL_QUEUE);
+        for _ in 0..POOL_QUEUE {
+            let done = done.clone();
+            assert!(pool.submit(move || done.send(()).unwrap()));
+        }
+        assert!(!pool.submit(|| {}));
+
+        release.send(()).unwrap();
+        for _ in 0..POOL_QUEUE {
+            finished.recv_timeout(Duration::from_secs(10)).unwrap();
+        }
+        assert!(pool.submit(|| {}));
+    }
+}
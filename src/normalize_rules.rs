@@ -0,0 +1,482 @@
+use crate::data::enums::{SpectrType, StarType};
+use crate::data::rule::Condition;
+use crate::transform_rules::Rules;
+
+/// Far more stars than a galaxy can have, the range searched for a count of
+/// stars that satisfies a `Composite` condition.
+const MAX_STAR_COUNT: u32 = 1024;
+
+const STAR_TYPES: [StarType; 5] = [
+    StarType::MainSeqStar,
+    StarType::GiantStar,
+    StarType::WhiteDwarf,
+    StarType::NeutronStar,
+    StarType::BlackHole,
+];
+
+/// Simplifies a rule tree before it is transformed: nested `And`s and `Or`s
+/// are flattened, single children replace their parent, duplicates are
+/// removed and conditions on the same value are merged into the tightest one.
+///
+/// Fails with the reason if the rule can never match, e.g. a black hole with
+/// three planets, so the search can be refused before it starts.
+pub fn normalize_rules(rule: Rules) -> Result<Rules, String> {
+    normalize(rule)
+}
+
+fn always_star() -> Rules {
+    Rules::And { rules: vec![] }
+}
+
+fn always_galaxy() -> Rules {
+    Rules::CompositeAnd { rules: vec![] }
+}
+
+fn is_always(rule: &Rules) -> bool {
+    matches!(rule, Rules::And { rules } | Rules::CompositeAnd { rules } if rules.is_empty())
+}
+
+/// Rules compare equal when they serialize to the same JSON.
+fn dedup(rules: &mut Vec<Rules>) {
+    let mut seen = vec![];
+    rules.retain(|rule| {
+        let json = serde_json::to_value(rule).ok();
+        if seen.contains(&json) {
+            false
+        } else {
+            seen.push(json);
+            true
+        }
+    });
+}
+
+fn normalize(rule: Rules) -> Result<Rules, String> {
+    match rule {
+        Rules::And { rules } => normalize_and(rules, false),
+        Rules::CompositeAnd { rules } => normalize_and(rules, true),
+        Rules::Or { rules } => normalize_or(rules, false),
+        Rules::CompositeOr { rules } => normalize_or(rules, true),
+        Rules::Composite { rule, condition } => {
+            if !has_count(&[&condition], 0, MAX_STAR_COUNT) {
+                return Err(format!("no number of stars satisfies {:?}", condition));
+            }
+            match normalize(*rule) {
+                Ok(rule) => Ok(Rules::Composite {
+                    rule: Box::new(rule),
+                    condition,
+                }),
+                // No star matches, so the count is always 0.
+                Err(_) if condition.eval(0.0) => Ok(always_galaxy()),
+                Err(reason) => Err(reason),
+            }
+        }
+        rule => {
+            let mut rules = vec![rule];
+            check_star(&mut rules)?;
+            Ok(rules.remove(0))
+        }
+    }
+}
+
+fn normalize_and(rules: Vec<Rules>, galaxy: bool) -> Result<Rules, String> {
+    let mut flat = vec![];
+    for rule in rules {
+        match normalize(rule)? {
+            Rules::And { rules } if !galaxy => flat.extend(rules),
+            Rules::CompositeAnd { rules } if galaxy => flat.extend(rules),
+            rule => flat.push(rule),
+        }
+    }
+    flat.retain(|rule| !is_always(rule));
+    dedup(&mut flat);
+    if !galaxy {
+        narrow_alternatives(&mut flat)?;
+        check_star(&mut flat)?;
+    }
+    Ok(match flat.len() {
+        1 => flat.remove(0),
+        _ if galaxy => Rules::CompositeAnd { rules: flat },
+        _ => Rules::And { rules: flat },
+    })
+}
+
+fn normalize_or(rules: Vec<Rules>, galaxy: bool) -> Result<Rules, String> {
+    let mut flat = vec![];
+    let mut reasons = vec![];
+    for rule in rules {
+        match normalize(rule) {
+            Ok(Rules::Or { rules }) if !galaxy => flat.extend(rules),
+            Ok(Rules::CompositeOr { rules }) if galaxy => flat.extend(rules),
+            Ok(rule) => flat.push(rule),
+            Err(reason) => reasons.push(reason),
+        }
+    }
+    if flat.iter().any(is_always) {
+        return Ok(if galaxy {
+            always_galaxy()
+        } else {
+            always_star()
+        });
+    }
+    dedup(&mut flat);
+    match flat.len() {
+        0 if reasons.is_empty() => Err("no alternatives".to_owned()),
+        0 => Err(format!("no alternative can match: {}", reasons.join("; "))),
+        1 => Ok(flat.remove(0)),
+        _ if galaxy => Ok(Rules::CompositeOr { rules: flat }),
+        _ => Ok(Rules::Or { rules: flat }),
+    }
+}
+
+/// Drops the alternatives of `Or`s that contradict the star type or spectral
+/// class required next to them.
+fn narrow_alternatives(rules: &mut Vec<Rules>) -> Result<(), String> {
+    let context: Vec<Rules> = rules
+        .iter()
+        .filter(|rule| matches!(rule, Rules::StarType(_) | Rules::Spectr(_)))
+        .cloned()
+        .collect();
+    if context.is_empty() {
+        return Ok(());
+    }
+    let fits = |alternative: &Rules| {
+        let mut rules = context.clone();
+        match alternative {
+            Rules::And { rules: all } => rules.extend(all.iter().cloned()),
+            Rules::Or { .. } => return true,
+            rule => rules.push(rule.clone()),
+        }
+        check_star(&mut rules).is_ok()
+    };
+    let mut narrowed = vec![];
+    for rule in rules.drain(..) {
+        match rule {
+            Rules::Or {
+                rules: alternatives,
+            } => {
+                let mut alternatives: Vec<Rules> = alternatives.into_iter().filter(fits).collect();
+                match alternatives.len() {
+                    0 => {
+                        return Err(
+                            "no alternative fits the required star type or spectral class"
+                                .to_owned(),
+                        )
+                    }
+                    1 => match alternatives.remove(0) {
+                        Rules::And { rules } => narrowed.extend(rules),
+                        rule => narrowed.push(rule),
+                    },
+                    _ => narrowed.push(Rules::Or {
+                        rules: alternatives,
+                    }),
+                }
+            }
+            rule => narrowed.push(rule),
+        }
+    }
+    *rules = narrowed;
+    dedup(rules);
+    Ok(())
+}
+
+/// What a rule with a single condition measures of a star.
+#[derive(Clone, Copy, PartialEq)]
+enum Measure {
+    /// A non-negative amount, e.g. luminosity or a distance.
+    Amount,
+    Planets,
+    /// Planets other than gas giants, or planets of some kind.
+    SomePlanets,
+    GasGiants,
+    Satellites,
+}
+
+fn measure(rule: &Rules) -> Option<(&Condition, Measure)> {
+    Some(match rule {
+        Rules::Luminosity(r) => (&r.condition, Measure::Amount),
+        Rules::DysonRadius(r) => (&r.condition, Measure::Amount),
+        Rules::BirthDistance(r) => (&r.condition, Measure::Amount),
+        Rules::HabitableRadius(r) => (&r.condition, Measure::Amount),
+        Rules::LightBalanceRadius(r) => (&r.condition, Measure::Amount),
+        Rules::OrbitScaler(r) => (&r.condition, Measure::Amount),
+        Rules::GasRate(r) => (&r.condition, Measure::Amount),
+        Rules::AverageVeinAmount(r) => (&r.condition, Measure::Amount),
+        Rules::PlanetCount(r) if r.exclude_giant => (&r.condition, Measure::SomePlanets),
+        Rules::PlanetCount(r) => (&r.condition, Measure::Planets),
+        Rules::GasCount(r) => (&r.condition, Measure::GasGiants),
+        Rules::SatelliteCount(r) => (&r.condition, Measure::Satellites),
+        Rules::TidalLockCount(r) => (&r.condition, Measure::SomePlanets),
+        Rules::HabitableZoneCount(r) => (&r.condition, Measure::SomePlanets),
+        Rules::RetrogradeCount(r) => (&r.condition, Measure::SomePlanets),
+        Rules::ResonanceCount(r) => (&r.condition, Measure::SomePlanets),
+        Rules::PlanetInDysonCount(r) => (&r.condition, Measure::SomePlanets),
+        _ => return None,
+    })
+}
+
+/// The possible counts for a star type, following `load_planets`.
+fn count_range(measure: Measure, star_type: &StarType) -> (u32, u32) {
+    let (planets, gas_giants, satellites) = match star_type {
+        StarType::MainSeqStar => (6, 6, 6),
+        StarType::GiantStar => (3, 1, 2),
+        StarType::WhiteDwarf => (2, 1, 1),
+        StarType::NeutronStar | StarType::BlackHole => (1, 0, 0),
+    };
+    match measure {
+        Measure::Planets => (1, planets),
+        Measure::SomePlanets => (0, planets),
+        Measure::GasGiants => (0, gas_giants),
+        Measure::Satellites => (0, satellites),
+        Measure::Amount => unreachable!("amounts are not counts"),
+    }
+}
+
+fn has_count(conditions: &[&Condition], low: u32, high: u32) -> bool {
+    (low..=high).any(|n| conditions.iter().all(|c| c.eval(n as f32)))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Bounds {
+    low: f32,
+    low_closed: bool,
+    high: f32,
+    high_closed: bool,
+}
+
+impl Bounds {
+    const ALL: Bounds = Bounds {
+        low: f32::NEG_INFINITY,
+        low_closed: false,
+        high: f32::INFINITY,
+        high_closed: false,
+    };
+
+    fn of(condition: &Condition) -> Option<Self> {
+        let all = Self::ALL;
+        Some(match *condition {
+            Condition::Eq(v) => Self::closed(v, v),
+            Condition::Lt(v) => Self { high: v, ..all },
+            Condition::Lte(v) => Self {
+                high: v,
+                high_closed: true,
+                ..all
+            },
+            Condition::Gt(v) => Self { low: v, ..all },
+            Condition::Gte(v) => Self {
+                low: v,
+                low_closed: true,
+                ..all
+            },
+            Condition::Between(low, high) => Self::closed(low, high),
+            Condition::Approx(v, tolerance) => Self::closed(v - tolerance, v + tolerance),
+            Condition::Interval {
+                min,
+                max,
+                min_open,
+                max_open,
+            } => Self {
+                low: min,
+                low_closed: !min_open,
+                high: max,
+                high_closed: !max_open,
+            },
+            Condition::Neq(_)
+            | Condition::NotBetween(_, _)
+            | Condition::In(_)
+            | Condition::NotIn(_) => return None,
+        })
+    }
+
+    fn closed(low: f32, high: f32) -> Self {
+        Self {
+            low,
+            low_closed: true,
+            high,
+            high_closed: true,
+        }
+    }
+
+    fn intersect(self, other: Self) -> Self {
+        let (low, low_closed) = if self.low == other.low {
+            (self.low, self.low_closed && other.low_closed)
+        } else if self.low > other.low {
+            (self.low, self.low_closed)
+        } else {
+            (other.low, other.low_closed)
+        };
+        let (high, high_closed) = if self.high == other.high {
+            (self.high, self.high_closed && other.high_closed)
+        } else if self.high < other.high {
+            (self.high, self.high_closed)
+        } else {
+            (other.high, other.high_closed)
+        };
+        Self {
+            low,
+            low_closed,
+            high,
+            high_closed,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.low > self.high || (self.low == self.high && !(self.low_closed && self.high_closed))
+    }
+}
+
+fn names<T: serde::Serialize>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|value| match serde_json::to_value(value) {
+            Ok(serde_json::Value::String(name)) => name,
+            _ => String::new(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Checks the star rules that must all hold for one star, and merges
+/// conditions on the same value into the tightest of them.
+fn check_star(rules: &mut Vec<Rules>) -> Result<(), String> {
+    let mut star_types = STAR_TYPES.to_vec();
+    let mut spectrs: Option<Vec<SpectrType>> = None;
+    for rule in rules.iter() {
+        match rule {
+            Rules::StarType(r) => star_types.retain(|t| r.star_type.contains(t)),
+            Rules::Spectr(r) => {
+                let allowed = spectrs.get_or_insert_with(|| r.spectr.clone());
+                allowed.retain(|s| r.spectr.contains(s));
+            }
+            Rules::ThemeId(r) if r.theme_ids.is_empty() => {
+                return Err("no theme is allowed".to_owned())
+            }
+            _ => {}
+        }
+    }
+    if let Some(spectrs) = &spectrs {
+        if spectrs.is_empty() {
+            return Err("no spectral class is allowed".to_owned());
+        }
+        // Only white dwarfs, neutron stars and black holes are of class X.
+        let x = spectrs.contains(&SpectrType::X);
+        let other = spectrs.iter().any(|s| *s != SpectrType::X);
+        star_types.retain(|t| {
+            let is_x = matches!(
+                t,
+                StarType::WhiteDwarf | StarType::NeutronStar | StarType::BlackHole
+            );
+            (is_x && x) || (!is_x && other)
+        });
+    }
+    if star_types.is_empty() {
+        return Err("no star type fits both the star type and the spectral class".to_owned());
+    }
+
+    // Conditions grouped by what they measure, with the rule's other fields.
+    let mut groups: Vec<(serde_json::Value, Measure, Vec<usize>)> = vec![];
+    for (index, rule) in rules.iter().enumerate() {
+        let Some((_, measure)) = self::measure(rule) else {
+            continue;
+        };
+        let mut key = serde_json::to_value(rule).unwrap_or_default();
+        if let Some(object) = key.as_object_mut() {
+            object.remove("condition");
+        }
+        match groups.iter_mut().find(|(k, _, _)| *k == key) {
+            Some((_, _, indexes)) => indexes.push(index),
+            None => groups.push((key, measure, vec![index])),
+        }
+    }
+
+    let mut dropped = vec![];
+    for (key, measure, indexes) in groups {
+        let label = key["type"].as_str().unwrap_or_default().to_owned();
+        let conditions: Vec<&Condition> = indexes
+            .iter()
+            .map(|&i| measure_condition(&rules[i]))
+            .collect();
+        if measure == Measure::Amount {
+            let bounds = conditions.iter().filter_map(|c| Bounds::of(c));
+            let merged = bounds.fold(Bounds::ALL, Bounds::intersect);
+            let merged = merged.intersect(Bounds::of(&Condition::Gte(0.0)).unwrap());
+            // A set of allowed values needs one that meets every condition.
+            let values = conditions.iter().find_map(|c| match c {
+                Condition::In(values) => Some(values),
+                _ => None,
+            });
+            let possible = match values {
+                Some(values) => values
+                    .iter()
+                    .any(|&v| v >= 0.0 && conditions.iter().all(|c| c.eval(v))),
+                None => !merged.is_empty(),
+            };
+            if !possible {
+                return Err(format!("the {} conditions contradict each other", label));
+            }
+        } else if !star_types.iter().any(|t| {
+            let (low, high) = count_range(measure, t);
+            has_count(&conditions, low, high)
+        }) {
+            let (low, high) = star_types
+                .iter()
+                .map(|t| count_range(measure, t))
+                .fold((u32::MAX, 0), |(l, h), (low, high)| {
+                    (l.min(low), h.max(high))
+                });
+            let unit = match measure {
+                Measure::GasGiants => "gas giants",
+                Measure::Satellites => "satellites",
+                _ => "planets",
+            };
+            return Err(format!(
+                "{} stars have {} to {} {}, so {} can never hold",
+                names(&star_types),
+                low,
+                high,
+                unit,
+                label
+            ));
+        }
+        dropped.extend(redundant(&indexes, &conditions));
+    }
+    let mut index = 0;
+    rules.retain(|_| {
+        index += 1;
+        !dropped.contains(&(index - 1))
+    });
+    Ok(())
+}
+
+fn measure_condition(rule: &Rules) -> &Condition {
+    measure(rule).map(|(condition, _)| condition).unwrap()
+}
+
+/// Rules of one group whose bounds are implied by the others: only the rules
+/// giving the tightest lower and upper bound are kept.
+fn redundant(indexes: &[usize], conditions: &[&Condition]) -> Vec<usize> {
+    let bounds: Vec<(usize, Bounds)> = indexes
+        .iter()
+        .zip(conditions)
+        .filter_map(|(&i, c)| Bounds::of(c).map(|b| (i, b)))
+        .collect();
+    let merged = bounds
+        .iter()
+        .fold(Bounds::ALL, |merged, (_, b)| merged.intersect(*b));
+    let keep: Vec<usize> = match bounds.iter().find(|(_, b)| *b == merged) {
+        Some((i, _)) => vec![*i],
+        None => {
+            let low = bounds
+                .iter()
+                .find(|(_, b)| (b.low, b.low_closed) == (merged.low, merged.low_closed));
+            let high = bounds
+                .iter()
+                .find(|(_, b)| (b.high, b.high_closed) == (merged.high, merged.high_closed));
+            low.into_iter().chain(high).map(|(i, _)| *i).collect()
+        }
+    };
+    bounds
+        .iter()
+        .map(|(i, _)| *i)
+        .filter(|i| !keep.contains(i))
+        .collect()
+}
@@ -0,0 +1,142 @@
+use super::QueryError;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Ident,
+    Number,
+    Str,
+    /// One of `=`, `==`, `!=`, `<`, `<=`, `>`, `>=`, `^=`, `~`, `~=` and `+-`.
+    Op,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    End,
+}
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// The source text, except for strings where it is the unescaped value.
+    pub text: String,
+    pub span: Range<usize>,
+}
+
+impl Token {
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Ident && self.text == keyword
+    }
+
+    pub fn is_op(&self, op: &str) -> bool {
+        self.kind == TokenKind::Op && self.text == op
+    }
+
+    pub fn describe(&self) -> String {
+        match self.kind {
+            TokenKind::End => "end of query".to_owned(),
+            TokenKind::Str => format!("\"{}\"", self.text),
+            _ => format!("`{}`", self.text),
+        }
+    }
+}
+
+pub fn tokenize(source: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = vec![];
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let (kind, text) = if c.is_ascii_alphabetic() || c == '_' {
+            let mut text = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                text.push(c);
+                chars.next();
+            }
+            (TokenKind::Ident, text)
+        } else if c.is_ascii_digit() || c == '-' || c == '.' {
+            let mut text = String::new();
+            let mut previous = ' ';
+            while let Some(&(_, c)) = chars.peek() {
+                let sign = (c == '-' || c == '+') && (text.is_empty() || previous == 'e');
+                if !(c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || sign) {
+                    break;
+                }
+                previous = c.to_ascii_lowercase();
+                text.push(c);
+                chars.next();
+            }
+            if text.parse::<f64>().is_err() {
+                return Err(QueryError::new(
+                    format!("Invalid number `{}`", text),
+                    start..start + text.len(),
+                ));
+            }
+            (TokenKind::Number, text)
+        } else if c == '"' {
+            chars.next();
+            let unterminated = || QueryError::new("Unterminated string", start..source.len());
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, c)) => text.push(c),
+                        None => return Err(unterminated()),
+                    },
+                    Some((_, c)) => text.push(c),
+                    None => return Err(unterminated()),
+                }
+            }
+            (TokenKind::Str, text)
+        } else {
+            chars.next();
+            let next = chars.peek().map(|&(_, c)| c);
+            let kind = match c {
+                '(' => TokenKind::LParen,
+                ')' => TokenKind::RParen,
+                '[' => TokenKind::LBracket,
+                ']' => TokenKind::RBracket,
+                ',' => TokenKind::Comma,
+                '=' | '<' | '>' | '~' => TokenKind::Op,
+                '!' | '^' if next == Some('=') => TokenKind::Op,
+                '+' if next == Some('-') => TokenKind::Op,
+                _ => {
+                    return Err(QueryError::new(
+                        format!("Unexpected character `{}`", c),
+                        start..start + c.len_utf8(),
+                    ))
+                }
+            };
+            let mut text = c.to_string();
+            // `-` only joins `+`, so `>-1` is `>` followed by a negative number.
+            let joined = match c {
+                '+' => next == Some('-'),
+                _ => kind == TokenKind::Op && next == Some('='),
+            };
+            if joined {
+                text.push(next.unwrap());
+                chars.next();
+            }
+            (kind, text)
+        };
+        let end = chars.peek().map_or(source.len(), |&(end, _)| end);
+        tokens.push(Token {
+            kind,
+            text,
+            span: start..end,
+        });
+    }
+    tokens.push(Token {
+        kind: TokenKind::End,
+        text: String::new(),
+        span: source.len()..source.len(),
+    });
+    Ok(tokens)
+}
@@ -0,0 +1,69 @@
+//! A text form of rule trees, e.g.
+//! `star where luminosity > 2 and gas(ice=true) >= 2 and dist(birth) < 10`.
+
+mod lexer;
+mod parser;
+mod printer;
+
+use crate::transform_rules::Rules;
+use serde::Serialize;
+use std::ops::Range;
+
+/// A query that could not be parsed. `span` is the byte range of the
+/// offending part of the query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QueryError {
+    pub message: String,
+    pub span: Range<usize>,
+}
+
+impl QueryError {
+    pub fn new(message: impl Into<String>, span: Range<usize>) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// The message followed by the line of the query it refers to, with the
+    /// span underlined.
+    pub fn render(&self, source: &str) -> String {
+        let start = self.span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let line = &source[line_start..line_end];
+        let column = source[line_start..start].chars().count();
+        let end = self.span.end.clamp(start, line_end);
+        let width = source[start..end].chars().count().max(1);
+        format!(
+            "{}\n  {}\n  {}{}",
+            self,
+            line,
+            " ".repeat(column),
+            "^".repeat(width)
+        )
+    }
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+pub fn parse_query(source: &str) -> Result<Rules, QueryError> {
+    parser::parse(source)
+}
+
+/// The query text of a rule tree; `parse_query` gives the same tree back.
+pub fn format_query(rule: &Rules) -> String {
+    printer::format(rule)
+}
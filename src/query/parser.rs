@@ -0,0 +1,669 @@
+use super::lexer::{tokenize, Token, TokenKind};
+use super::QueryError;
+use crate::data::rule::Condition;
+use crate::rules;
+use crate::rules::star_name::{NameMatch, RuleStarName, StarNamePattern};
+use crate::transform_rules::Rules;
+use serde::de::DeserializeOwned;
+use std::ops::Range;
+
+/// A literal in a query.
+#[derive(Debug, Clone)]
+enum Value {
+    Number(String),
+    Ident(String),
+    Str(String),
+}
+
+impl Value {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Number(text) => match text.parse::<i64>() {
+                Ok(n) => n.into(),
+                Err(_) => text.parse::<f64>().unwrap_or_default().into(),
+            },
+            Value::Ident(text) if text == "true" => true.into(),
+            Value::Ident(text) if text == "false" => false.into(),
+            Value::Ident(text) | Value::Str(text) => text.clone().into(),
+        }
+    }
+}
+
+/// What follows a predicate or a named argument.
+#[derive(Debug, Clone)]
+enum Test {
+    Condition(Condition),
+    Is(Value),
+    OneOf(Vec<Value>),
+    Prefix(String),
+    Regex(String),
+}
+
+#[derive(Debug)]
+enum Arg {
+    Positional(Value),
+    Named(String, Test),
+}
+
+/// A predicate such as `gas(ice=true) >= 2` before it is turned into a rule.
+struct Predicate {
+    name: String,
+    span: Range<usize>,
+    args: Vec<(Arg, Range<usize>)>,
+    test: Option<(Test, Range<usize>)>,
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str, span: &Range<usize>) -> Result<T, QueryError> {
+    text.parse()
+        .map_err(|_| QueryError::new(format!("Invalid number `{}`", text), span.clone()))
+}
+
+/// The values of `in (...)` used as a numeric condition.
+fn numbers(values: &[Value], span: &Range<usize>) -> Result<Vec<f32>, QueryError> {
+    values
+        .iter()
+        .map(|value| match value {
+            Value::Number(text) => parse_number(text, span),
+            _ => Err(QueryError::new("Expected numbers", span.clone())),
+        })
+        .collect()
+}
+
+fn convert<T: DeserializeOwned>(
+    value: &Value,
+    what: &str,
+    span: &Range<usize>,
+) -> Result<T, QueryError> {
+    serde_json::from_value(value.to_json()).map_err(|_| {
+        let text = match value {
+            Value::Number(text) | Value::Ident(text) | Value::Str(text) => text,
+        };
+        QueryError::new(format!("Invalid {} `{}`", what, text), span.clone())
+    })
+}
+
+impl Predicate {
+    fn error(&self, message: impl Into<String>) -> QueryError {
+        QueryError::new(message, self.span.clone())
+    }
+
+    fn take_test(&mut self) -> Result<(Test, Range<usize>), QueryError> {
+        self.test
+            .take()
+            .ok_or_else(|| self.error(format!("`{}` needs a comparison", self.name)))
+    }
+
+    fn condition(&mut self) -> Result<Condition, QueryError> {
+        match self.take_test()? {
+            (Test::Condition(condition), _) => Ok(condition),
+            (Test::OneOf(values), span) => Ok(Condition::In(numbers(&values, &span)?)),
+            (_, span) => Err(QueryError::new("Expected a numeric comparison", span)),
+        }
+    }
+
+    /// The values of `= value` or `in (value, ...)`.
+    fn values<T: DeserializeOwned>(&mut self, what: &str) -> Result<Vec<T>, QueryError> {
+        let (test, span) = self.take_test()?;
+        let values = match test {
+            Test::Is(value) => vec![value],
+            Test::OneOf(values) => values,
+            Test::Condition(Condition::Eq(n)) => vec![Value::Number(n.to_string())],
+            _ => return Err(QueryError::new("Expected `= value` or `in (...)`", span)),
+        };
+        values.iter().map(|v| convert(v, what, &span)).collect()
+    }
+
+    fn positional<T: DeserializeOwned>(&mut self, what: &str) -> Result<T, QueryError> {
+        let position = self
+            .args
+            .iter()
+            .position(|(arg, _)| matches!(arg, Arg::Positional(_)))
+            .ok_or_else(|| self.error(format!("`{}` needs the {}", self.name, what)))?;
+        match self.args.remove(position) {
+            (Arg::Positional(value), span) => convert(&value, what, &span),
+            _ => unreachable!(),
+        }
+    }
+
+    fn named(&mut self, key: &str) -> Option<(Test, Range<usize>)> {
+        let position = self
+            .args
+            .iter()
+            .position(|(arg, _)| matches!(arg, Arg::Named(k, _) if k == key))?;
+        match self.args.remove(position) {
+            (Arg::Named(_, test), span) => Some((test, span)),
+            _ => None,
+        }
+    }
+
+    fn named_value<T: DeserializeOwned>(&mut self, key: &str) -> Result<Option<T>, QueryError> {
+        match self.named(key) {
+            None => Ok(None),
+            Some((Test::Is(value), span)) => convert(&value, key, &span).map(Some),
+            Some((Test::Condition(Condition::Eq(n)), span)) => {
+                convert(&Value::Number(n.to_string()), key, &span).map(Some)
+            }
+            Some((_, span)) => Err(QueryError::new(format!("Expected `{}=value`", key), span)),
+        }
+    }
+
+    fn named_condition(&mut self, key: &str) -> Result<Option<Condition>, QueryError> {
+        match self.named(key) {
+            None => Ok(None),
+            Some((Test::Condition(condition), _)) => Ok(Some(condition)),
+            Some((_, span)) => Err(QueryError::new(
+                format!("Expected a numeric comparison for `{}`", key),
+                span,
+            )),
+        }
+    }
+
+    /// Reports arguments and comparisons the predicate does not take.
+    fn finish(self, rule: Rules) -> Result<Rules, QueryError> {
+        if let Some((arg, span)) = self.args.into_iter().next() {
+            let message = match arg {
+                Arg::Named(key, _) => format!("`{}` does not take `{}`", self.name, key),
+                Arg::Positional(_) => format!("Unexpected argument to `{}`", self.name),
+            };
+            return Err(QueryError::new(message, span));
+        }
+        if let Some((_, span)) = self.test {
+            return Err(QueryError::new(
+                format!("`{}` does not take a comparison", self.name),
+                span,
+            ));
+        }
+        Ok(rule)
+    }
+
+    fn build(mut self) -> Result<Rules, QueryError> {
+        let rule = match self.name.as_str() {
+            "all" => Rules::And { rules: vec![] },
+            "none" => Rules::Or { rules: vec![] },
+            "birth" => Rules::Birth(rules::birth::RuleBirth {}),
+            "luminosity" => Rules::Luminosity(rules::luminosity::RuleLuminosity {
+                condition: self.condition()?,
+            }),
+            "dyson_radius" => Rules::DysonRadius(rules::dyson_radius::RuleDysonRadius {
+                condition: self.condition()?,
+            }),
+            "habitable_radius" => {
+                Rules::HabitableRadius(rules::habitable_radius::RuleHabitableRadius {
+                    condition: self.condition()?,
+                })
+            }
+            "light_balance_radius" => {
+                Rules::LightBalanceRadius(rules::light_balance_radius::RuleLightBalanceRadius {
+                    condition: self.condition()?,
+                })
+            }
+            "orbit_scaler" => Rules::OrbitScaler(rules::orbit_scaler::RuleOrbitScaler {
+                condition: self.condition()?,
+            }),
+            "spectr" => Rules::Spectr(rules::spectr::RuleSpectr {
+                spectr: self.values("spectral class")?,
+            }),
+            "star_type" => Rules::StarType(rules::star_type::RuleStarType {
+                star_type: self.values("star type")?,
+            }),
+            "name" => {
+                let ignore_case = self.named_value("ignore_case")?.unwrap_or(false);
+                let (test, span) = self.take_test()?;
+                let (name, mode) = match test {
+                    Test::Is(Value::Str(name)) => (name, NameMatch::Exact),
+                    Test::Prefix(name) => (name, NameMatch::Prefix),
+                    Test::Regex(name) => (name, NameMatch::Regex),
+                    _ => {
+                        return Err(QueryError::new(
+                            "Expected `= \"name\"`, `^= \"prefix\"` or `~ \"regex\"`",
+                            span,
+                        ))
+                    }
+                };
+                let pattern = StarNamePattern {
+                    name,
+                    mode,
+                    ignore_case,
+                };
+                Rules::StarName(
+                    RuleStarName::try_from(pattern).map_err(|err| QueryError::new(err, span))?,
+                )
+            }
+            "dist" => {
+                let (target, span) = match self.args.first() {
+                    Some((Arg::Positional(Value::Ident(target)), span)) => {
+                        (target.clone(), span.clone())
+                    }
+                    _ => {
+                        return Err(self.error(
+                            "Expected `dist(birth)`, `dist(x)` or `dist(<spectral class>)`",
+                        ))
+                    }
+                };
+                self.args.remove(0);
+                match target.as_str() {
+                    "birth" => Rules::BirthDistance(rules::birth_distance::RuleBirthDistance {
+                        condition: self.condition()?,
+                    }),
+                    "x" => Rules::XDistance(rules::x_distance::RuleXDistance {
+                        all: self.named_value("all")?.unwrap_or(false),
+                        condition: self.condition()?,
+                    }),
+                    _ => Rules::SpectrDistance(rules::spectr_distance::RuleSpectrDistance {
+                        spectr: convert(&Value::Ident(target), "spectral class", &span)?,
+                        count_condition: self
+                            .named_condition("count")?
+                            .unwrap_or(Condition::Gte(1.0)),
+                        distance_condition: self.condition()?,
+                    }),
+                }
+            }
+            "planets" => Rules::PlanetCount(rules::planet_count::RulePlanetCount {
+                exclude_giant: self.named_value("exclude_giant")?.unwrap_or(false),
+                condition: self.condition()?,
+            }),
+            "satellites" => Rules::SatelliteCount(rules::satellite_count::RuleSatelliteCount {
+                condition: self.condition()?,
+            }),
+            "gas" => Rules::GasCount(rules::gas_count::RuleGasCount {
+                ice: self.named_value("ice")?,
+                condition: self.condition()?,
+            }),
+            "tidal_locked" => Rules::TidalLockCount(rules::tidal_lock_count::RuleTidalLockCount {
+                condition: self.condition()?,
+            }),
+            "planets_in_dyson" => {
+                Rules::PlanetInDysonCount(rules::planet_in_dyson_count::RulePlanetInDysonCount {
+                    include_giant: self.named_value("include_giant")?.unwrap_or(false),
+                    condition: self.condition()?,
+                })
+            }
+            "habitable_zone" => {
+                Rules::HabitableZoneCount(rules::habitable_zone_count::RuleHabitableZoneCount {
+                    condition: self.condition()?,
+                })
+            }
+            "rotation_period" => {
+                Rules::RotationPeriod(rules::rotation_period::RuleRotationPeriod {
+                    count: self
+                        .named_condition("count")?
+                        .unwrap_or_else(rules::rotation_period::RuleRotationPeriod::default_count),
+                    include_giant: self.named_value("include_giant")?.unwrap_or(false),
+                    condition: self.condition()?,
+                })
+            }
+            "retrograde" => Rules::RetrogradeCount(rules::retrograde_count::RuleRetrogradeCount {
+                condition: self.condition()?,
+            }),
+            "resonance" => Rules::ResonanceCount(rules::resonance_count::RuleResonanceCount {
+                resonance: self.positional("resonance")?,
+                condition: self.condition()?,
+            }),
+            "orbital_ratio" => Rules::OrbitalRatio(rules::orbital_ratio::RuleOrbitalRatio {
+                ratio: self.positional("ratio")?,
+                tolerance: self
+                    .named_value("tolerance")?
+                    .unwrap_or_else(rules::orbital_ratio::RuleOrbitalRatio::default_tolerance),
+            }),
+            "theme" => Rules::ThemeId(rules::theme_id::RuleThemeId {
+                theme_ids: self.values("theme id")?,
+            }),
+            "ocean" => {
+                let mut oceans = self.values("ocean type")?;
+                if oceans.len() != 1 {
+                    return Err(self.error("Expected a single ocean type"));
+                }
+                Rules::OceanType(rules::ocean_type::RuleOceanType {
+                    ocean_type: oceans.remove(0),
+                })
+            }
+            "gas_rate" => Rules::GasRate(rules::gas_rate::RuleGasRate {
+                gas_type: self.positional("gas")?,
+                condition: self.condition()?,
+            }),
+            "vein" => Rules::AverageVeinAmount(rules::average_vein_amount::RuleAverageVeinAmount {
+                vein: self.positional("vein")?,
+                condition: self.condition()?,
+            }),
+            _ => return Err(self.error(format!("Unknown condition `{}`", self.name))),
+        };
+        self.finish(rule)
+    }
+}
+
+/// A parsed expression and whether it is about the galaxy (built from
+/// `count(...)`) rather than single stars.
+struct Node {
+    rule: Rules,
+    galaxy: bool,
+    span: Range<usize>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position]
+    }
+
+    fn peek_at(&self, offset: usize) -> &Token {
+        &self.tokens[(self.position + offset).min(self.tokens.len() - 1)]
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.position].clone();
+        if token.kind != TokenKind::End {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn unexpected(&self, expected: &str) -> QueryError {
+        let token = self.peek();
+        QueryError::new(
+            format!("Expected {}, found {}", expected, token.describe()),
+            token.span.clone(),
+        )
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &str) -> Result<Token, QueryError> {
+        if self.peek().kind == kind {
+            Ok(self.next())
+        } else {
+            Err(self.unexpected(expected))
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek().is_keyword(keyword) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn query(&mut self) -> Result<Rules, QueryError> {
+        let expect_galaxy = match self.peek() {
+            token if token.is_keyword("star") && self.peek_at(1).is_keyword("where") => Some(false),
+            token if token.is_keyword("galaxy") && self.peek_at(1).is_keyword("where") => {
+                Some(true)
+            }
+            _ => None,
+        };
+        if expect_galaxy.is_some() {
+            self.position += 2;
+        }
+        let node = self.or()?;
+        if self.peek().kind != TokenKind::End {
+            return Err(self.unexpected("`and`, `or` or end of query"));
+        }
+        match expect_galaxy {
+            // `all` and `none` fit both kinds of query.
+            Some(true) => match node.rule {
+                Rules::And { rules } if rules.is_empty() => Ok(Rules::CompositeAnd { rules }),
+                Rules::Or { rules } if rules.is_empty() => Ok(Rules::CompositeOr { rules }),
+                _ if !node.galaxy => Err(QueryError::new(
+                    "A galaxy query needs `count(...)` conditions",
+                    node.span,
+                )),
+                rule => Ok(rule),
+            },
+            Some(false) if node.galaxy => Err(QueryError::new(
+                "`count(...)` can only be used in a galaxy query",
+                node.span,
+            )),
+            _ => Ok(node.rule),
+        }
+    }
+
+    fn combine(nodes: Vec<Node>, and: bool) -> Result<Node, QueryError> {
+        if nodes.len() == 1 {
+            return Ok(nodes.into_iter().next().unwrap());
+        }
+        let galaxy = nodes[0].galaxy;
+        if let Some(node) = nodes.iter().find(|node| node.galaxy != galaxy) {
+            return Err(QueryError::new(
+                "Cannot combine star conditions with `count(...)`, wrap them in `count(...)`",
+                node.span.clone(),
+            ));
+        }
+        let span = nodes[0].span.start..nodes[nodes.len() - 1].span.end;
+        let rules = nodes.into_iter().map(|node| node.rule).collect();
+        let rule = match (galaxy, and) {
+            (false, true) => Rules::And { rules },
+            (false, false) => Rules::Or { rules },
+            (true, true) => Rules::CompositeAnd { rules },
+            (true, false) => Rules::CompositeOr { rules },
+        };
+        Ok(Node { rule, galaxy, span })
+    }
+
+    fn or(&mut self) -> Result<Node, QueryError> {
+        let mut nodes = vec![self.and()?];
+        while self.eat_keyword("or") {
+            nodes.push(self.and()?);
+        }
+        Self::combine(nodes, false)
+    }
+
+    fn and(&mut self) -> Result<Node, QueryError> {
+        let mut nodes = vec![self.atom()?];
+        while self.eat_keyword("and") {
+            nodes.push(self.atom()?);
+        }
+        Self::combine(nodes, true)
+    }
+
+    fn atom(&mut self) -> Result<Node, QueryError> {
+        let start = self.peek().span.start;
+        if self.peek().kind == TokenKind::LParen {
+            self.next();
+            let node = self.or()?;
+            let end = self.expect(TokenKind::RParen, "`)`")?.span.end;
+            return Ok(Node {
+                span: start..end,
+                ..node
+            });
+        }
+        let name = self.expect(TokenKind::Ident, "a condition")?;
+        if name.text == "count" && self.peek().kind == TokenKind::LParen {
+            self.next();
+            let inner = self.or()?;
+            if inner.galaxy {
+                return Err(QueryError::new("`count(...)` cannot be nested", inner.span));
+            }
+            self.expect(TokenKind::RParen, "`)`")?;
+            let (test, span) = self
+                .test()?
+                .ok_or_else(|| self.unexpected("a comparison"))?;
+            let Test::Condition(condition) = test else {
+                return Err(QueryError::new("Expected a numeric comparison", span));
+            };
+            return Ok(Node {
+                rule: Rules::Composite {
+                    rule: Box::new(inner.rule),
+                    condition,
+                },
+                galaxy: true,
+                span: start..span.end,
+            });
+        }
+        let mut predicate = Predicate {
+            name: name.text,
+            span: name.span.clone(),
+            args: vec![],
+            test: None,
+        };
+        if self.peek().kind == TokenKind::LParen {
+            self.next();
+            while self.peek().kind != TokenKind::RParen {
+                predicate.args.push(self.arg()?);
+                if self.peek().kind != TokenKind::Comma {
+                    break;
+                }
+                self.next();
+            }
+            self.expect(TokenKind::RParen, "`,` or `)`")?;
+        }
+        predicate.test = self.test()?;
+        let end = match &predicate.test {
+            Some((_, span)) => span.end,
+            None => self.tokens[self.position - 1].span.end,
+        };
+        let rule = predicate.build()?;
+        Ok(Node {
+            rule,
+            galaxy: false,
+            span: start..end,
+        })
+    }
+
+    fn arg(&mut self) -> Result<(Arg, Range<usize>), QueryError> {
+        let start = self.peek().span.start;
+        if self.peek().kind == TokenKind::Ident && self.starts_test(1) {
+            let key = self.next().text;
+            let (test, span) = self.test()?.unwrap();
+            return Ok((Arg::Named(key, test), start..span.end));
+        }
+        let token = self.next();
+        let value = self.value(token, "an argument")?;
+        Ok((
+            Arg::Positional(value),
+            start..self.tokens[self.position - 1].span.end,
+        ))
+    }
+
+    fn value(&self, token: Token, expected: &str) -> Result<Value, QueryError> {
+        match token.kind {
+            TokenKind::Number => Ok(Value::Number(token.text)),
+            TokenKind::Ident => Ok(Value::Ident(token.text)),
+            TokenKind::Str => Ok(Value::Str(token.text)),
+            _ => Err(QueryError::new(
+                format!("Expected {}, found {}", expected, token.describe()),
+                token.span,
+            )),
+        }
+    }
+
+    fn number(&mut self) -> Result<f32, QueryError> {
+        let token = self.expect(TokenKind::Number, "a number")?;
+        parse_number(&token.text, &token.span)
+    }
+
+    fn starts_test(&self, offset: usize) -> bool {
+        let token = self.peek_at(offset);
+        token.kind == TokenKind::Op || token.is_keyword("in") || token.is_keyword("not")
+    }
+
+    fn test(&mut self) -> Result<Option<(Test, Range<usize>)>, QueryError> {
+        if !self.starts_test(0) {
+            return Ok(None);
+        }
+        let start = self.peek().span.start;
+        let token = self.next();
+        let test = if token.kind == TokenKind::Op {
+            match token.text.as_str() {
+                "=" | "==" => {
+                    let token = self.next();
+                    match token.kind {
+                        TokenKind::Number => {
+                            Test::Condition(Condition::Eq(parse_number(&token.text, &token.span)?))
+                        }
+                        _ => Test::Is(self.value(token, "a value")?),
+                    }
+                }
+                "!=" => Test::Condition(Condition::Neq(self.number()?)),
+                "<" => Test::Condition(Condition::Lt(self.number()?)),
+                "<=" => Test::Condition(Condition::Lte(self.number()?)),
+                ">" => Test::Condition(Condition::Gt(self.number()?)),
+                ">=" => Test::Condition(Condition::Gte(self.number()?)),
+                "^=" => Test::Prefix(self.expect(TokenKind::Str, "a string")?.text),
+                "~" => Test::Regex(self.expect(TokenKind::Str, "a string")?.text),
+                "~=" => {
+                    let value = self.number()?;
+                    if !self.peek().is_op("+-") {
+                        return Err(self.unexpected("`+-` and a tolerance"));
+                    }
+                    self.next();
+                    Test::Condition(Condition::Approx(value, self.number()?))
+                }
+                _ => {
+                    return Err(QueryError::new(
+                        format!("Expected a comparison, found {}", token.describe()),
+                        token.span,
+                    ))
+                }
+            }
+        } else {
+            let negated = token.is_keyword("not");
+            if negated && !self.eat_keyword("in") {
+                return Err(self.unexpected("`in`"));
+            }
+            if matches!(self.peek().kind, TokenKind::LBracket | TokenKind::RBracket) {
+                // `[` and `]` include a bound, `]` and `[` exclude it.
+                let min_open = self.next().kind == TokenKind::RBracket;
+                let min = self.number()?;
+                self.expect(TokenKind::Comma, "`,`")?;
+                let max = self.number()?;
+                let close = self.peek().clone();
+                let max_open = match close.kind {
+                    TokenKind::RBracket => false,
+                    TokenKind::LBracket => true,
+                    _ => return Err(self.unexpected("`]` or `[`")),
+                };
+                self.next();
+                match (negated, min_open || max_open) {
+                    (false, false) => Test::Condition(Condition::Between(min, max)),
+                    (true, false) => Test::Condition(Condition::NotBetween(min, max)),
+                    (false, true) => Test::Condition(Condition::Interval {
+                        min,
+                        max,
+                        min_open,
+                        max_open,
+                    }),
+                    (true, true) => {
+                        return Err(QueryError::new(
+                            "`not in` needs both bounds included",
+                            start..close.span.end,
+                        ))
+                    }
+                }
+            } else if self.peek().kind == TokenKind::LParen {
+                self.next();
+                let mut values = vec![];
+                while self.peek().kind != TokenKind::RParen {
+                    let token = self.next();
+                    values.push(self.value(token, "a value")?);
+                    if self.peek().kind != TokenKind::Comma {
+                        break;
+                    }
+                    self.next();
+                }
+                self.expect(TokenKind::RParen, "`,` or `)`")?;
+                if negated {
+                    Test::Condition(Condition::NotIn(numbers(
+                        &values,
+                        &(start..self.tokens[self.position - 1].span.end),
+                    )?))
+                } else {
+                    Test::OneOf(values)
+                }
+            } else {
+                return Err(self.unexpected("`[` or `(`"));
+            }
+        };
+        Ok(Some((test, start..self.tokens[self.position - 1].span.end)))
+    }
+}
+
+pub fn parse(source: &str) -> Result<Rules, QueryError> {
+    Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+    }
+    .query()
+}
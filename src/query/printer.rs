@@ -0,0 +1,220 @@
+use crate::data::rule::Condition;
+use crate::rules::star_name::NameMatch;
+use crate::transform_rules::Rules;
+use serde::Serialize;
+
+fn test(condition: &Condition) -> String {
+    match condition {
+        Condition::Eq(n) => format!("= {}", n),
+        Condition::Neq(n) => format!("!= {}", n),
+        Condition::Lt(n) => format!("< {}", n),
+        Condition::Lte(n) => format!("<= {}", n),
+        Condition::Gt(n) => format!("> {}", n),
+        Condition::Gte(n) => format!(">= {}", n),
+        Condition::Between(low, high) => format!("in [{}, {}]", low, high),
+        Condition::NotBetween(low, high) => format!("not in [{}, {}]", low, high),
+        Condition::In(values) => format!("in ({})", numbers(values)),
+        Condition::NotIn(values) => format!("not in ({})", numbers(values)),
+        Condition::Approx(value, tolerance) => format!("~= {} +- {}", value, tolerance),
+        Condition::Interval {
+            min,
+            max,
+            min_open,
+            max_open,
+        } => format!(
+            "in {}{}, {}{}",
+            if *min_open { ']' } else { '[' },
+            min,
+            max,
+            if *max_open { '[' } else { ']' }
+        ),
+    }
+}
+
+fn numbers(values: &[f32]) -> String {
+    values
+        .iter()
+        .map(f32::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Enums are written by their serialized name, which the parser reads back.
+fn name(value: &impl Serialize) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        Ok(value) => value.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+fn string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn one_of(values: Vec<String>) -> String {
+    if values.len() == 1 {
+        format!("= {}", values[0])
+    } else {
+        format!("in ({})", values.join(", "))
+    }
+}
+
+fn predicate(name: &str, args: &[String], test: &str) -> String {
+    let mut out = name.to_owned();
+    if !args.is_empty() {
+        out += &format!("({})", args.join(", "));
+    }
+    if !test.is_empty() {
+        out += " ";
+        out += test;
+    }
+    out
+}
+
+fn flag(args: &mut Vec<String>, key: &str, value: bool) {
+    if value {
+        args.push(format!("{}=true", key));
+    }
+}
+
+fn join(rules: &[Rules], separator: &str, wrap: impl Fn(&Rules) -> bool) -> String {
+    rules
+        .iter()
+        .map(|rule| {
+            let text = expression(rule);
+            if wrap(rule) {
+                format!("({})", text)
+            } else {
+                text
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn expression(rule: &Rules) -> String {
+    match rule {
+        Rules::And { rules } | Rules::CompositeAnd { rules } if rules.is_empty() => {
+            "all".to_owned()
+        }
+        Rules::Or { rules } | Rules::CompositeOr { rules } if rules.is_empty() => "none".to_owned(),
+        Rules::And { rules } | Rules::CompositeAnd { rules } => join(rules, " and ", |rule| {
+            matches!(
+                rule,
+                Rules::And { .. }
+                    | Rules::Or { .. }
+                    | Rules::CompositeAnd { .. }
+                    | Rules::CompositeOr { .. }
+            )
+        }),
+        Rules::Or { rules } | Rules::CompositeOr { rules } => join(rules, " or ", |rule| {
+            matches!(rule, Rules::Or { .. } | Rules::CompositeOr { .. })
+        }),
+        Rules::Composite { rule, condition } => {
+            format!("count({}) {}", expression(rule), test(condition))
+        }
+        Rules::Luminosity(r) => predicate("luminosity", &[], &test(&r.condition)),
+        Rules::DysonRadius(r) => predicate("dyson_radius", &[], &test(&r.condition)),
+        Rules::AverageVeinAmount(r) => predicate("vein", &[name(&r.vein)], &test(&r.condition)),
+        Rules::Spectr(r) => predicate("spectr", &[], &one_of(r.spectr.iter().map(name).collect())),
+        Rules::TidalLockCount(r) => predicate("tidal_locked", &[], &test(&r.condition)),
+        Rules::OceanType(r) => predicate("ocean", &[], &format!("= {}", name(&r.ocean_type))),
+        Rules::StarType(r) => predicate(
+            "star_type",
+            &[],
+            &one_of(r.star_type.iter().map(name).collect()),
+        ),
+        Rules::GasCount(r) => {
+            let args = match r.ice {
+                Some(ice) => vec![format!("ice={}", ice)],
+                None => vec![],
+            };
+            predicate("gas", &args, &test(&r.condition))
+        }
+        Rules::SatelliteCount(r) => predicate("satellites", &[], &test(&r.condition)),
+        Rules::Birth(_) => "birth".to_owned(),
+        Rules::ThemeId(r) => predicate(
+            "theme",
+            &[],
+            &one_of(r.theme_ids.iter().map(|id| id.to_string()).collect()),
+        ),
+        Rules::PlanetCount(r) => {
+            let mut args = vec![];
+            flag(&mut args, "exclude_giant", r.exclude_giant);
+            predicate("planets", &args, &test(&r.condition))
+        }
+        Rules::BirthDistance(r) => predicate("dist", &["birth".to_owned()], &test(&r.condition)),
+        Rules::XDistance(r) => {
+            let mut args = vec!["x".to_owned()];
+            flag(&mut args, "all", r.all);
+            predicate("dist", &args, &test(&r.condition))
+        }
+        Rules::SpectrDistance(r) => {
+            let mut args = vec![name(&r.spectr)];
+            if r.count_condition != Condition::Gte(1.0) {
+                args.push(format!("count {}", test(&r.count_condition)));
+            }
+            predicate("dist", &args, &test(&r.distance_condition))
+        }
+        Rules::GasRate(r) => predicate("gas_rate", &[name(&r.gas_type)], &test(&r.condition)),
+        Rules::PlanetInDysonCount(r) => {
+            let mut args = vec![];
+            flag(&mut args, "include_giant", r.include_giant);
+            predicate("planets_in_dyson", &args, &test(&r.condition))
+        }
+        Rules::StarName(r) => {
+            let mut args = vec![];
+            flag(&mut args, "ignore_case", r.pattern.ignore_case);
+            let op = match r.pattern.mode {
+                NameMatch::Exact => "=",
+                NameMatch::Prefix => "^=",
+                NameMatch::Regex => "~",
+            };
+            predicate(
+                "name",
+                &args,
+                &format!("{} {}", op, string(&r.pattern.name)),
+            )
+        }
+        Rules::HabitableRadius(r) => predicate("habitable_radius", &[], &test(&r.condition)),
+        Rules::LightBalanceRadius(r) => predicate("light_balance_radius", &[], &test(&r.condition)),
+        Rules::OrbitScaler(r) => predicate("orbit_scaler", &[], &test(&r.condition)),
+        Rules::HabitableZoneCount(r) => predicate("habitable_zone", &[], &test(&r.condition)),
+        Rules::RotationPeriod(r) => {
+            let mut args = vec![];
+            if r.count != crate::rules::rotation_period::RuleRotationPeriod::default_count() {
+                args.push(format!("count {}", test(&r.count)));
+            }
+            flag(&mut args, "include_giant", r.include_giant);
+            predicate("rotation_period", &args, &test(&r.condition))
+        }
+        Rules::RetrogradeCount(r) => predicate("retrograde", &[], &test(&r.condition)),
+        Rules::ResonanceCount(r) => {
+            predicate("resonance", &[name(&r.resonance)], &test(&r.condition))
+        }
+        Rules::OrbitalRatio(r) => {
+            let mut args = vec![r.ratio.to_string()];
+            if r.tolerance != crate::rules::orbital_ratio::RuleOrbitalRatio::default_tolerance() {
+                args.push(format!("tolerance={}", r.tolerance));
+            }
+            predicate("orbital_ratio", &args, "")
+        }
+    }
+}
+
+fn is_galaxy_level(rule: &Rules) -> bool {
+    matches!(
+        rule,
+        Rules::Composite { .. } | Rules::CompositeAnd { .. } | Rules::CompositeOr { .. }
+    )
+}
+
+pub fn format(rule: &Rules) -> String {
+    let target = if is_galaxy_level(rule) {
+        "galaxy"
+    } else {
+        "star"
+    };
+    format!("{} where {}", target, expression(rule))
+}
@@ -0,0 +1,383 @@
+use crate::data::enums::{GasType, OceanType, SpectrType, StarType, VeinType};
+use crate::data::galaxy::Galaxy;
+use crate::data::locale::{fill, star_kind_name, Label, Locale, Localize};
+use crate::data::star_planets::StarWithPlanets;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Alignment {
+    /// The n-th closest star to birth is compared with the n-th closest star of the other seeds.
+    DistanceRank,
+    /// Stars are grouped by type and spectrum, then ranked by distance within the group.
+    StarType,
+}
+
+#[derive(Debug, Clone)]
+pub struct CompareOptions {
+    pub alignment: Alignment,
+    /// Radius (in light years) around birth for counting O/B stars.
+    pub nearby_distance: f64,
+    /// Maximum number of aligned rows to report, 0 means all.
+    pub max_rows: usize,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        Self {
+            alignment: Alignment::DistanceRank,
+            nearby_distance: 10.0,
+            max_rows: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StarSummary {
+    pub index: usize,
+    pub name: String,
+    pub star_type: StarType,
+    pub spectr: SpectrType,
+    pub distance: f64,
+    pub luminosity: f32,
+    pub dyson_radius: i32,
+    pub planet_count: usize,
+    pub tidal_locked: usize,
+    pub rare_veins: Vec<VeinType>,
+    pub ocean_types: Vec<OceanType>,
+    pub gas_rates: Vec<(GasType, f32)>,
+}
+
+impl StarSummary {
+    pub fn new(sp: &StarWithPlanets) -> Self {
+        let star = &sp.star;
+        let mut rare_veins: Vec<VeinType> = vec![];
+        let mut ocean_types: Vec<OceanType> = vec![];
+        let mut gas_rates: BTreeMap<GasType, f32> = BTreeMap::new();
+        let mut planet_count = 0;
+        let mut tidal_locked = 0;
+        for planet in sp.get_planets() {
+            planet_count += 1;
+            if planet.is_tidal_locked() {
+                tidal_locked += 1;
+            }
+            let water_item_id = planet.get_theme().water_item_id;
+            if water_item_id.is_pumpable() && !ocean_types.contains(&water_item_id) {
+                ocean_types.push(water_item_id);
+            }
+            for (item, rate) in planet.get_gases() {
+                *gas_rates.entry(*item).or_default() += *rate;
+            }
+            for vein in planet.get_veins() {
+                if vein.vein_type.is_rare() && !rare_veins.contains(&vein.vein_type) {
+                    rare_veins.push(vein.vein_type.clone());
+                }
+            }
+        }
+        rare_veins.sort_by_key(|v| v.clone() as i32);
+        ocean_types.sort();
+        Self {
+            index: star.index,
+            name: sp.name.clone(),
+            star_type: star.star_type.clone(),
+            spectr: star.get_spectr(),
+            distance: star.position.magnitude(),
+            luminosity: star.get_luminosity(),
+            dyson_radius: star.get_dyson_radius(),
+            planet_count,
+            tidal_locked,
+            rare_veins,
+            ocean_types,
+            gas_rates: gas_rates.into_iter().collect(),
+        }
+    }
+
+    fn group_key(&self) -> String {
+        match self.star_type {
+            StarType::MainSeqStar => format!("{:?}", self.spectr),
+            StarType::GiantStar => format!("Giant {:?}", self.spectr),
+            _ => format!("{:?}", self.star_type),
+        }
+    }
+
+    /// Names of the metrics in which `self` and `other` differ.
+    fn differences(&self, other: &Self) -> Vec<&'static str> {
+        let mut result = vec![];
+        if self.star_type != other.star_type {
+            result.push("starType");
+        }
+        if self.spectr != other.spectr {
+            result.push("spectr");
+        }
+        if (self.luminosity - other.luminosity).abs() > 0.005 {
+            result.push("luminosity");
+        }
+        if self.dyson_radius != other.dyson_radius {
+            result.push("dysonRadius");
+        }
+        if self.planet_count != other.planet_count {
+            result.push("planetCount");
+        }
+        if self.tidal_locked != other.tidal_locked {
+            result.push("tidalLocked");
+        }
+        if self.rare_veins != other.rare_veins {
+            result.push("rareVeins");
+        }
+        if self.ocean_types != other.ocean_types {
+            result.push("oceanTypes");
+        }
+        let gas_differs = self.gas_rates.len() != other.gas_rates.len()
+            || self
+                .gas_rates
+                .iter()
+                .zip(other.gas_rates.iter())
+                .any(|((i1, r1), (i2, r2))| i1 != i2 || (r1 - r2).abs() > 0.005);
+        if gas_differs {
+            result.push("gasRates");
+        }
+        result
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GalaxySummary {
+    pub seed: i32,
+    pub star_count: usize,
+    pub nearby_ob_count: usize,
+    pub rare_veins: Vec<VeinType>,
+    pub tidal_locked: usize,
+    pub max_dyson_radius: i32,
+    pub stars: Vec<StarSummary>,
+}
+
+impl GalaxySummary {
+    pub fn new(galaxy: &Galaxy, nearby_distance: f64) -> Self {
+        let stars: Vec<StarSummary> = galaxy.stars.iter().map(StarSummary::new).collect();
+        let mut rare_veins: Vec<VeinType> = vec![];
+        for star in &stars {
+            for vein in &star.rare_veins {
+                if !rare_veins.contains(vein) {
+                    rare_veins.push(vein.clone());
+                }
+            }
+        }
+        rare_veins.sort_by_key(|v| v.clone() as i32);
+        Self {
+            seed: galaxy.seed,
+            star_count: stars.len(),
+            nearby_ob_count: stars
+                .iter()
+                .filter(|s| {
+                    matches!(s.spectr, SpectrType::O | SpectrType::B)
+                        && s.distance <= nearby_distance
+                })
+                .count(),
+            rare_veins,
+            tidal_locked: stars.iter().map(|s| s.tidal_locked).sum(),
+            max_dyson_radius: stars.iter().map(|s| s.dyson_radius).max().unwrap_or(0),
+            stars,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonRow {
+    pub key: String,
+    /// For each galaxy, the position of the aligned star in `GalaxySummary::stars`.
+    pub stars: Vec<Option<usize>>,
+    pub differences: Vec<&'static str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comparison {
+    pub alignment: Alignment,
+    pub nearby_distance: f64,
+    pub galaxies: Vec<GalaxySummary>,
+    pub rows: Vec<ComparisonRow>,
+}
+
+fn ranked_by_distance<'s>(stars: impl Iterator<Item = &'s StarSummary>) -> Vec<usize> {
+    let mut indexes: Vec<(usize, f64)> = stars.map(|s| (s.index, s.distance)).collect();
+    indexes.sort_by(|a, b| a.1.total_cmp(&b.1));
+    indexes.into_iter().map(|(index, _)| index).collect()
+}
+
+fn make_row(key: String, galaxies: &[GalaxySummary], stars: Vec<Option<usize>>) -> ComparisonRow {
+    let present: Vec<&StarSummary> = stars
+        .iter()
+        .zip(galaxies)
+        .filter_map(|(s, g)| s.map(|i| &g.stars[i]))
+        .collect();
+    let mut differences: Vec<&'static str> = vec![];
+    if present.len() != stars.len() {
+        differences.push("missing");
+    }
+    for other in present.iter().skip(1) {
+        for diff in present[0].differences(other) {
+            if !differences.contains(&diff) {
+                differences.push(diff);
+            }
+        }
+    }
+    ComparisonRow {
+        key,
+        stars,
+        differences,
+    }
+}
+
+/// Aligns the stars of several galaxies and reports how they differ.
+pub fn compare_galaxies(galaxies: &[&Galaxy], options: &CompareOptions) -> Comparison {
+    let summaries: Vec<GalaxySummary> = galaxies
+        .iter()
+        .map(|g| GalaxySummary::new(g, options.nearby_distance))
+        .collect();
+
+    let mut rows: Vec<ComparisonRow> = vec![];
+    match options.alignment {
+        Alignment::DistanceRank => {
+            let ranks: Vec<Vec<usize>> = summaries
+                .iter()
+                .map(|g| ranked_by_distance(g.stars.iter()))
+                .collect();
+            let len = ranks.iter().map(Vec::len).max().unwrap_or(0);
+            for rank in 0..len {
+                let key = if rank == 0 {
+                    "Birth".to_owned()
+                } else {
+                    format!("#{}", rank)
+                };
+                let stars = ranks.iter().map(|r| r.get(rank).copied()).collect();
+                rows.push(make_row(key, &summaries, stars));
+            }
+        }
+        Alignment::StarType => {
+            let mut groups: BTreeMap<String, Vec<Vec<usize>>> = BTreeMap::new();
+            for (n, g) in summaries.iter().enumerate() {
+                let mut keys: Vec<String> = g.stars.iter().map(StarSummary::group_key).collect();
+                keys.sort();
+                keys.dedup();
+                for key in keys {
+                    let ranked =
+                        ranked_by_distance(g.stars.iter().filter(|s| s.group_key() == key));
+                    groups
+                        .entry(key)
+                        .or_insert_with(|| vec![vec![]; summaries.len()])[n] = ranked;
+                }
+            }
+            for (key, ranks) in groups {
+                let len = ranks.iter().map(Vec::len).max().unwrap_or(0);
+                for rank in 0..len {
+                    let stars = ranks.iter().map(|r| r.get(rank).copied()).collect();
+                    rows.push(make_row(
+                        format!("{} #{}", key, rank + 1),
+                        &summaries,
+                        stars,
+                    ));
+                }
+            }
+        }
+    }
+    if options.max_rows > 0 {
+        rows.truncate(options.max_rows);
+    }
+
+    Comparison {
+        alignment: options.alignment,
+        nearby_distance: options.nearby_distance,
+        galaxies: summaries,
+        rows,
+    }
+}
+
+fn join<T: Localize>(items: &[T], locale: Locale) -> String {
+    items
+        .iter()
+        .map(|item| item.localized(locale))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Comparison {
+    pub fn to_text(&self, locale: Locale) -> String {
+        let mut out = String::new();
+        for g in &self.galaxies {
+            let _ = writeln!(
+                out,
+                "{}",
+                fill(
+                    Label::GalaxySummary,
+                    locale,
+                    &[
+                        &g.seed,
+                        &g.star_count,
+                        &g.nearby_ob_count,
+                        &self.nearby_distance,
+                        &g.tidal_locked,
+                        &g.max_dyson_radius,
+                        &join(&g.rare_veins, locale),
+                    ],
+                )
+            );
+        }
+        for row in &self.rows {
+            let _ = writeln!(out);
+            if row.differences.is_empty() {
+                let _ = writeln!(out, "{}", row.key);
+            } else {
+                let differences: Vec<&str> = row
+                    .differences
+                    .iter()
+                    .map(|key| Label::Difference(key).localized(locale))
+                    .collect();
+                let _ = writeln!(
+                    out,
+                    "{}",
+                    fill(Label::Differs, locale, &[&row.key, &differences.join(", ")])
+                );
+            }
+            for (g, star) in self.galaxies.iter().zip(&row.stars) {
+                match star.map(|i| &g.stars[i]) {
+                    Some(s) => {
+                        let gases: Vec<String> = s
+                            .gas_rates
+                            .iter()
+                            .map(|(item, rate)| format!("{}: {:.3}", item.localized(locale), rate))
+                            .collect();
+                        let _ = writeln!(
+                            out,
+                            "  {}",
+                            fill(
+                                Label::StarSummary,
+                                locale,
+                                &[
+                                    &g.seed,
+                                    &s.name,
+                                    &star_kind_name(&s.star_type, &s.spectr, locale),
+                                    &format!("{:.2}", s.distance),
+                                    &format!("{:.3}", s.luminosity),
+                                    &s.dyson_radius,
+                                    &s.planet_count,
+                                    &s.tidal_locked,
+                                    &join(&s.rare_veins, locale),
+                                    &join(&s.ocean_types, locale),
+                                    &gases.join(", "),
+                                ],
+                            )
+                        );
+                    }
+                    None => {
+                        let _ = writeln!(out, "  {}: -", g.seed);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
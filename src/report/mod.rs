@@ -0,0 +1,4 @@
+pub mod compare;
+pub mod stats;
+pub mod svg;
+pub mod xstars;
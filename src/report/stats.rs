@@ -0,0 +1,683 @@
+use crate::data::enums::{SpectrType, StarType, VeinType};
+use crate::data::galaxy::Galaxy;
+use crate::data::game_desc::GameDesc;
+use crate::data::locale::{fill, Label, Locale, Localize};
+use crate::data::rule::Evaluaton;
+use crate::report::xstars::{XStarRanking, XStarWeights};
+use crate::transform_rules::{transform_rules, Rules};
+use crate::worldgen::galaxy_gen::generate_galaxy;
+use serde::de::{value::StrDeserializer, IntoDeserializer};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Metric {
+    /// Luminosity of the brightest star.
+    MaxLuminosity,
+    /// Largest Dyson sphere radius of any star.
+    MaxDysonRadius,
+    /// Number of stars with at least one planet carrying the vein.
+    VeinStarCount { vein: VeinType },
+    /// Highest `get_avg_vein` of any star.
+    MaxAverageVein { vein: VeinType },
+    /// Distance from birth to the nearest star of the type, missing if there is none.
+    NearestStarType {
+        #[serde(rename = "starType")]
+        star_type: StarType,
+    },
+    /// Number of stars of the spectrum within `within` light years of birth.
+    SpectrCount { spectr: SpectrType, within: f64 },
+    /// Best score of an X star or white dwarf with default weights, missing if there is none.
+    XStarScore,
+}
+
+fn parse_enum<'de, T: Deserialize<'de>>(value: &'de str) -> Result<T, String> {
+    let deserializer: StrDeserializer<serde::de::value::Error> = value.into_deserializer();
+    T::deserialize(deserializer).map_err(|_| format!("Unknown value: {}", value))
+}
+
+impl std::str::FromStr for Metric {
+    type Err = String;
+
+    /// Parses the short form used on the command line, e.g. `max-vein:Grat`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let name = parts.next().unwrap_or_default();
+        let arg = parts.next();
+        let arg2 = parts.next();
+        let require = || arg.ok_or_else(|| format!("Metric {} needs an argument", name));
+        match name {
+            "max-luminosity" => Ok(Metric::MaxLuminosity),
+            "max-dyson-radius" => Ok(Metric::MaxDysonRadius),
+            "x-star-score" => Ok(Metric::XStarScore),
+            "vein-stars" => Ok(Metric::VeinStarCount {
+                vein: parse_enum(require()?)?,
+            }),
+            "max-vein" => Ok(Metric::MaxAverageVein {
+                vein: parse_enum(require()?)?,
+            }),
+            "nearest" => Ok(Metric::NearestStarType {
+                star_type: parse_enum(require()?)?,
+            }),
+            "spectr-count" => Ok(Metric::SpectrCount {
+                spectr: parse_enum(require()?)?,
+                within: match arg2 {
+                    Some(within) => within
+                        .parse()
+                        .map_err(|_| format!("Invalid distance: {}", within))?,
+                    None => f64::INFINITY,
+                },
+            }),
+            _ => Err(format!("Unknown metric: {}", name)),
+        }
+    }
+}
+
+impl Metric {
+    pub fn name(&self, locale: Locale) -> String {
+        match self {
+            Metric::MaxLuminosity => Label::MaxLuminosity.localized(locale).to_owned(),
+            Metric::MaxDysonRadius => Label::MaxDysonRadius.localized(locale).to_owned(),
+            Metric::VeinStarCount { vein } => {
+                fill(Label::StarsWith, locale, &[&vein.localized(locale)])
+            }
+            Metric::MaxAverageVein { vein } => {
+                fill(Label::MaxAverage, locale, &[&vein.localized(locale)])
+            }
+            Metric::NearestStarType { star_type } => {
+                fill(Label::Nearest, locale, &[&star_type.localized(locale)])
+            }
+            Metric::SpectrCount { spectr, within } => {
+                if within.is_finite() {
+                    fill(
+                        Label::SpectrWithin,
+                        locale,
+                        &[&spectr.localized(locale), within],
+                    )
+                } else {
+                    spectr.localized(locale).to_owned()
+                }
+            }
+            Metric::XStarScore => Label::XStarScore.localized(locale).to_owned(),
+        }
+    }
+
+    pub fn needs_planets(&self) -> bool {
+        matches!(
+            self,
+            Metric::VeinStarCount { .. } | Metric::MaxAverageVein { .. } | Metric::XStarScore
+        )
+    }
+
+    pub fn measure(&self, galaxy: &Galaxy) -> Option<f64> {
+        let stars = galaxy.stars.iter();
+        match self {
+            Metric::MaxLuminosity => stars
+                .map(|sp| sp.star.get_luminosity() as f64)
+                .reduce(f64::max),
+            Metric::MaxDysonRadius => stars
+                .map(|sp| sp.star.get_dyson_radius() as f64)
+                .reduce(f64::max),
+            Metric::VeinStarCount { vein } => Some(
+                stars
+                    .filter(|sp| {
+                        sp.get_planets()
+                            .any(|p| p.get_veins().iter().any(|v| &v.vein_type == vein))
+                    })
+                    .count() as f64,
+            ),
+            Metric::MaxAverageVein { vein } => stars
+                .map(|sp| sp.get_avg_vein(vein) as f64)
+                .reduce(f64::max),
+            Metric::NearestStarType { star_type } => stars
+                .filter(|sp| &sp.star.star_type == star_type)
+                .map(|sp| sp.star.position.magnitude())
+                .reduce(f64::min),
+            Metric::SpectrCount { spectr, within } => Some(
+                stars
+                    .filter(|sp| {
+                        &sp.star.get_spectr() == spectr && sp.star.position.magnitude() <= *within
+                    })
+                    .count() as f64,
+            ),
+            Metric::XStarScore => XStarRanking::new(galaxy, &XStarWeights::default()).best_score(),
+        }
+    }
+}
+
+/// Values kept as they are before a distribution switches to a `Sketch`.
+const EXACT_VALUES: usize = 4096;
+
+/// Relative error of the values in a `Sketch`.
+const SKETCH_ACCURACY: f64 = 0.005;
+
+/// Values closer to zero than this are counted as zero by a `Sketch`.
+const SKETCH_ZERO: f64 = 1e-9;
+
+#[derive(Debug, Clone, Default)]
+pub struct Distribution {
+    /// Every value, until there are more than `EXACT_VALUES`.
+    values: Vec<f64>,
+    /// All values from then on, so that memory doesn't grow with the seeds.
+    sketch: Option<Sketch>,
+    missing: usize,
+}
+
+/// Counts values in buckets whose bounds grow geometrically, so that any
+/// quantile comes within `SKETCH_ACCURACY` of the true one while the number
+/// of buckets only grows with the range of the values.
+#[derive(Debug, Clone)]
+struct Sketch {
+    /// Counts by bucket index of the positive values, and of the magnitude
+    /// of the negative ones.
+    positive: BTreeMap<i32, usize>,
+    negative: BTreeMap<i32, usize>,
+    zero: usize,
+    count: usize,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Sketch {
+    fn new() -> Self {
+        Self {
+            positive: BTreeMap::new(),
+            negative: BTreeMap::new(),
+            zero: 0,
+            count: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn gamma() -> f64 {
+        (1.0 + SKETCH_ACCURACY) / (1.0 - SKETCH_ACCURACY)
+    }
+
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        if value.abs() < SKETCH_ZERO {
+            self.zero += 1;
+            return;
+        }
+        let index = (value.abs().ln() / Self::gamma().ln()).ceil() as i32;
+        let buckets = match value > 0.0 {
+            true => &mut self.positive,
+            false => &mut self.negative,
+        };
+        *buckets.entry(index).or_default() += 1;
+    }
+
+    fn merge(&mut self, other: Sketch) {
+        for (index, count) in other.positive {
+            *self.positive.entry(index).or_default() += count;
+        }
+        for (index, count) in other.negative {
+            *self.negative.entry(index).or_default() += count;
+        }
+        self.zero += other.zero;
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Pairs of (value, count) by increasing value, each value standing for
+    /// its bucket.
+    fn buckets(&self) -> impl Iterator<Item = (f64, usize)> + '_ {
+        let gamma = Self::gamma();
+        let value = move |index: i32| 2.0 * gamma.powi(index) / (gamma + 1.0);
+        let negative = self
+            .negative
+            .iter()
+            .rev()
+            .map(move |(&index, &count)| (-value(index), count));
+        let positive = self
+            .positive
+            .iter()
+            .map(move |(&index, &count)| (value(index), count));
+        negative
+            .chain((self.zero > 0).then_some((0.0, self.zero)))
+            .chain(positive)
+            .map(|(value, count)| (value.clamp(self.min, self.max), count))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistogramBin {
+    pub start: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DistributionSummary {
+    pub count: usize,
+    pub missing: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// Pairs of (probability, value).
+    pub quantiles: Vec<(f64, f64)>,
+    pub histogram: Vec<HistogramBin>,
+}
+
+const QUANTILES: &[f64] = &[0.01, 0.05, 0.25, 0.5, 0.75, 0.95, 0.99];
+
+impl Distribution {
+    pub fn add(&mut self, value: Option<f64>) {
+        match (value, &mut self.sketch) {
+            (Some(value), Some(sketch)) => sketch.add(value),
+            (Some(value), None) => {
+                self.values.push(value);
+                if self.values.len() > EXACT_VALUES {
+                    self.start_sketch();
+                }
+            }
+            (None, _) => self.missing += 1,
+        }
+    }
+
+    pub fn merge(&mut self, other: Distribution) {
+        self.values.extend(other.values);
+        self.missing += other.missing;
+        if let Some(sketch) = other.sketch {
+            self.start_sketch();
+            self.sketch.as_mut().unwrap().merge(sketch);
+        }
+        if self.sketch.is_some() || self.values.len() > EXACT_VALUES {
+            self.start_sketch();
+        }
+    }
+
+    /// Moves the values kept so far into the sketch.
+    fn start_sketch(&mut self) {
+        let sketch = self.sketch.get_or_insert_with(Sketch::new);
+        for value in self.values.drain(..) {
+            sketch.add(value);
+        }
+    }
+
+    /// Quantiles and the histogram are exact up to `EXACT_VALUES` values, and
+    /// within `SKETCH_ACCURACY` of the values beyond.
+    pub fn summarize(&self, bins: usize) -> DistributionSummary {
+        let (buckets, count, mean): (Vec<(f64, usize)>, usize, f64) = match &self.sketch {
+            Some(sketch) => (
+                sketch.buckets().collect(),
+                sketch.count,
+                sketch.sum / (sketch.count as f64),
+            ),
+            None => {
+                let mut sorted = self.values.clone();
+                sorted.sort_by(f64::total_cmp);
+                let mean = sorted.iter().sum::<f64>() / (sorted.len() as f64);
+                (
+                    sorted.into_iter().map(|v| (v, 1)).collect(),
+                    self.values.len(),
+                    mean,
+                )
+            }
+        };
+        if count == 0 {
+            return DistributionSummary {
+                count,
+                missing: self.missing,
+                min: f64::NAN,
+                max: f64::NAN,
+                mean: f64::NAN,
+                quantiles: vec![],
+                histogram: vec![],
+            };
+        }
+        let (min, max) = match &self.sketch {
+            Some(sketch) => (sketch.min, sketch.max),
+            None => (buckets[0].0, buckets[buckets.len() - 1].0),
+        };
+        // The value at `rank` in increasing order.
+        let value_at = |rank: usize| {
+            let mut seen = 0;
+            for &(value, count) in &buckets {
+                seen += count;
+                if rank < seen {
+                    return value;
+                }
+            }
+            max
+        };
+        let quantiles = QUANTILES
+            .iter()
+            .map(|&q| {
+                let pos = q * ((count - 1) as f64);
+                let lo = value_at(pos.floor() as usize);
+                let hi = value_at(pos.ceil() as usize);
+                (q, lo + (hi - lo) * (pos - pos.floor()))
+            })
+            .collect();
+        let bins = bins.max(1);
+        let width = (max - min) / (bins as f64);
+        let mut histogram: Vec<HistogramBin> = (0..bins)
+            .map(|i| HistogramBin {
+                start: min + width * (i as f64),
+                end: min + width * ((i + 1) as f64),
+                count: 0,
+            })
+            .collect();
+        for (value, count) in buckets {
+            let bin = if width > 0.0 {
+                (((value - min) / width) as usize).min(bins - 1)
+            } else {
+                0
+            };
+            histogram[bin].count += count;
+        }
+        DistributionSummary {
+            count,
+            missing: self.missing,
+            min,
+            max,
+            mean,
+            quantiles,
+            histogram,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HitRate {
+    pub hits: usize,
+    pub trials: usize,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HitRateEstimate {
+    pub hits: usize,
+    pub trials: usize,
+    pub rate: f64,
+    pub confidence: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+fn normal_cdf(x: f64) -> f64 {
+    // Abramowitz and Stegun 7.1.26
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs() / std::f64::consts::SQRT_2);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-(x * x) / 2.0).exp();
+    if x >= 0.0 {
+        (1.0 + erf) / 2.0
+    } else {
+        (1.0 - erf) / 2.0
+    }
+}
+
+/// Two-sided critical value of the standard normal distribution.
+fn z_score(confidence: f64) -> f64 {
+    let target = 1.0 - (1.0 - confidence) / 2.0;
+    let (mut lo, mut hi) = (0.0, 10.0);
+    for _ in 0..64 {
+        let mid = (lo + hi) / 2.0;
+        if normal_cdf(mid) < target {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+impl HitRate {
+    pub fn add(&mut self, hit: bool) {
+        self.trials += 1;
+        if hit {
+            self.hits += 1;
+        }
+    }
+
+    /// Wilson score interval, which stays meaningful for very rare hits.
+    pub fn estimate(&self, confidence: f64) -> HitRateEstimate {
+        let n = self.trials as f64;
+        let (rate, low, high) = if self.trials == 0 {
+            (0.0, 0.0, 1.0)
+        } else {
+            let p = (self.hits as f64) / n;
+            let z = z_score(confidence);
+            let z2 = z * z;
+            let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+            let half = z / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+            (p, (center - half).max(0.0), (center + half).min(1.0))
+        };
+        HitRateEstimate {
+            hits: self.hits,
+            trials: self.trials,
+            rate,
+            confidence,
+            low,
+            high,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Statistics {
+    pub metrics: Vec<Metric>,
+    pub distributions: Vec<Distribution>,
+    pub hit_rate: HitRate,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricReport {
+    pub metric: Metric,
+    pub name: String,
+    pub distribution: DistributionSummary,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticsReport {
+    pub galaxies: usize,
+    pub metrics: Vec<MetricReport>,
+    pub hit_rate: Option<HitRateEstimate>,
+}
+
+impl Statistics {
+    pub fn new(metrics: Vec<Metric>) -> Self {
+        Self {
+            distributions: vec![Distribution::default(); metrics.len()],
+            metrics,
+            hit_rate: HitRate::default(),
+        }
+    }
+
+    pub fn add_galaxy(&mut self, galaxy: &Galaxy) {
+        if self.metrics.iter().any(Metric::needs_planets) {
+            // Planet themes depend on generation order.
+            for sp in &galaxy.stars {
+                sp.load_planets();
+            }
+        }
+        for (metric, distribution) in self.metrics.iter().zip(self.distributions.iter_mut()) {
+            distribution.add(metric.measure(galaxy));
+        }
+    }
+
+    pub fn merge(&mut self, other: Statistics) {
+        for (d1, d2) in self.distributions.iter_mut().zip(other.distributions) {
+            d1.merge(d2);
+        }
+        self.hit_rate.hits += other.hit_rate.hits;
+        self.hit_rate.trials += other.hit_rate.trials;
+    }
+
+    pub fn report(
+        &self,
+        galaxies: usize,
+        bins: usize,
+        confidence: Option<f64>,
+        locale: Locale,
+    ) -> StatisticsReport {
+        StatisticsReport {
+            galaxies,
+            metrics: self
+                .metrics
+                .iter()
+                .zip(self.distributions.iter())
+                .map(|(metric, distribution)| MetricReport {
+                    metric: metric.clone(),
+                    name: metric.name(locale),
+                    distribution: distribution.summarize(bins),
+                })
+                .collect(),
+            hit_rate: confidence.map(|c| self.hit_rate.estimate(c)),
+        }
+    }
+}
+
+fn sweep_worker(
+    game: &GameDesc,
+    seeds: impl Iterator<Item = i32>,
+    metrics: &[Metric],
+    rule: Option<&Rules>,
+) -> Statistics {
+    let mut stats = Statistics::new(metrics.to_vec());
+    let mut transformed = rule.map(|r| transform_rules(r.clone()));
+    let mut evaluation = Evaluaton::new(0);
+    let mut found = vec![];
+    for seed in seeds {
+        let desc = game.with_seed(seed);
+        let galaxy = generate_galaxy(&desc);
+        // Metrics may load every planet first, in the order of the stars as
+        // the game does, which doesn't change what the rule finds.
+        stats.add_galaxy(&galaxy);
+        if let Some(rule) = transformed.as_mut() {
+            evaluation.reset(galaxy.stars.len());
+            found.clear();
+            rule.evaluate(&galaxy, &evaluation, &mut found);
+            stats.hit_rate.add(!found.is_empty());
+        }
+    }
+    stats
+}
+
+/// Measures every galaxy in `seeds` and, if given, how often `rule` matches.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn sweep(
+    game: &GameDesc,
+    seeds: Range<i32>,
+    metrics: &[Metric],
+    rule: Option<&Rules>,
+    threads: usize,
+) -> Statistics {
+    let threads = threads.max(1);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                let seeds = seeds.clone().skip(t).step_by(threads);
+                scope.spawn(move || sweep_worker(game, seeds, metrics, rule))
+            })
+            .collect();
+        let mut stats = Statistics::new(metrics.to_vec());
+        for handle in handles {
+            stats.merge(handle.join().unwrap());
+        }
+        stats
+    })
+}
+
+/// Measures every galaxy in `seeds` and, if given, how often `rule` matches.
+#[cfg(target_arch = "wasm32")]
+pub fn sweep(
+    game: &GameDesc,
+    seeds: Range<i32>,
+    metrics: &[Metric],
+    rule: Option<&Rules>,
+    _threads: usize,
+) -> Statistics {
+    sweep_worker(game, seeds, metrics, rule)
+}
+
+impl StatisticsReport {
+    pub fn to_text(&self, locale: Locale) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "{}", fill(Label::Galaxies, locale, &[&self.galaxies]));
+        for report in &self.metrics {
+            let d = &report.distribution;
+            let _ = writeln!(out);
+            let _ = writeln!(out, "{}", report.name);
+            if d.count == 0 {
+                let _ = writeln!(out, "  {}", fill(Label::NoValues, locale, &[&d.missing]));
+                continue;
+            }
+            let _ = writeln!(
+                out,
+                "  {}",
+                fill(
+                    Label::Distribution,
+                    locale,
+                    &[
+                        &d.count,
+                        &d.missing,
+                        &format!("{:.3}", d.min),
+                        &format!("{:.3}", d.mean),
+                        &format!("{:.3}", d.max),
+                    ],
+                )
+            );
+            let quantiles: Vec<String> = d
+                .quantiles
+                .iter()
+                .map(|(q, v)| format!("p{} {:.3}", q * 100.0, v))
+                .collect();
+            let _ = writeln!(out, "  {}", quantiles.join(", "));
+            let peak = d
+                .histogram
+                .iter()
+                .map(|b| b.count)
+                .max()
+                .unwrap_or(0)
+                .max(1);
+            for bin in &d.histogram {
+                let bar = "#".repeat(bin.count * 40 / peak);
+                let _ = writeln!(
+                    out,
+                    "  [{:>10.3}, {:>10.3}) {:>8} {}",
+                    bin.start, bin.end, bin.count, bar
+                );
+            }
+        }
+        if let Some(h) = &self.hit_rate {
+            let _ = writeln!(out);
+            let _ = writeln!(
+                out,
+                "{}",
+                fill(
+                    Label::HitRate,
+                    locale,
+                    &[
+                        &h.hits,
+                        &h.trials,
+                        &format!("{:.4}", h.rate * 100.0),
+                        &(h.confidence * 100.0),
+                        &format!("{:.4}", h.low * 100.0),
+                        &format!("{:.4}", h.high * 100.0),
+                    ],
+                )
+            );
+        }
+        out
+    }
+}
@@ -0,0 +1,204 @@
+use crate::data::enums::{SpectrType, StarType};
+use crate::data::galaxy::Galaxy;
+use crate::data::locale::Locale;
+use crate::data::star::Star;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SvgView {
+    /// Looking down the y axis, plotting x against z.
+    Top,
+    /// Looking along the z axis, plotting x against y.
+    Side,
+    /// Both projections side by side.
+    Both,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    pub view: SvgView,
+    /// Width and height of a single projection, in pixels.
+    pub size: f64,
+    /// Radii (in light years) of rings drawn around the birth star.
+    pub rings: Vec<f64>,
+    /// Star indexes to highlight, e.g. the output of `find_stars`.
+    pub highlights: Vec<usize>,
+    pub labels: bool,
+    /// Language of the star kinds in the tooltips.
+    pub locale: Locale,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            view: SvgView::Both,
+            size: 480.0,
+            rings: vec![],
+            highlights: vec![],
+            labels: false,
+            locale: Locale::En,
+        }
+    }
+}
+
+const MARGIN: f64 = 24.0;
+const BACKGROUND: &str = "#0b0e17";
+const FOREGROUND: &str = "#c8cedb";
+
+pub fn star_color(star: &Star) -> &'static str {
+    match star.star_type {
+        StarType::WhiteDwarf => "#e6ecff",
+        StarType::NeutronStar => "#66d9ff",
+        StarType::BlackHole => "#9d6bff",
+        _ => match star.get_spectr() {
+            SpectrType::M => "#ff7a4d",
+            SpectrType::K => "#ffb066",
+            SpectrType::G => "#ffe27a",
+            SpectrType::F => "#fff6cc",
+            SpectrType::A => "#f2f5ff",
+            SpectrType::B => "#a8c4ff",
+            SpectrType::O => "#5b8cff",
+            SpectrType::X => "#ffffff",
+        },
+    }
+}
+
+fn star_radius(star: &Star) -> f64 {
+    match star.star_type {
+        StarType::GiantStar => 6.0,
+        StarType::WhiteDwarf => 2.5,
+        _ => 3.5,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders a galaxy into a standalone SVG document.
+pub fn render_svg(galaxy: &Galaxy, options: &SvgOptions) -> String {
+    let views: &[SvgView] = match options.view {
+        SvgView::Both => &[SvgView::Top, SvgView::Side],
+        SvgView::Top => &[SvgView::Top],
+        SvgView::Side => &[SvgView::Side],
+    };
+    let size = options.size;
+    let width = size * views.len() as f64;
+
+    // Same scale for all projections so they can be compared side by side.
+    let extent = galaxy
+        .stars
+        .iter()
+        .map(|sp| {
+            let p = &sp.star.position;
+            p.0.abs().max(p.1.abs()).max(p.2.abs())
+        })
+        .chain(options.rings.iter().copied())
+        .fold(1.0_f64, f64::max)
+        * 1.05;
+    let scale = (size / 2.0 - MARGIN) / extent;
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{size}" viewBox="0 0 {width} {size}" font-family="sans-serif">"#
+    );
+    let _ = writeln!(
+        out,
+        r#"<rect width="{width}" height="{size}" fill="{BACKGROUND}"/>"#
+    );
+    let _ = writeln!(
+        out,
+        r#"<text x="8" y="16" font-size="12" fill="{FOREGROUND}">Seed {} · {} stars</text>"#,
+        galaxy.seed,
+        galaxy.stars.len()
+    );
+
+    for (panel, view) in views.iter().enumerate() {
+        let cx = size * (panel as f64) + size / 2.0;
+        let cy = size / 2.0;
+        let project = |star: &Star| -> (f64, f64) {
+            let p = &star.position;
+            match view {
+                SvgView::Side => (cx + p.0 * scale, cy - p.1 * scale),
+                _ => (cx + p.0 * scale, cy - p.2 * scale),
+            }
+        };
+        let (class, title) = match view {
+            SvgView::Side => ("side", "Side (x-y)"),
+            _ => ("top", "Top (x-z)"),
+        };
+        let _ = writeln!(out, r#"<g class="{class}">"#);
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" font-size="11" fill="{FOREGROUND}" text-anchor="middle">{title}</text>"#,
+            cx,
+            size - 6.0
+        );
+        for ring in &options.rings {
+            let _ = writeln!(
+                out,
+                r##"<circle cx="{cx:.1}" cy="{cy:.1}" r="{:.1}" fill="none" stroke="#3a4256" stroke-dasharray="4 4"/>"##,
+                ring * scale
+            );
+            let _ = writeln!(
+                out,
+                r##"<text x="{:.1}" y="{:.1}" font-size="9" fill="#6b7590">{ring} ly</text>"##,
+                cx + ring * scale + 2.0,
+                cy - 2.0
+            );
+        }
+        for sp in &galaxy.stars {
+            let star = &sp.star;
+            let (x, y) = project(star);
+            let r = star_radius(star);
+            let color = star_color(star);
+            let kind = star.kind_name(options.locale);
+            let title = if sp.name.is_empty() {
+                kind.to_owned()
+            } else {
+                escape(&format!("{} ({})", sp.name, kind))
+            };
+            if star.star_type == StarType::BlackHole {
+                let _ = writeln!(
+                    out,
+                    r#"<circle cx="{x:.1}" cy="{y:.1}" r="{r:.1}" fill="{BACKGROUND}" stroke="{color}" stroke-width="1.5"><title>{title}</title></circle>"#
+                );
+            } else {
+                let _ = writeln!(
+                    out,
+                    r#"<circle cx="{x:.1}" cy="{y:.1}" r="{r:.1}" fill="{color}"><title>{title}</title></circle>"#
+                );
+            }
+            if star.is_birth() {
+                let _ = writeln!(
+                    out,
+                    r##"<circle cx="{x:.1}" cy="{y:.1}" r="{:.1}" fill="none" stroke="#4cff8a" stroke-width="1.5"/>"##,
+                    r + 4.0
+                );
+            }
+            if options.highlights.contains(&star.index) {
+                let _ = writeln!(
+                    out,
+                    r##"<circle cx="{x:.1}" cy="{y:.1}" r="{:.1}" fill="none" stroke="#ff4c6a" stroke-width="2"/>"##,
+                    r + 7.0
+                );
+            }
+            if options.labels && !sp.name.is_empty() {
+                let _ = writeln!(
+                    out,
+                    r#"<text x="{:.1}" y="{:.1}" font-size="9" fill="{FOREGROUND}">{}</text>"#,
+                    x + r + 2.0,
+                    y + 3.0,
+                    escape(&sp.name)
+                );
+            }
+        }
+        let _ = writeln!(out, "</g>");
+    }
+    out.push_str("</svg>\n");
+    out
+}
@@ -0,0 +1,258 @@
+use crate::data::enums::{SpectrType, StarType, VeinType};
+use crate::data::galaxy::Galaxy;
+use crate::data::game_desc::GameDesc;
+use crate::data::locale::{fill, Label, Locale, Localize};
+use crate::data::star::Star;
+use crate::seeds::SeedSelection;
+use crate::worldgen::galaxy_gen::generate_galaxy;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// How much each part of an entry counts towards its score. Yields are the
+/// expected vein amounts of `get_avg_vein`, distances are in light years.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct XStarWeights {
+    pub mag: f64,
+    pub grat: f64,
+    pub fractal: f64,
+    /// Amount subtracted per light year from birth.
+    pub birth_distance: f64,
+    /// Amount subtracted per light year to the nearest blue giant or O star.
+    /// Without one, the star counts as `MISSING_GIANT_DISTANCE` away.
+    pub giant_distance: f64,
+}
+
+impl Default for XStarWeights {
+    fn default() -> Self {
+        Self {
+            mag: 1.0,
+            grat: 0.5,
+            fractal: 0.5,
+            birth_distance: 50000.0,
+            giant_distance: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XStarEntry {
+    pub index: usize,
+    pub name: String,
+    pub star_type: StarType,
+    pub mag: f32,
+    pub grat: f32,
+    pub fractal: f32,
+    pub birth_distance: f64,
+    /// Distance to the nearest blue giant or O star, missing if there is none.
+    pub giant_distance: Option<f64>,
+    pub score: f64,
+}
+
+/// Distance to the nearest blue giant counted for galaxies without one,
+/// farther than stars of a galaxy ever are from each other.
+const MISSING_GIANT_DISTANCE: f64 = 1000.0;
+
+impl XStarEntry {
+    /// The entry's score under `weights`, higher is better.
+    pub fn score(&self, weights: &XStarWeights) -> f64 {
+        weights.mag * self.mag as f64
+            + weights.grat * self.grat as f64
+            + weights.fractal * self.fractal as f64
+            - weights.birth_distance * self.birth_distance
+            - weights.giant_distance * self.giant_distance.unwrap_or(MISSING_GIANT_DISTANCE)
+    }
+}
+
+/// X stars (neutron stars and black holes) and white dwarfs of one galaxy,
+/// best score first.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XStarRanking {
+    pub seed: i32,
+    pub weights: XStarWeights,
+    pub stars: Vec<XStarEntry>,
+}
+
+pub fn is_ranked_star_type(star_type: &StarType) -> bool {
+    matches!(
+        star_type,
+        StarType::WhiteDwarf | StarType::NeutronStar | StarType::BlackHole
+    )
+}
+
+fn is_blue_giant(star: &Star) -> bool {
+    let spectr = star.get_spectr();
+    spectr == SpectrType::O || (star.star_type == StarType::GiantStar && spectr == SpectrType::B)
+}
+
+impl XStarRanking {
+    pub fn new(galaxy: &Galaxy, weights: &XStarWeights) -> Self {
+        // Veins depend on the themes of all earlier stars.
+        for sp in &galaxy.stars {
+            sp.load_planets();
+        }
+        let mut stars: Vec<XStarEntry> = galaxy
+            .stars
+            .iter()
+            .filter(|sp| is_ranked_star_type(&sp.star.star_type))
+            .map(|sp| {
+                let position = &sp.star.position;
+                let mut entry = XStarEntry {
+                    index: sp.star.index,
+                    name: galaxy.get_star_name(sp.star.index),
+                    star_type: sp.star.star_type.clone(),
+                    mag: sp.get_avg_vein(&VeinType::Mag),
+                    grat: sp.get_avg_vein(&VeinType::Grat),
+                    fractal: sp.get_avg_vein(&VeinType::Fractal),
+                    birth_distance: position.magnitude(),
+                    giant_distance: galaxy
+                        .stars
+                        .iter()
+                        .filter(|other| is_blue_giant(&other.star))
+                        .map(|other| other.star.position.distance_from(position))
+                        .reduce(f64::min),
+                    score: 0.0,
+                };
+                entry.score = entry.score(weights);
+                entry
+            })
+            .collect();
+        stars.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.index.cmp(&b.index)));
+        Self {
+            seed: galaxy.seed,
+            weights: weights.clone(),
+            stars,
+        }
+    }
+
+    /// Score of the best star, the objective when ranking seeds.
+    pub fn best_score(&self) -> Option<f64> {
+        self.stars.first().map(|entry| entry.score)
+    }
+
+    pub fn to_text(&self, locale: Locale) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}",
+            fill(
+                Label::XStarSummary,
+                locale,
+                &[&self.seed, &self.stars.len()]
+            )
+        );
+        let light_years =
+            |distance: f64| fill(Label::LightYears, locale, &[&format!("{:.2}", distance)]);
+        for s in &self.stars {
+            let giant_distance = match s.giant_distance {
+                Some(distance) => light_years(distance),
+                None => "-".to_owned(),
+            };
+            let veins = format!(
+                "{} {:.0}, {} {:.0}, {} {:.0}",
+                VeinType::Mag.localized(locale),
+                s.mag,
+                VeinType::Grat.localized(locale),
+                s.grat,
+                VeinType::Fractal.localized(locale),
+                s.fractal
+            );
+            let _ = writeln!(
+                out,
+                "  {}",
+                fill(
+                    Label::XStar,
+                    locale,
+                    &[
+                        &format!("{:.0}", s.score),
+                        &s.name,
+                        &s.index,
+                        &s.star_type.localized(locale),
+                        &veins,
+                        &light_years(s.birth_distance),
+                        &giant_distance,
+                    ],
+                )
+            );
+        }
+        out
+    }
+}
+
+pub fn rank_x_stars(game: &GameDesc, weights: &XStarWeights) -> XStarRanking {
+    XStarRanking::new(&generate_galaxy(game), weights)
+}
+
+fn rank_seeds_worker(
+    game: &GameDesc,
+    seeds: impl Iterator<Item = i32>,
+    weights: &XStarWeights,
+    top: usize,
+) -> Vec<XStarRanking> {
+    let mut best: Vec<XStarRanking> = vec![];
+    for seed in seeds {
+        let ranking = rank_x_stars(&game.with_seed(seed), weights);
+        if ranking.best_score().is_some() {
+            insert_ranking(&mut best, ranking, top);
+        }
+    }
+    best
+}
+
+/// Keeps `best` sorted by best score, then seed, and at most `top` long.
+fn insert_ranking(best: &mut Vec<XStarRanking>, ranking: XStarRanking, top: usize) {
+    let score = |r: &XStarRanking| r.best_score().unwrap_or(f64::NEG_INFINITY);
+    let position = best.partition_point(|r| {
+        score(&ranking)
+            .total_cmp(&score(r))
+            .then(r.seed.cmp(&ranking.seed))
+            .is_lt()
+    });
+    if position < top {
+        best.insert(position, ranking);
+        best.truncate(top);
+    }
+}
+
+/// The `top` seeds whose best X star or white dwarf scores highest.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn rank_seeds(
+    game: &GameDesc,
+    seeds: &SeedSelection,
+    weights: &XStarWeights,
+    top: usize,
+    threads: usize,
+) -> Vec<XStarRanking> {
+    let threads = threads.max(1);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                let positions = (t..seeds.len()).step_by(threads);
+                scope.spawn(move || {
+                    rank_seeds_worker(game, positions.map(|p| seeds.get(p)), weights, top)
+                })
+            })
+            .collect();
+        let mut best = vec![];
+        for handle in handles {
+            for ranking in handle.join().unwrap() {
+                insert_ranking(&mut best, ranking, top);
+            }
+        }
+        best
+    })
+}
+
+/// The `top` seeds whose best X star or white dwarf scores highest.
+#[cfg(target_arch = "wasm32")]
+pub fn rank_seeds(
+    game: &GameDesc,
+    seeds: &SeedSelection,
+    weights: &XStarWeights,
+    top: usize,
+    _threads: usize,
+) -> Vec<XStarRanking> {
+    rank_seeds_worker(game, seeds.iter(), weights, top)
+}
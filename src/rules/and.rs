@@ -1,7 +1,9 @@
-use crate::data::rule::Rule;
+use crate::data::rule::{matching_planets_of_any, Rule, Scratch};
+use std::cell::RefCell;
 
 pub struct RuleAnd {
     pub rules: Vec<Box<dyn Rule + Send>>,
+    pub scratch: RefCell<Scratch>,
 }
 
 impl Rule for RuleAnd {
@@ -16,15 +18,51 @@ impl Rule for RuleAnd {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut e = evaluation.clone();
+        result: &mut Vec<usize>,
+    ) {
+        let Scratch {
+            evaluation: e,
+            matches,
+        } = &mut *self.scratch.borrow_mut();
+        e.clone_from(evaluation);
         for rule in &self.rules {
-            let result = rule.evaluate(galaxy, &e);
-            e.reject_others(&result);
+            matches.clear();
+            rule.evaluate(galaxy, e, matches);
+            e.reject_others(matches);
             if e.is_done() {
-                return e.collect_unknown();
+                break;
             }
         }
-        e.collect_unknown()
+        e.collect_unknown(result)
+    }
+    fn is_layout_decidable(&self) -> bool {
+        self.rules.iter().all(|rule| rule.is_layout_decidable())
+    }
+    fn evaluate_layout(
+        &self,
+        layout: &crate::data::galaxy::GalaxyLayout,
+        result: &mut Vec<usize>,
+    ) -> bool {
+        let matches = &mut self.scratch.borrow_mut().matches;
+        let mut narrowed = false;
+        for rule in &self.rules {
+            matches.clear();
+            if !rule.evaluate_layout(layout, matches) {
+                continue;
+            }
+            if narrowed {
+                result.retain(|index| matches.contains(index));
+            } else {
+                result.extend_from_slice(matches);
+                narrowed = true;
+            }
+            if result.is_empty() {
+                break;
+            }
+        }
+        narrowed
+    }
+    fn matching_planets(&self, star: &crate::data::star_planets::StarWithPlanets) -> Vec<usize> {
+        matching_planets_of_any(&self.rules, star)
     }
 }
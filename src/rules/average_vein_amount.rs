@@ -18,8 +18,8 @@ impl Rule for RuleAverageVeinAmount {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut result: Vec<usize> = vec![];
+        result: &mut Vec<usize>,
+    ) {
         for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
             if !evaluation.is_unknown(index) {
                 if !sp.is_safe() {
@@ -32,6 +32,5 @@ impl Rule for RuleAverageVeinAmount {
                 result.push(index);
             }
         }
-        result
     }
 }
@@ -13,7 +13,19 @@ impl Rule for RuleBirth {
         &self,
         _: &crate::data::galaxy::Galaxy,
         _: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        vec![0]
+        result: &mut Vec<usize>,
+    ) {
+        result.push(0);
+    }
+    fn is_layout_decidable(&self) -> bool {
+        true
+    }
+    fn evaluate_layout(
+        &self,
+        _: &crate::data::galaxy::GalaxyLayout,
+        result: &mut Vec<usize>,
+    ) -> bool {
+        result.push(0);
+        true
     }
 }
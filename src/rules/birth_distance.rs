@@ -15,8 +15,8 @@ impl Rule for RuleBirthDistance {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut result: Vec<usize> = vec![];
+        result: &mut Vec<usize>,
+    ) {
         for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
             if evaluation.is_known(index) {
                 continue;
@@ -26,6 +26,20 @@ impl Rule for RuleBirthDistance {
                 result.push(index)
             }
         }
-        result
+    }
+    fn is_layout_decidable(&self) -> bool {
+        true
+    }
+    fn evaluate_layout(
+        &self,
+        layout: &crate::data::galaxy::GalaxyLayout,
+        result: &mut Vec<usize>,
+    ) -> bool {
+        for (index, position) in layout.positions.iter().enumerate() {
+            if self.condition.eval(position.magnitude() as f32) {
+                result.push(index)
+            }
+        }
+        true
     }
 }
@@ -1,8 +1,11 @@
-use crate::data::rule::{Condition, Rule};
+use crate::data::rule::{matching_planets_of_any, Condition, Rule};
+use crate::data::star_planets::StarWithPlanets;
+use std::cell::RefCell;
 
 pub struct RuleComposite {
     pub rule: Box<dyn Rule + Send>,
     pub condition: Condition,
+    pub matches: RefCell<Vec<usize>>,
 }
 
 impl Rule for RuleComposite {
@@ -13,17 +16,52 @@ impl Rule for RuleComposite {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let result = self.rule.evaluate(galaxy, evaluation);
-        if self.condition.eval(result.len() as f32) {
-            return vec![0];
+        result: &mut Vec<usize>,
+    ) {
+        let matches = &mut *self.matches.borrow_mut();
+        matches.clear();
+        self.rule.evaluate(galaxy, evaluation, matches);
+        if self.condition.eval(matches.len() as f32) {
+            result.push(0);
         }
-        vec![]
+    }
+    fn is_layout_decidable(&self) -> bool {
+        self.rule.is_layout_decidable()
+    }
+    fn evaluate_layout(
+        &self,
+        layout: &crate::data::galaxy::GalaxyLayout,
+        result: &mut Vec<usize>,
+    ) -> bool {
+        let matches = &mut *self.matches.borrow_mut();
+        matches.clear();
+        if !self.rule.evaluate_layout(layout, matches) {
+            return false;
+        }
+        // Without an exact count, only the stars that may match bound it from
+        // above, which only tells conditions asking for enough of them.
+        let count = matches.len() as f32;
+        let may_match = if self.rule.is_layout_decidable() {
+            self.condition.eval(count)
+        } else {
+            match self.condition {
+                Condition::Gt(_) | Condition::Gte(_) => self.condition.eval(count),
+                _ => true,
+            }
+        };
+        if may_match {
+            result.push(0);
+        }
+        true
+    }
+    fn matching_planets(&self, star: &StarWithPlanets) -> Vec<usize> {
+        self.rule.matching_planets(star)
     }
 }
 
 pub struct RuleCompositeAnd {
     pub rules: Vec<Box<dyn Rule + Send>>,
+    pub matches: RefCell<Vec<usize>>,
 }
 
 impl Rule for RuleCompositeAnd {
@@ -38,14 +76,44 @@ impl Rule for RuleCompositeAnd {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
+        result: &mut Vec<usize>,
+    ) {
+        let matches = &mut *self.matches.borrow_mut();
+        for rule in &self.rules {
+            matches.clear();
+            rule.evaluate(galaxy, evaluation, matches);
+            if matches.is_empty() {
+                return;
+            }
+        }
+        result.push(0);
+    }
+    fn is_layout_decidable(&self) -> bool {
+        self.rules.iter().all(|rule| rule.is_layout_decidable())
+    }
+    fn evaluate_layout(
+        &self,
+        layout: &crate::data::galaxy::GalaxyLayout,
+        result: &mut Vec<usize>,
+    ) -> bool {
+        let matches = &mut *self.matches.borrow_mut();
+        let mut narrowed = false;
         for rule in &self.rules {
-            let result = rule.evaluate(galaxy, evaluation);
-            if result.is_empty() {
-                return result;
+            matches.clear();
+            if rule.evaluate_layout(layout, matches) {
+                if matches.is_empty() {
+                    return true;
+                }
+                narrowed = true;
             }
         }
-        vec![0]
+        if narrowed {
+            result.push(0);
+        }
+        narrowed
+    }
+    fn matching_planets(&self, star: &StarWithPlanets) -> Vec<usize> {
+        matching_planets_of_any(&self.rules, star)
     }
 }
 
@@ -65,13 +133,35 @@ impl Rule for RuleCompositeOr {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
+        result: &mut Vec<usize>,
+    ) {
+        for rule in &self.rules {
+            rule.evaluate(galaxy, evaluation, result);
+            if !result.is_empty() {
+                return;
+            }
+        }
+    }
+    fn is_layout_decidable(&self) -> bool {
+        self.rules.iter().all(|rule| rule.is_layout_decidable())
+    }
+    fn evaluate_layout(
+        &self,
+        layout: &crate::data::galaxy::GalaxyLayout,
+        result: &mut Vec<usize>,
+    ) -> bool {
         for rule in &self.rules {
-            let result = rule.evaluate(galaxy, evaluation);
+            if !rule.evaluate_layout(layout, result) {
+                result.clear();
+                return false;
+            }
             if !result.is_empty() {
-                return result;
+                return true;
             }
         }
-        vec![]
+        true
+    }
+    fn matching_planets(&self, star: &StarWithPlanets) -> Vec<usize> {
+        matching_planets_of_any(&self.rules, star)
     }
 }
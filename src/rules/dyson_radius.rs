@@ -15,8 +15,8 @@ impl Rule for RuleDysonRadius {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut result: Vec<usize> = vec![];
+        result: &mut Vec<usize>,
+    ) {
         for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
             if evaluation.is_known(index) {
                 continue;
@@ -26,6 +26,5 @@ impl Rule for RuleDysonRadius {
                 result.push(index)
             }
         }
-        result
     }
 }
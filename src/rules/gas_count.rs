@@ -22,8 +22,8 @@ impl Rule for RuleGasCount {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut result: Vec<usize> = vec![];
+        result: &mut Vec<usize>,
+    ) {
         if let Some(ice) = self.ice {
             for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
                 let is_safe = sp.is_safe();
@@ -63,6 +63,5 @@ impl Rule for RuleGasCount {
                 }
             }
         }
-        result
     }
 }
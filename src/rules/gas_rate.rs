@@ -1,3 +1,4 @@
+use crate::data::enums::GasType;
 use crate::data::rule::Condition;
 use crate::data::rule::Rule;
 use serde::{Deserialize, Serialize};
@@ -5,7 +6,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RuleGasRate {
-    pub gas_type: i32,
+    pub gas_type: GasType,
     pub condition: Condition,
 }
 
@@ -17,8 +18,8 @@ impl Rule for RuleGasRate {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut result: Vec<usize> = vec![];
+        result: &mut Vec<usize>,
+    ) {
         for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
             let is_safe = sp.is_safe();
             if !evaluation.is_unknown(index) {
@@ -46,6 +47,5 @@ impl Rule for RuleGasRate {
                 result.push(index);
             }
         }
-        result
     }
 }
@@ -0,0 +1,24 @@
+use crate::data::rule::{Condition, Rule};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleHabitableRadius {
+    pub condition: Condition,
+}
+
+impl Rule for RuleHabitableRadius {
+    fn get_priority(&self) -> i32 {
+        24
+    }
+    fn evaluate(
+        &self,
+        galaxy: &crate::data::galaxy::Galaxy,
+        evaluation: &crate::data::rule::Evaluaton,
+        result: &mut Vec<usize>,
+    ) {
+        self.condition.eval_stars(galaxy, evaluation, result, |sp| {
+            sp.star.get_habitable_radius()
+        });
+    }
+}
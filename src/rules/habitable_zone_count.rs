@@ -0,0 +1,26 @@
+use crate::data::rule::{Condition, Rule};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleHabitableZoneCount {
+    pub condition: Condition,
+}
+
+impl Rule for RuleHabitableZoneCount {
+    fn get_priority(&self) -> i32 {
+        35
+    }
+    fn evaluate(
+        &self,
+        galaxy: &crate::data::galaxy::Galaxy,
+        evaluation: &crate::data::rule::Evaluaton,
+        result: &mut Vec<usize>,
+    ) {
+        self.condition.eval_stars(galaxy, evaluation, result, |sp| {
+            sp.get_planets()
+                .filter(|planet| planet.is_in_habitable_zone())
+                .count() as f32
+        });
+    }
+}
@@ -6,14 +6,23 @@ pub mod composite;
 pub mod dyson_radius;
 pub mod gas_count;
 pub mod gas_rate;
+pub mod habitable_radius;
+pub mod habitable_zone_count;
+pub mod light_balance_radius;
 pub mod luminosity;
 pub mod ocean_type;
 pub mod or;
+pub mod orbit_scaler;
+pub mod orbital_ratio;
 pub mod planet_count;
 pub mod planet_in_dyson_count;
+pub mod resonance_count;
+pub mod retrograde_count;
+pub mod rotation_period;
 pub mod satellite_count;
 pub mod spectr;
 pub mod spectr_distance;
+pub mod star_name;
 pub mod star_type;
 pub mod theme_id;
 pub mod tidal_lock_count;
@@ -1,10 +1,11 @@
+use crate::data::enums::OceanType;
 use crate::data::rule::Rule;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RuleOceanType {
-    pub ocean_type: i32,
+    pub ocean_type: OceanType,
 }
 
 impl Rule for RuleOceanType {
@@ -15,8 +16,8 @@ impl Rule for RuleOceanType {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut result: Vec<usize> = vec![];
+        result: &mut Vec<usize>,
+    ) {
         for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
             if !evaluation.is_unknown(index) {
                 if !sp.is_safe() {
@@ -37,6 +38,5 @@ impl Rule for RuleOceanType {
                 result.push(index);
             }
         }
-        result
     }
 }
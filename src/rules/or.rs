@@ -1,7 +1,9 @@
-use crate::data::rule::Rule;
+use crate::data::rule::{matching_planets_of_any, Rule, Scratch};
+use std::cell::RefCell;
 
 pub struct RuleOr {
     pub rules: Vec<Box<dyn Rule + Send>>,
+    pub scratch: RefCell<Scratch>,
 }
 
 impl Rule for RuleOr {
@@ -16,15 +18,48 @@ impl Rule for RuleOr {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut e = evaluation.clone();
+        result: &mut Vec<usize>,
+    ) {
+        let Scratch {
+            evaluation: e,
+            matches,
+        } = &mut *self.scratch.borrow_mut();
+        e.clone_from(evaluation);
         for rule in &self.rules {
-            let result = rule.evaluate(galaxy, &e);
-            e.accept_many(&result);
+            matches.clear();
+            rule.evaluate(galaxy, e, matches);
+            e.accept_many(matches);
             if e.is_done() {
-                return e.collect_known();
+                break;
             }
         }
-        e.collect_known()
+        e.collect_known(result)
+    }
+    fn is_layout_decidable(&self) -> bool {
+        self.rules.iter().all(|rule| rule.is_layout_decidable())
+    }
+    fn evaluate_layout(
+        &self,
+        layout: &crate::data::galaxy::GalaxyLayout,
+        result: &mut Vec<usize>,
+    ) -> bool {
+        let matches = &mut self.scratch.borrow_mut().matches;
+        for rule in &self.rules {
+            matches.clear();
+            if !rule.evaluate_layout(layout, matches) {
+                result.clear();
+                return false;
+            }
+            for index in matches.iter() {
+                if !result.contains(index) {
+                    result.push(*index);
+                }
+            }
+        }
+        result.sort_unstable();
+        true
+    }
+    fn matching_planets(&self, star: &crate::data::star_planets::StarWithPlanets) -> Vec<usize> {
+        matching_planets_of_any(&self.rules, star)
     }
 }
@@ -0,0 +1,23 @@
+use crate::data::rule::{Condition, Rule};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleOrbitScaler {
+    pub condition: Condition,
+}
+
+impl Rule for RuleOrbitScaler {
+    fn get_priority(&self) -> i32 {
+        26
+    }
+    fn evaluate(
+        &self,
+        galaxy: &crate::data::galaxy::Galaxy,
+        evaluation: &crate::data::rule::Evaluaton,
+        result: &mut Vec<usize>,
+    ) {
+        self.condition
+            .eval_stars(galaxy, evaluation, result, |sp| sp.star.get_orbit_scaler());
+    }
+}
@@ -0,0 +1,80 @@
+use crate::data::rule::Rule;
+use crate::data::star_planets::StarWithPlanets;
+use serde::{Deserialize, Serialize};
+
+/// Matches stars with two planets whose orbital periods have the given ratio,
+/// e.g. 2 for a 1:2 orbital resonance. Satellites are not considered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleOrbitalRatio {
+    pub ratio: f64,
+    /// Allowed relative difference from `ratio`.
+    #[serde(default = "RuleOrbitalRatio::default_tolerance")]
+    pub tolerance: f64,
+}
+
+impl RuleOrbitalRatio {
+    pub fn default_tolerance() -> f64 {
+        0.01
+    }
+
+    fn is_ratio(&self, period1: f64, period2: f64) -> bool {
+        let ratio = period1.max(period2) / period1.min(period2);
+        (ratio / self.ratio - 1.0).abs() <= self.tolerance
+    }
+
+    fn periods<'s, 'a>(
+        star: &'s StarWithPlanets<'a>,
+    ) -> impl Iterator<Item = (usize, f64)> + use<'s, 'a> {
+        star.get_planets()
+            .filter(|planet| planet.orbit_around.is_none())
+            .map(|planet| (planet.index, planet.get_orbital_period()))
+    }
+
+    /// Same as `matching_planets` not being empty, without collecting them.
+    fn has_ratio(&self, star: &StarWithPlanets) -> bool {
+        Self::periods(star).enumerate().any(|(i, (_, period1))| {
+            Self::periods(star)
+                .skip(i + 1)
+                .any(|(_, period2)| self.is_ratio(period1, period2))
+        })
+    }
+}
+
+impl Rule for RuleOrbitalRatio {
+    fn get_priority(&self) -> i32 {
+        39
+    }
+    fn evaluate(
+        &self,
+        galaxy: &crate::data::galaxy::Galaxy,
+        evaluation: &crate::data::rule::Evaluaton,
+        result: &mut Vec<usize>,
+    ) {
+        for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
+            if evaluation.is_known(index) {
+                continue;
+            }
+            if self.has_ratio(sp) {
+                result.push(index)
+            }
+        }
+    }
+    fn matching_planets(&self, star: &StarWithPlanets) -> Vec<usize> {
+        let periods: Vec<(usize, f64)> = Self::periods(star).collect();
+        let mut result: Vec<usize> = vec![];
+        for (i, &(index1, period1)) in periods.iter().enumerate() {
+            for &(index2, period2) in &periods[i + 1..] {
+                if self.is_ratio(period1, period2) {
+                    for index in [index1, index2] {
+                        if !result.contains(&index) {
+                            result.push(index);
+                        }
+                    }
+                }
+            }
+        }
+        result.sort_unstable();
+        result
+    }
+}
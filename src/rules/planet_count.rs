@@ -18,8 +18,8 @@ impl Rule for RulePlanetCount {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut result: Vec<usize> = vec![];
+        result: &mut Vec<usize>,
+    ) {
         for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
             if evaluation.is_known(index) {
                 continue;
@@ -34,6 +34,5 @@ impl Rule for RulePlanetCount {
                 result.push(index)
             }
         }
-        result
     }
 }
@@ -0,0 +1,64 @@
+use crate::data::planet::Planet;
+use crate::data::rule::{Condition, Rule};
+use crate::data::star_planets::StarWithPlanets;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Resonance {
+    /// Could be generated with a resonance, whether it was or not.
+    Eligible,
+    Any,
+    TidalLock,
+    OneToTwo,
+    OneToFour,
+}
+
+impl Resonance {
+    pub fn matches(&self, planet: &Planet) -> bool {
+        match self {
+            Resonance::Eligible => planet.get_eligible_for_resonance(),
+            Resonance::Any => planet.get_resonance().is_some(),
+            Resonance::TidalLock => planet.get_resonance() == Some(1),
+            Resonance::OneToTwo => planet.get_resonance() == Some(2),
+            Resonance::OneToFour => planet.get_resonance() == Some(4),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleResonanceCount {
+    pub resonance: Resonance,
+    pub condition: Condition,
+}
+
+impl Rule for RuleResonanceCount {
+    fn get_priority(&self) -> i32 {
+        38
+    }
+    fn evaluate(
+        &self,
+        galaxy: &crate::data::galaxy::Galaxy,
+        evaluation: &crate::data::rule::Evaluaton,
+        result: &mut Vec<usize>,
+    ) {
+        for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
+            if evaluation.is_known(index) {
+                continue;
+            }
+            if self.condition.eval(
+                sp.get_planets()
+                    .filter(|planet| self.resonance.matches(planet))
+                    .count() as f32,
+            ) {
+                result.push(index)
+            }
+        }
+    }
+    fn matching_planets(&self, star: &StarWithPlanets) -> Vec<usize> {
+        star.get_planets()
+            .filter(|planet| self.resonance.matches(planet))
+            .map(|planet| planet.index)
+            .collect()
+    }
+}
@@ -0,0 +1,40 @@
+use crate::data::rule::{Condition, Rule};
+use crate::data::star_planets::StarWithPlanets;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleRetrogradeCount {
+    pub condition: Condition,
+}
+
+impl Rule for RuleRetrogradeCount {
+    fn get_priority(&self) -> i32 {
+        37
+    }
+    fn evaluate(
+        &self,
+        galaxy: &crate::data::galaxy::Galaxy,
+        evaluation: &crate::data::rule::Evaluaton,
+        result: &mut Vec<usize>,
+    ) {
+        for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
+            if evaluation.is_known(index) {
+                continue;
+            }
+            if self.condition.eval(
+                sp.get_planets()
+                    .filter(|planet| planet.is_retrograde())
+                    .count() as f32,
+            ) {
+                result.push(index)
+            }
+        }
+    }
+    fn matching_planets(&self, star: &StarWithPlanets) -> Vec<usize> {
+        star.get_planets()
+            .filter(|planet| planet.is_retrograde())
+            .map(|planet| planet.index)
+            .collect()
+    }
+}
@@ -0,0 +1,59 @@
+use crate::data::planet::Planet;
+use crate::data::rule::{Condition, Rule};
+use crate::data::star_planets::StarWithPlanets;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleRotationPeriod {
+    /// Length of a day in seconds, regardless of the direction of rotation.
+    pub condition: Condition,
+    #[serde(default = "RuleRotationPeriod::default_count")]
+    pub count: Condition,
+    #[serde(default)]
+    pub include_giant: bool,
+}
+
+impl RuleRotationPeriod {
+    pub fn default_count() -> Condition {
+        Condition::Gte(1.0)
+    }
+
+    fn matches(&self, planet: &Planet) -> bool {
+        (self.include_giant || !planet.is_gas_giant())
+            && self
+                .condition
+                .eval(planet.get_rotation_period().abs() as f32)
+    }
+}
+
+impl Rule for RuleRotationPeriod {
+    fn get_priority(&self) -> i32 {
+        36
+    }
+    fn evaluate(
+        &self,
+        galaxy: &crate::data::galaxy::Galaxy,
+        evaluation: &crate::data::rule::Evaluaton,
+        result: &mut Vec<usize>,
+    ) {
+        for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
+            if evaluation.is_known(index) {
+                continue;
+            }
+            if self.count.eval(
+                sp.get_planets()
+                    .filter(|planet| self.matches(planet))
+                    .count() as f32,
+            ) {
+                result.push(index)
+            }
+        }
+    }
+    fn matching_planets(&self, star: &StarWithPlanets) -> Vec<usize> {
+        star.get_planets()
+            .filter(|planet| self.matches(planet))
+            .map(|planet| planet.index)
+            .collect()
+    }
+}
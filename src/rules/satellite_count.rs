@@ -16,8 +16,8 @@ impl Rule for RuleSatelliteCount {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut result: Vec<usize> = vec![];
+        result: &mut Vec<usize>,
+    ) {
         for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
             if evaluation.is_known(index) {
                 continue;
@@ -28,6 +28,5 @@ impl Rule for RuleSatelliteCount {
                 result.push(index)
             }
         }
-        result
     }
 }
@@ -16,8 +16,8 @@ impl Rule for RuleSpectr {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut result: Vec<usize> = vec![];
+        result: &mut Vec<usize>,
+    ) {
         for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
             if evaluation.is_known(index) {
                 continue;
@@ -27,6 +27,5 @@ impl Rule for RuleSpectr {
                 result.push(index)
             }
         }
-        result
     }
 }
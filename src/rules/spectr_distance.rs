@@ -1,9 +1,9 @@
 use crate::data::{
     enums::SpectrType,
     rule::{Condition, Rule},
-    star_planets::StarWithPlanets,
 };
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -13,7 +13,14 @@ pub struct RuleSpectrDistance {
     pub count_condition: Condition,
 }
 
-impl Rule for RuleSpectrDistance {
+/// Evaluates a `RuleSpectrDistance`, keeping the stars of its spectral class
+/// in a buffer reused from one evaluation to the next.
+pub struct RuleSpectrDistanceEvaluator {
+    pub rule: RuleSpectrDistance,
+    pub candidates: RefCell<Vec<usize>>,
+}
+
+impl Rule for RuleSpectrDistanceEvaluator {
     fn get_priority(&self) -> i32 {
         14
     }
@@ -21,16 +28,22 @@ impl Rule for RuleSpectrDistance {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut result: Vec<usize> = vec![];
-        let good_stars: Vec<&StarWithPlanets> = galaxy
-            .stars
-            .iter()
-            .filter(|sp| sp.star.get_spectr() == self.spectr)
-            .collect();
+        result: &mut Vec<usize>,
+    ) {
+        let rule = &self.rule;
+        let candidates = &mut *self.candidates.borrow_mut();
+        candidates.clear();
+        candidates.extend(
+            galaxy
+                .stars
+                .iter()
+                .enumerate()
+                .filter(|(_, sp)| sp.star.get_spectr() == rule.spectr)
+                .map(|(index, _)| index),
+        );
 
-        if good_stars.is_empty() {
-            return result;
+        if candidates.is_empty() {
+            return;
         }
 
         for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
@@ -38,19 +51,19 @@ impl Rule for RuleSpectrDistance {
                 continue;
             }
             let star = &sp.star;
-            let count = good_stars
+            let count = candidates
                 .iter()
-                .filter(|sp2| {
-                    sp2.star.index != star.index
-                        && self
+                .map(|&other| &galaxy.stars[other].star)
+                .filter(|star2| {
+                    star2.index != star.index
+                        && rule
                             .distance_condition
-                            .eval(star.position.distance_from(&sp2.star.position) as f32)
+                            .eval(star.position.distance_from(&star2.position) as f32)
                 })
                 .count();
-            if self.count_condition.eval(count as f32) {
+            if rule.count_condition.eval(count as f32) {
                 result.push(index)
             }
         }
-        result
     }
 }
@@ -0,0 +1,89 @@
+use crate::data::rule::Rule;
+use regex_lite::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NameMatch {
+    #[default]
+    Exact,
+    Prefix,
+    Regex,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StarNamePattern {
+    pub name: String,
+    #[serde(default)]
+    pub mode: NameMatch,
+    #[serde(default)]
+    pub ignore_case: bool,
+}
+
+impl StarNamePattern {
+    pub fn compile(&self) -> Result<Regex, String> {
+        let pattern = match self.mode {
+            NameMatch::Exact => format!("^{}$", regex_lite::escape(&self.name)),
+            NameMatch::Prefix => format!("^{}", regex_lite::escape(&self.name)),
+            NameMatch::Regex => self.name.clone(),
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(self.ignore_case)
+            .build()
+            .map_err(|err| format!("Invalid star name pattern {}: {}", self.name, err))
+    }
+}
+
+/// The pattern is compiled when the rule is deserialized, so an invalid
+/// regex is reported together with other errors in the rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "StarNamePattern", into = "StarNamePattern")]
+pub struct RuleStarName {
+    pub pattern: StarNamePattern,
+    regex: Regex,
+}
+
+impl RuleStarName {
+    pub fn is_match(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+impl TryFrom<StarNamePattern> for RuleStarName {
+    type Error = String;
+
+    fn try_from(pattern: StarNamePattern) -> Result<Self, Self::Error> {
+        Ok(Self {
+            regex: pattern.compile()?,
+            pattern,
+        })
+    }
+}
+
+impl From<RuleStarName> for StarNamePattern {
+    fn from(rule: RuleStarName) -> Self {
+        rule.pattern
+    }
+}
+
+impl Rule for RuleStarName {
+    fn get_priority(&self) -> i32 {
+        23
+    }
+    fn evaluate(
+        &self,
+        galaxy: &crate::data::galaxy::Galaxy,
+        evaluation: &crate::data::rule::Evaluaton,
+        result: &mut Vec<usize>,
+    ) {
+        for index in 0..galaxy.stars.len().min(evaluation.get_len()) {
+            if evaluation.is_known(index) {
+                continue;
+            }
+            if self.is_match(&galaxy.get_star_name(index)) {
+                result.push(index)
+            }
+        }
+    }
+}
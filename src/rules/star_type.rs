@@ -16,8 +16,8 @@ impl Rule for RuleStarType {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut result: Vec<usize> = vec![];
+        result: &mut Vec<usize>,
+    ) {
         for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
             if evaluation.is_known(index) {
                 continue;
@@ -27,6 +27,20 @@ impl Rule for RuleStarType {
                 result.push(index)
             }
         }
-        result
+    }
+    fn is_layout_decidable(&self) -> bool {
+        true
+    }
+    fn evaluate_layout(
+        &self,
+        layout: &crate::data::galaxy::GalaxyLayout,
+        result: &mut Vec<usize>,
+    ) -> bool {
+        for (index, star_type) in layout.star_types.iter().enumerate() {
+            if self.star_type.contains(star_type) {
+                result.push(index)
+            }
+        }
+        true
     }
 }
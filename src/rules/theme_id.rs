@@ -15,8 +15,8 @@ impl Rule for RuleThemeId {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut result: Vec<usize> = vec![];
+        result: &mut Vec<usize>,
+    ) {
         for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
             let planets = sp.get_planets();
             if !evaluation.is_unknown(index) {
@@ -37,6 +37,5 @@ impl Rule for RuleThemeId {
                 result.push(index);
             }
         }
-        result
     }
 }
@@ -4,6 +4,7 @@ use crate::data::{
     vector3::Vector3,
 };
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -12,7 +13,32 @@ pub struct RuleXDistance {
     pub all: bool,
 }
 
-impl Rule for RuleXDistance {
+fn is_x_star(star_type: &StarType) -> bool {
+    *star_type == StarType::BlackHole || *star_type == StarType::NeutronStar
+}
+
+impl RuleXDistance {
+    fn matches<'p>(
+        &self,
+        position: &Vector3,
+        mut x_stars: impl Iterator<Item = &'p Vector3>,
+    ) -> bool {
+        if self.all {
+            x_stars.all(|p| self.condition.eval(position.distance_from(p) as f32))
+        } else {
+            x_stars.any(|p| self.condition.eval(position.distance_from(p) as f32))
+        }
+    }
+}
+
+/// Evaluates a `RuleXDistance`, keeping the neutron stars and black holes in
+/// a buffer reused from one evaluation to the next.
+pub struct RuleXDistanceEvaluator {
+    pub rule: RuleXDistance,
+    pub candidates: RefCell<Vec<usize>>,
+}
+
+impl Rule for RuleXDistanceEvaluator {
     fn get_priority(&self) -> i32 {
         13
     }
@@ -20,41 +46,64 @@ impl Rule for RuleXDistance {
         &self,
         galaxy: &crate::data::galaxy::Galaxy,
         evaluation: &crate::data::rule::Evaluaton,
-    ) -> Vec<usize> {
-        let mut result: Vec<usize> = vec![];
-        let x_stars: Vec<&Vector3> = galaxy
-            .stars
-            .iter()
-            .filter(|sp| {
-                sp.star.star_type == StarType::BlackHole
-                    || sp.star.star_type == StarType::NeutronStar
-            })
-            .map(|sp| &sp.star.position)
-            .collect();
-
-        if x_stars.is_empty() {
-            return result;
+        result: &mut Vec<usize>,
+    ) {
+        let candidates = &mut *self.candidates.borrow_mut();
+        candidates.clear();
+        candidates.extend(
+            galaxy
+                .stars
+                .iter()
+                .enumerate()
+                .filter(|(_, sp)| is_x_star(&sp.star.star_type))
+                .map(|(index, _)| index),
+        );
+
+        if candidates.is_empty() {
+            return;
         }
 
         for (index, sp) in galaxy.stars.iter().take(evaluation.get_len()).enumerate() {
             if evaluation.is_known(index) {
                 continue;
             }
-            let star = &sp.star;
-            if self.all {
-                if x_stars
-                    .iter()
-                    .all(|p| self.condition.eval(star.position.distance_from(p) as f32))
-                {
-                    result.push(index)
-                }
-            } else if x_stars
+            let x_stars = candidates
                 .iter()
-                .any(|p| self.condition.eval(star.position.distance_from(p) as f32))
-            {
+                .map(|&other| &galaxy.stars[other].star.position);
+            if self.rule.matches(&sp.star.position, x_stars) {
+                result.push(index)
+            }
+        }
+    }
+    fn is_layout_decidable(&self) -> bool {
+        true
+    }
+    fn evaluate_layout(
+        &self,
+        layout: &crate::data::galaxy::GalaxyLayout,
+        result: &mut Vec<usize>,
+    ) -> bool {
+        let candidates = &mut *self.candidates.borrow_mut();
+        candidates.clear();
+        candidates.extend(
+            layout
+                .star_types
+                .iter()
+                .enumerate()
+                .filter(|(_, star_type)| is_x_star(star_type))
+                .map(|(index, _)| index),
+        );
+
+        if candidates.is_empty() {
+            return true;
+        }
+
+        for (index, position) in layout.positions.iter().enumerate() {
+            let x_stars = candidates.iter().map(|&other| &layout.positions[other]);
+            if self.rule.matches(position, x_stars) {
                 result.push(index)
             }
         }
-        result
+        true
     }
 }
@@ -0,0 +1,296 @@
+use crate::data::game_desc::GameDesc;
+use crate::seeds::SeedSelection;
+use crate::transform_rules::{transform_rules, Rules};
+use crate::worldgen::batch_gen::SEARCH_LANES;
+use crate::worldgen::workspace::Workspace;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::SystemTime;
+
+/// Rules below this priority only look at stars, so their seeds can be
+/// generated in batches.
+const PLANET_PRIORITY: i32 = 30;
+
+/// The threads a search runs on. They are started for each search, outside of
+/// any async runtime, and can be given cores and a priority of their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Workers {
+    pub threads: usize,
+    /// Pins each thread to a core, taking cores in turn.
+    pub pin: bool,
+    /// Niceness of the threads, from -20 (most favorable) to 19. Only on Linux,
+    /// where it can be set per thread, and lowering it usually needs
+    /// privileges.
+    pub nice: Option<i32>,
+}
+
+impl Workers {
+    pub fn new(threads: usize) -> Self {
+        Self {
+            threads,
+            pin: false,
+            nice: None,
+        }
+    }
+
+    /// Applies the options to the current thread, the `index`-th of the search.
+    fn set_up(&self, index: usize, cores: &[core_affinity::CoreId]) {
+        if self.pin {
+            let pinned =
+                !cores.is_empty() && core_affinity::set_for_current(cores[index % cores.len()]);
+            if !pinned && index == 0 {
+                eprintln!("Cannot pin search threads to cores.");
+            }
+        }
+        // Elsewhere this would renice the whole process.
+        #[cfg(target_os = "linux")]
+        if let Some(nice) = self.nice {
+            if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 && index == 0 {
+                eprintln!(
+                    "Cannot set the niceness of search threads: {}",
+                    std::io::Error::last_os_error()
+                );
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        if self.nice.is_some() && index == 0 {
+            eprintln!("The niceness of search threads can only be set on Linux.");
+        }
+    }
+}
+
+/// Progress is reported as positions in the seed selection, see `SeedSelection::get`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchEvent {
+    Result { seed: i32, indexes: Vec<usize> },
+    Progress { start: usize, end: usize },
+    Done { start: usize, end: usize },
+}
+
+/// Seeds a worker claims at most at a time. Claiming and reporting progress
+/// happen once per chunk, so workers don't contend per seed.
+const MAX_CHUNK: usize = 1024;
+
+/// Hands out the positions of a seed selection in chunks, smaller and smaller
+/// as the end approaches so that workers finish at about the same time.
+struct Chunks {
+    next: AtomicUsize,
+    len: usize,
+    threads: usize,
+}
+
+impl Chunks {
+    /// Claims the next chunk, whose length is a multiple of `lanes` unless it
+    /// ends the selection.
+    fn claim(&self, lanes: usize) -> Option<Range<usize>> {
+        let mut start = self.next.load(Ordering::Relaxed);
+        loop {
+            if start >= self.len {
+                return None;
+            }
+            let remaining = self.len - start;
+            let size = (remaining / (self.threads * 4))
+                .next_multiple_of(lanes)
+                .clamp(lanes, MAX_CHUNK)
+                .min(remaining);
+            match self.next.compare_exchange_weak(
+                start,
+                start + size,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(start..start + size),
+                Err(current) => start = current,
+            }
+        }
+    }
+}
+
+struct FindState {
+    pub progress_start: usize,
+    pub progress_end: usize,
+    /// Chunks done past `progress_end`, from their start to their end and the
+    /// results held back until `progress_end` passes them.
+    pub pending: BTreeMap<usize, (usize, Vec<SearchEvent>)>,
+    pub autosave: u64,
+    pub last_notify: SystemTime,
+    /// Turn of the next events taken from the state, see `Turns`.
+    pub next_turn: usize,
+}
+
+impl FindState {
+    /// Marks `done` as searched. `found`, the results of `done` in order, are
+    /// returned once the seeds before them are all searched, followed by the
+    /// progress covering them when it is due.
+    pub fn add(&mut self, done: Range<usize>, found: Vec<SearchEvent>) -> Vec<SearchEvent> {
+        if done.is_empty() {
+            return vec![];
+        }
+        if self.progress_end != done.start {
+            self.pending.insert(done.start, (done.end, found));
+            return vec![];
+        }
+        let mut ready = found;
+        self.progress_end = done.end;
+        while let Some((end, found)) = self.pending.remove(&self.progress_end) {
+            ready.extend(found);
+            self.progress_end = end;
+        }
+        let now = SystemTime::now();
+        if now.duration_since(self.last_notify).unwrap().as_secs() >= self.autosave {
+            self.last_notify = now;
+            ready.push(SearchEvent::Progress {
+                start: self.progress_start,
+                end: self.progress_end,
+            });
+            self.progress_start = self.progress_end;
+        }
+        ready
+    }
+
+    /// Takes a turn to report events taken from the state.
+    pub fn take_turn(&mut self) -> usize {
+        self.next_turn += 1;
+        self.next_turn - 1
+    }
+}
+
+/// Lets threads report events in the order they took them from `FindState`,
+/// without holding its lock meanwhile: a client slow to take events only
+/// holds up the threads with events to report.
+#[derive(Default)]
+struct Turns {
+    current: Mutex<usize>,
+    changed: Condvar,
+}
+
+impl Turns {
+    fn report(&self, turn: usize, events: Vec<SearchEvent>, on_event: impl Fn(SearchEvent)) {
+        let mut current = self.current.lock().unwrap();
+        while *current != turn {
+            current = self.changed.wait(current).unwrap();
+        }
+        drop(current);
+        events.into_iter().for_each(on_event);
+        *self.current.lock().unwrap() += 1;
+        self.changed.notify_all();
+    }
+}
+
+/// Evaluates `rule` on every seed of `seeds` using `workers`, blocking
+/// until all seeds are done or `stop` is set. Progress is reported at most
+/// every `autosave` seconds, and `Done` is always the last event.
+///
+/// Results are reported as threads find them, unless `ordered` is set: they
+/// are then reported in the order of `seeds`, each once the seeds before it
+/// are all searched, so two runs give the same events. This is the order of
+/// positions in the selection, which for seed lists need not be increasing
+/// seeds. A stopped search then drops the results past the end of `Done`,
+/// which resuming finds again.
+#[allow(clippy::too_many_arguments)]
+pub fn search(
+    game: &GameDesc,
+    rule: &Rules,
+    seeds: &SeedSelection,
+    workers: Workers,
+    autosave: u64,
+    ordered: bool,
+    stop: &AtomicBool,
+    on_event: impl Fn(SearchEvent) + Sync,
+) {
+    let len = seeds.len();
+    let threads = workers.threads.clamp(1, len.max(1));
+    let cores = match workers.pin {
+        true => core_affinity::get_core_ids().unwrap_or_default(),
+        false => vec![],
+    };
+    let chunks = Chunks {
+        next: AtomicUsize::new(0),
+        len,
+        threads,
+    };
+    let state = Mutex::new(FindState {
+        progress_start: 0,
+        progress_end: 0,
+        pending: BTreeMap::new(),
+        autosave,
+        last_notify: SystemTime::now(),
+        next_turn: 0,
+    });
+    let turns = Turns::default();
+
+    std::thread::scope(|scope| {
+        for index in 0..threads {
+            let transformed = transform_rules(rule.clone());
+            let (chunks, state, turns, on_event, cores) =
+                (&chunks, &state, &turns, &on_event, &cores);
+            let lanes = if transformed.get_priority() < PLANET_PRIORITY {
+                SEARCH_LANES
+            } else {
+                1
+            };
+            let worker = move || {
+                workers.set_up(index, cores);
+                let mut workspace = Workspace::new(game);
+                let mut batch = [0; SEARCH_LANES];
+                while let Some(chunk) = chunks.claim(lanes) {
+                    let mut found = vec![];
+                    let mut done = chunk.start;
+                    while done < chunk.end && !stop.load(Ordering::SeqCst) {
+                        let positions = done..(done + lanes).min(chunk.end);
+                        let claimed = &mut batch[..positions.len()];
+                        for (seed, position) in claimed.iter_mut().zip(positions.clone()) {
+                            *seed = seeds.get(position);
+                        }
+                        if lanes == 1 {
+                            workspace.generate_matching(claimed[0], &*transformed);
+                        } else {
+                            workspace
+                                .generate_batch_matching::<SEARCH_LANES>(claimed, &*transformed);
+                        }
+                        for lane in 0..positions.len() {
+                            let seed = workspace.galaxy(lane).seed;
+                            let star_indexes = workspace.find_stars(lane, &*transformed);
+                            if !star_indexes.is_empty() {
+                                let result = SearchEvent::Result {
+                                    seed,
+                                    indexes: star_indexes.to_vec(),
+                                };
+                                if ordered {
+                                    found.push(result);
+                                } else {
+                                    on_event(result);
+                                }
+                            }
+                        }
+                        done = positions.end;
+                    }
+                    let (events, turn) = {
+                        let mut state = state.lock().unwrap();
+                        let events = state.add(chunk.start..done, found);
+                        let turn = (!events.is_empty()).then(|| state.take_turn());
+                        (events, turn)
+                    };
+                    if let Some(turn) = turn {
+                        turns.report(turn, events, on_event);
+                    }
+                    if done < chunk.end {
+                        break;
+                    }
+                }
+            };
+            std::thread::Builder::new()
+                .name(format!("search-{}", index))
+                .spawn_scoped(scope, worker)
+                .expect("cannot start a search thread");
+        }
+    });
+
+    let state = state.into_inner().unwrap();
+    on_event(SearchEvent::Done {
+        start: state.progress_start,
+        end: state.progress_end,
+    });
+}
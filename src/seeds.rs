@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum SeedSource {
+    /// Seeds `start, start + step, ...` below `end`.
+    Range {
+        start: i32,
+        end: i32,
+        #[serde(default = "SeedSource::default_step")]
+        step: i32,
+    },
+    List {
+        seeds: Vec<i32>,
+    },
+    /// A text file with one seed per line, read by `SeedSelection::resolve`.
+    /// Only the command line reads files; the server refuses them.
+    File {
+        path: String,
+    },
+}
+
+impl SeedSource {
+    pub fn default_step() -> i32 {
+        1
+    }
+}
+
+/// Splits a seed sequence into `count` interleaved shards, so that several
+/// machines can each take one without coordinating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl Default for Shard {
+    fn default() -> Self {
+        Self { index: 0, count: 1 }
+    }
+}
+
+impl std::str::FromStr for Shard {
+    type Err = String;
+
+    /// Parses `k/n`, where `k` counts from 1.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid shard {}, expected <k>/<n>", s);
+        let (k, n) = s.split_once('/').ok_or_else(invalid)?;
+        let k: usize = k.trim().parse().map_err(|_| invalid())?;
+        let n: usize = n.trim().parse().map_err(|_| invalid())?;
+        if k == 0 || k > n {
+            return Err(invalid());
+        }
+        Ok(Self {
+            index: k - 1,
+            count: n,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeedSelection {
+    pub source: SeedSource,
+    #[serde(default)]
+    pub shard: Shard,
+}
+
+impl SeedSelection {
+    pub fn range(start: i32, end: i32) -> Self {
+        Self {
+            source: SeedSource::Range {
+                start,
+                end,
+                step: 1,
+            },
+            shard: Shard::default(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.shard.count == 0 || self.shard.index >= self.shard.count {
+            return Err(format!(
+                "Invalid shard {} of {}",
+                self.shard.index + 1,
+                self.shard.count
+            ));
+        }
+        match &self.source {
+            SeedSource::Range { step, .. } if *step <= 0 => {
+                Err(format!("Invalid step {}, it must be positive", step))
+            }
+            SeedSource::File { path } => Err(format!("Seed file {} is not loaded", path)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Loads seed files so the selection can be iterated.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn resolve(self) -> Result<Self, String> {
+        let source = match self.source {
+            SeedSource::File { path } => {
+                let text = std::fs::read_to_string(&path)
+                    .map_err(|err| format!("Cannot read {}: {}", path, err))?;
+                SeedSource::List {
+                    seeds: parse_seed_list(&text).map_err(|err| format!("{}: {}", path, err))?,
+                }
+            }
+            source => source,
+        };
+        let resolved = Self {
+            source,
+            shard: self.shard,
+        };
+        resolved.validate()?;
+        Ok(resolved)
+    }
+
+    fn base_len(&self) -> usize {
+        match &self.source {
+            SeedSource::Range { start, end, step } => {
+                if end <= start || *step <= 0 {
+                    0
+                } else {
+                    ((*end as i64 - *start as i64 + *step as i64 - 1) / *step as i64) as usize
+                }
+            }
+            SeedSource::List { seeds } => seeds.len(),
+            SeedSource::File { .. } => 0,
+        }
+    }
+
+    /// Number of seeds in this shard.
+    pub fn len(&self) -> usize {
+        let base = self.base_len();
+        if self.shard.index >= base {
+            0
+        } else {
+            (base - self.shard.index).div_ceil(self.shard.count)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The seed at `position` of this shard.
+    pub fn get(&self, position: usize) -> i32 {
+        let base_position = self.shard.index + position * self.shard.count;
+        match &self.source {
+            SeedSource::Range { start, step, .. } => {
+                (*start as i64 + base_position as i64 * *step as i64) as i32
+            }
+            SeedSource::List { seeds } => seeds[base_position],
+            SeedSource::File { .. } => panic!("seed file must be resolved first"),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = i32> + '_ {
+        (0..self.len()).map(|position| self.get(position))
+    }
+
+    /// Whether positions map to consecutive seeds, i.e. a plain range.
+    pub fn is_contiguous(&self) -> bool {
+        matches!(self.source, SeedSource::Range { step: 1, .. }) && self.shard.count == 1
+    }
+}
+
+/// Parses seeds from text, taking the first field of every line. Fields may be
+/// separated by commas, semicolons, tabs or spaces, so columns exported from a
+/// spreadsheet work. Empty lines, `#` comments and a header line are skipped.
+pub fn parse_seed_list(text: &str) -> Result<Vec<i32>, String> {
+    let mut seeds = vec![];
+    let mut first_line = true;
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let field = line
+            .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+            .next()
+            .unwrap_or_default()
+            .trim_matches('"');
+        match field.parse::<i32>() {
+            Ok(seed) => seeds.push(seed),
+            Err(_) if first_line => {}
+            Err(_) => return Err(format!("line {}: invalid seed {}", number + 1, field)),
+        }
+        first_line = false;
+    }
+    Ok(seeds)
+}
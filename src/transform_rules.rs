@@ -38,6 +38,15 @@ pub enum Rules {
     SpectrDistance(rules::spectr_distance::RuleSpectrDistance),
     GasRate(rules::gas_rate::RuleGasRate),
     PlanetInDysonCount(rules::planet_in_dyson_count::RulePlanetInDysonCount),
+    StarName(rules::star_name::RuleStarName),
+    HabitableRadius(rules::habitable_radius::RuleHabitableRadius),
+    LightBalanceRadius(rules::light_balance_radius::RuleLightBalanceRadius),
+    OrbitScaler(rules::orbit_scaler::RuleOrbitScaler),
+    HabitableZoneCount(rules::habitable_zone_count::RuleHabitableZoneCount),
+    RotationPeriod(rules::rotation_period::RuleRotationPeriod),
+    RetrogradeCount(rules::retrograde_count::RuleRetrogradeCount),
+    ResonanceCount(rules::resonance_count::RuleResonanceCount),
+    OrbitalRatio(rules::orbital_ratio::RuleOrbitalRatio),
 }
 
 pub fn sort_rules(rules: Vec<Rules>) -> Vec<Box<dyn Rule + Send>> {
@@ -51,18 +60,22 @@ pub fn transform_rules(r: Rules) -> Box<dyn Rule + Send> {
         Rules::Composite { rule, condition } => Box::new(rules::composite::RuleComposite {
             rule: transform_rules(*rule),
             condition,
+            matches: Default::default(),
         }),
         Rules::CompositeAnd { rules } => Box::new(rules::composite::RuleCompositeAnd {
             rules: sort_rules(rules),
+            matches: Default::default(),
         }),
         Rules::CompositeOr { rules } => Box::new(rules::composite::RuleCompositeOr {
             rules: sort_rules(rules),
         }),
         Rules::And { rules } => Box::new(rules::and::RuleAnd {
             rules: sort_rules(rules),
+            scratch: Default::default(),
         }),
         Rules::Or { rules } => Box::new(rules::or::RuleOr {
             rules: sort_rules(rules),
+            scratch: Default::default(),
         }),
         Rules::Luminosity(rule) => Box::new(rule),
         Rules::DysonRadius(rule) => Box::new(rule),
@@ -77,9 +90,26 @@ pub fn transform_rules(r: Rules) -> Box<dyn Rule + Send> {
         Rules::ThemeId(rule) => Box::new(rule),
         Rules::PlanetCount(rule) => Box::new(rule),
         Rules::BirthDistance(rule) => Box::new(rule),
-        Rules::XDistance(rule) => Box::new(rule),
-        Rules::SpectrDistance(rule) => Box::new(rule),
+        Rules::XDistance(rule) => Box::new(rules::x_distance::RuleXDistanceEvaluator {
+            rule,
+            candidates: Default::default(),
+        }),
+        Rules::SpectrDistance(rule) => {
+            Box::new(rules::spectr_distance::RuleSpectrDistanceEvaluator {
+                rule,
+                candidates: Default::default(),
+            })
+        }
         Rules::GasRate(rule) => Box::new(rule),
         Rules::PlanetInDysonCount(rule) => Box::new(rule),
+        Rules::StarName(rule) => Box::new(rule),
+        Rules::HabitableRadius(rule) => Box::new(rule),
+        Rules::LightBalanceRadius(rule) => Box::new(rule),
+        Rules::OrbitScaler(rule) => Box::new(rule),
+        Rules::HabitableZoneCount(rule) => Box::new(rule),
+        Rules::RotationPeriod(rule) => Box::new(rule),
+        Rules::RetrogradeCount(rule) => Box::new(rule),
+        Rules::ResonanceCount(rule) => Box::new(rule),
+        Rules::OrbitalRatio(rule) => Box::new(rule),
     }
 }
@@ -0,0 +1,119 @@
+use super::galaxy_gen::{GalaxySlot, StarLayout};
+use crate::data::galaxy::Galaxy;
+use crate::data::game_desc::GameDesc;
+use crate::data::random::DspRandomBatch;
+use crate::data::rule::Rule;
+use crate::data::star::{Star, StarDraws};
+use crate::data::vector3::Vector3;
+
+/// Lanes used by searches whose rules only look at stars.
+pub const SEARCH_LANES: usize = 8;
+
+/// The draws of star `index` of `N` galaxies, one lane per galaxy.
+fn star_draws<const N: usize>(seeds: [i32; N], index: usize) -> [StarDraws; N] {
+    let mut rand1 = DspRandomBatch::new(seeds);
+    let name_seed = rand1.next_seed();
+    let mut rand2 = DspRandomBatch::new(rand1.next_seed());
+    rand1.next_f64();
+    let planets_seed = rand1.next_seed();
+    let r1_1 = rand2.next_f64();
+    let r2_1 = rand2.next_f64();
+    let age_factor = rand2.next_f64();
+    let rn = rand2.next_f64();
+    let rt = rand2.next_f64();
+    let mass_factor = if index == 0 {
+        [0.0; N]
+    } else {
+        rand2.next_f64()
+    };
+    let lifetime_factor = rand2.next_f64();
+    let ry = rand2.next_f64();
+    std::array::from_fn(|lane| StarDraws {
+        name_seed: name_seed[lane],
+        planets_seed: planets_seed[lane],
+        r1_1: r1_1[lane],
+        r2_1: r2_1[lane],
+        age_factor: age_factor[lane],
+        rn: rn[lane],
+        rt: rt[lane],
+        mass_factor: mass_factor[lane],
+        lifetime_factor: lifetime_factor[lane],
+        ry: ry[lane],
+    })
+}
+
+/// Generates the galaxies of `seeds`, at most `N`, in `slots` with their
+/// random streams advanced in lockstep. Star positions depend on collision
+/// checks, so they are still placed one galaxy at a time. Galaxies rejected
+/// by `prefilter` are left without stars (see `GalaxySlot::plan`).
+pub(crate) fn generate_batch<'a, const N: usize>(
+    slots: &mut [GalaxySlot<'a>],
+    games: &[&'a GameDesc],
+    seeds: &[i32],
+    prefilter: Option<&dyn Rule>,
+    drunk: &mut Vec<Vector3>,
+) {
+    // Lanes past the end of `seeds` repeat its last seed and are dropped.
+    let lanes = seeds.len();
+    let mut rand = DspRandomBatch::<N>::new(std::array::from_fn(|lane| seeds[lane.min(lanes - 1)]));
+    let pose_seeds = rand.next_seed();
+    let mut star_counts = [0; N];
+    for lane in 0..lanes {
+        star_counts[lane] =
+            slots[lane].begin(seeds[lane], pose_seeds[lane], games[lane].star_count, drunk);
+    }
+    let (r1, r2, r3, r4) = (
+        rand.next_f32(),
+        rand.next_f32(),
+        rand.next_f32(),
+        rand.next_f32(),
+    );
+    let layouts: [Option<StarLayout>; N] = std::array::from_fn(|lane| {
+        (lane < lanes)
+            .then(|| StarLayout::new(star_counts[lane], r1[lane], r2[lane], r3[lane], r4[lane]))
+            .filter(|layout| slots[lane].plan(layout, prefilter))
+    });
+
+    // Draws past the stars of the galaxies left are not needed.
+    let star_count = (0..N)
+        .filter(|&lane| layouts[lane].is_some())
+        .map(|lane| star_counts[lane])
+        .max()
+        .unwrap_or_default();
+    for index in 0..star_count {
+        let draws = star_draws(rand.next_seed(), index);
+        for (lane, layout) in layouts.iter().enumerate() {
+            let Some(layout) = layout.as_ref().filter(|_| index < star_counts[lane]) else {
+                continue;
+            };
+            let (need_type, need_spectr) = layout.need(index);
+            let slot = &mut slots[lane];
+            slot.put_star(Star::from_draws(
+                games[lane],
+                slot.context(),
+                index,
+                draws[lane],
+                slot.position(index),
+                need_type,
+                &need_spectr,
+            ));
+        }
+    }
+}
+
+/// Same as `generate_galaxy` for each of `games`, generating `N` galaxies at
+/// a time. Worth it when rules only look at stars; planets are generated
+/// lazily as usual.
+pub fn generate_galaxies<const N: usize>(games: &[GameDesc]) -> Vec<Galaxy<'_>> {
+    let mut drunk = vec![];
+    games
+        .chunks(N)
+        .flat_map(|games| {
+            let mut slots: Vec<GalaxySlot> = games.iter().map(|_| GalaxySlot::new()).collect();
+            let seeds: Vec<i32> = games.iter().map(|game| game.seed).collect();
+            let games: Vec<&GameDesc> = games.iter().collect();
+            generate_batch::<N>(&mut slots, &games, &seeds, None, &mut drunk);
+            slots.into_iter().map(GalaxySlot::into_galaxy)
+        })
+        .collect()
+}
@@ -1,6 +1,7 @@
-use super::name_gen::random_name;
+use super::name_gen::star_name;
+use crate::data::context::GenerationContext;
 use crate::data::enums::{SpectrType, StarType};
-use crate::data::galaxy::Galaxy;
+use crate::data::galaxy::{Galaxy, GalaxyLayout};
 use crate::data::game_desc::GameDesc;
 use crate::data::random::DspRandom;
 use crate::data::rule::{Evaluaton, Rule};
@@ -9,7 +10,10 @@ use crate::data::star_planets::StarWithPlanets;
 use crate::data::vector3::Vector3;
 use std::rc::Rc;
 
+#[allow(clippy::too_many_arguments)]
 fn generate_temp_poses(
+    tmp_poses: &mut Vec<Vector3>,
+    tmp_drunk: &mut Vec<Vector3>,
     seed: i32,
     target_count: usize,
     iter_count: usize,
@@ -17,11 +21,12 @@ fn generate_temp_poses(
     min_step_len: f64,
     max_step_len: f64,
     flatten: f64,
-) -> Vec<Vector3> {
-    let mut tmp_poses: Vec<Vector3> = vec![];
+) {
+    tmp_poses.clear();
     let actual_iter_count = iter_count.clamp(1, 16);
     random_poses(
-        &mut tmp_poses,
+        tmp_poses,
+        tmp_drunk,
         seed,
         target_count * actual_iter_count,
         min_dist,
@@ -37,12 +42,11 @@ fn generate_temp_poses(
             break;
         }
     }
-
-    tmp_poses
 }
 
 fn random_poses(
     tmp_poses: &mut Vec<Vector3>,
+    tmp_drunk: &mut Vec<Vector3>,
     seed: i32,
     max_count: usize,
     min_dist: f64,
@@ -51,7 +55,7 @@ fn random_poses(
 ) {
     let mut rand = DspRandom::new(seed);
     let r1 = rand.next_f64();
-    let mut tmp_drunk: Vec<Vector3> = vec![];
+    tmp_drunk.clear();
     tmp_poses.push(Vector3::zero());
     let min_drunk_num = 6;
     let max_drunk_num = 8;
@@ -122,90 +126,234 @@ fn check_collision(tmp_poses: &[Vector3], pt: &Vector3, min_dist: f64) -> bool {
         .any(|pt1| pt1.distance_sq_from(pt) < min_dist_sq)
 }
 
-fn generate_stars(game_desc: &GameDesc) -> Vec<StarWithPlanets<'_>> {
-    let galaxy_seed = game_desc.seed;
-
-    let mut rand = DspRandom::new(galaxy_seed);
-    let tmp_poses = generate_temp_poses(
-        rand.next_seed(),
-        game_desc.star_count,
-        4,
-        2.0,
-        2.3,
-        3.5,
-        0.18,
-    );
-    let star_count = tmp_poses.len();
-
-    let r1 = rand.next_f32();
-    let r2 = rand.next_f32();
-    let r3 = rand.next_f32();
-    let r4 = rand.next_f32();
-    let black_hole_num = (0.01 * (star_count as f64) + (r1 as f64) * 0.3).ceil() as usize;
-    let neutro_star_num = (0.01 * (star_count as f64) + (r2 as f64) * 0.3).ceil() as usize;
-    let white_dwarf_num = (0.016 * (star_count as f64) + (r3 as f64) * 0.4).ceil() as usize;
-    let giant_star_num = (0.013 * (star_count as f64) + (r4 as f64) * 1.3).ceil() as usize;
-    let black_hole_start = star_count - black_hole_num;
-    let neutron_star_start = black_hole_start - neutro_star_num;
-    let white_dwarf_start = neutron_star_start - white_dwarf_num;
-    // Pick a giant star from main seq stars in each giant group
-    let giant_group_num = (white_dwarf_start - 1) / giant_star_num;
-    let giant_offset = giant_group_num / 2;
-
-    let mut stars: Vec<StarWithPlanets> = vec![];
-
-    for (index, position) in tmp_poses.into_iter().enumerate() {
-        let seed = rand.next_seed();
+/// Which star types and spectral classes the game asks for at each index,
+/// decided by four draws of the galaxy's random stream.
+pub(crate) struct StarLayout {
+    black_hole_start: usize,
+    neutron_star_start: usize,
+    white_dwarf_start: usize,
+    giant_group_num: usize,
+    giant_offset: usize,
+}
+
+impl StarLayout {
+    pub(crate) fn new(star_count: usize, r1: f32, r2: f32, r3: f32, r4: f32) -> Self {
+        let black_hole_num = (0.01 * (star_count as f64) + (r1 as f64) * 0.3).ceil() as usize;
+        let neutro_star_num = (0.01 * (star_count as f64) + (r2 as f64) * 0.3).ceil() as usize;
+        let white_dwarf_num = (0.016 * (star_count as f64) + (r3 as f64) * 0.4).ceil() as usize;
+        let giant_star_num = (0.013 * (star_count as f64) + (r4 as f64) * 1.3).ceil() as usize;
+        let black_hole_start = star_count - black_hole_num;
+        let neutron_star_start = black_hole_start - neutro_star_num;
+        let white_dwarf_start = neutron_star_start - white_dwarf_num;
+        // Pick a giant star from main seq stars in each giant group
+        let giant_group_num = (white_dwarf_start - 1) / giant_star_num;
+        let giant_offset = giant_group_num / 2;
+        Self {
+            black_hole_start,
+            neutron_star_start,
+            white_dwarf_start,
+            giant_group_num,
+            giant_offset,
+        }
+    }
+
+    pub(crate) fn need(&self, index: usize) -> (StarType, SpectrType) {
         if index == 0 {
-            stars.push(StarWithPlanets::new(Rc::new(Star::new(
-                game_desc,
+            return (StarType::MainSeqStar, SpectrType::X);
+        }
+        let need_spectr = if index == 3 {
+            SpectrType::M
+        } else if index == self.white_dwarf_start - 1 {
+            SpectrType::O
+        } else {
+            SpectrType::X
+        };
+        let need_type = if index % self.giant_group_num == self.giant_offset {
+            StarType::GiantStar
+        } else if index >= self.black_hole_start {
+            StarType::BlackHole
+        } else if index >= self.neutron_star_start {
+            StarType::NeutronStar
+        } else if index >= self.white_dwarf_start {
+            StarType::WhiteDwarf
+        } else {
+            StarType::MainSeqStar
+        };
+        (need_type, need_spectr)
+    }
+}
+
+/// Positions of the stars of a galaxy. `drunk` is scratch memory.
+pub(crate) fn generate_star_poses(
+    poses: &mut Vec<Vector3>,
+    drunk: &mut Vec<Vector3>,
+    seed: i32,
+    star_count: usize,
+) {
+    generate_temp_poses(poses, drunk, seed, star_count, 4, 2.0, 2.3, 3.5, 0.18)
+}
+
+/// A galaxy and the memory generating it takes. A galaxy generated in the
+/// slot reuses the stars, planets and buffers of the one before.
+pub(crate) struct GalaxySlot<'a> {
+    pub galaxy: Galaxy<'a>,
+    /// Whether the galaxy was left without stars by a pre-filter.
+    pub rejected: bool,
+    poses: Vec<Vector3>,
+    star_types: Vec<StarType>,
+    candidates: Vec<usize>,
+    context: Option<Rc<GenerationContext>>,
+    /// Stands in for stars in planets waiting to be recycled.
+    placeholder: Option<Rc<Star<'a>>>,
+    /// Stars of rejected galaxies, recycled by the next ones.
+    spare_stars: Vec<StarWithPlanets<'a>>,
+}
+
+impl<'a> GalaxySlot<'a> {
+    pub(crate) fn new() -> Self {
+        Self {
+            galaxy: Galaxy {
+                seed: 0,
+                stars: vec![],
+            },
+            rejected: false,
+            poses: vec![],
+            star_types: vec![],
+            candidates: vec![],
+            context: None,
+            placeholder: None,
+            spare_stars: vec![],
+        }
+    }
+
+    pub(crate) fn into_galaxy(self) -> Galaxy<'a> {
+        self.galaxy
+    }
+
+    /// Places the stars of the galaxy of `seed`, whose stars are then set in
+    /// order with `put_star`. Returns the number of stars.
+    pub(crate) fn begin(
+        &mut self,
+        seed: i32,
+        pose_seed: i32,
+        star_count: usize,
+        drunk: &mut Vec<Vector3>,
+    ) -> usize {
+        self.galaxy.seed = seed;
+        self.rejected = false;
+        generate_star_poses(&mut self.poses, drunk, pose_seed, star_count);
+        let star_count = self.poses.len();
+        match &self.context {
+            Some(context) if context.star_capacity() >= star_count => context.reset(),
+            _ => self.context = Some(Rc::new(GenerationContext::new(star_count))),
+        }
+        self.galaxy.stars.truncate(star_count);
+        star_count
+    }
+
+    /// Returns whether `prefilter` may match the galaxy judging by where its
+    /// stars are and the types `layout` gives them. If not, the galaxy is left
+    /// without stars and must not be given any.
+    pub(crate) fn plan(&mut self, layout: &StarLayout, prefilter: Option<&dyn Rule>) -> bool {
+        let Some(rule) = prefilter else {
+            return true;
+        };
+        self.star_types.clear();
+        self.star_types
+            .extend((0..self.poses.len()).map(|index| layout.need(index).0));
+        let layout = GalaxyLayout {
+            positions: &self.poses,
+            star_types: &self.star_types,
+        };
+        self.candidates.clear();
+        if rule.evaluate_layout(&layout, &mut self.candidates) && self.candidates.is_empty() {
+            self.spare_stars.append(&mut self.galaxy.stars);
+            self.rejected = true;
+        }
+        !self.rejected
+    }
+
+    pub(crate) fn context(&self) -> Rc<GenerationContext> {
+        self.context.clone().expect("galaxy must be begun")
+    }
+
+    pub(crate) fn position(&self, index: usize) -> Vector3 {
+        self.poses[index].clone()
+    }
+
+    pub(crate) fn put_star(&mut self, star: Star<'a>) {
+        if star.index == self.galaxy.stars.len() {
+            match self.spare_stars.pop() {
+                Some(sp) => self.galaxy.stars.push(sp),
+                None => {
+                    self.galaxy.stars.push(StarWithPlanets::new(Rc::new(star)));
+                    return;
+                }
+            }
+        }
+        let placeholder = self.placeholder.get_or_insert_with(|| {
+            Rc::new(Star::new(
+                star.game_desc,
+                star.context.clone(),
+                0,
                 0,
-                seed,
                 Vector3::zero(),
                 StarType::MainSeqStar,
                 &SpectrType::X,
-            ))));
-        } else {
-            let need_spectr = if index == 3 {
-                SpectrType::M
-            } else if index == white_dwarf_start - 1 {
-                SpectrType::O
-            } else {
-                SpectrType::X
-            };
-            let need_type = if index % giant_group_num == giant_offset {
-                StarType::GiantStar
-            } else if index >= black_hole_start {
-                StarType::BlackHole
-            } else if index >= neutron_star_start {
-                StarType::NeutronStar
-            } else if index >= white_dwarf_start {
-                StarType::WhiteDwarf
-            } else {
-                StarType::MainSeqStar
-            };
-            stars.push(StarWithPlanets::new(Rc::new(Star::new(
+            ))
+        });
+        self.galaxy.stars[star.index].recycle(star, placeholder);
+    }
+
+    /// Generates the galaxy of `seed` in the slot, unless `prefilter` rejects
+    /// it (see `plan`).
+    pub(crate) fn generate(
+        &mut self,
+        game_desc: &'a GameDesc,
+        seed: i32,
+        prefilter: Option<&dyn Rule>,
+        drunk: &mut Vec<Vector3>,
+    ) {
+        let mut rand = DspRandom::new(seed);
+        let star_count = self.begin(seed, rand.next_seed(), game_desc.star_count, drunk);
+
+        let r1 = rand.next_f32();
+        let r2 = rand.next_f32();
+        let r3 = rand.next_f32();
+        let r4 = rand.next_f32();
+        let layout = StarLayout::new(star_count, r1, r2, r3, r4);
+        if !self.plan(&layout, prefilter) {
+            return;
+        }
+
+        for index in 0..star_count {
+            let seed = rand.next_seed();
+            let (need_type, need_spectr) = layout.need(index);
+            self.put_star(Star::new(
                 game_desc,
+                self.context(),
                 index,
                 seed,
-                position,
+                self.position(index),
                 need_type,
                 &need_spectr,
-            ))));
+            ));
         }
     }
-    stars
+}
+
+fn generate_stars(game_desc: &GameDesc) -> Vec<StarWithPlanets<'_>> {
+    let mut slot = GalaxySlot::new();
+    slot.generate(game_desc, game_desc.seed, None, &mut vec![]);
+    slot.into_galaxy().stars
 }
 
 pub fn create_galaxy(game_desc: &GameDesc) -> Galaxy<'_> {
     let mut stars = generate_stars(game_desc);
-    let mut names: Vec<&str> = vec![];
 
-    for sp in stars.iter_mut() {
-        let name = random_name(sp.star.name_seed, &sp.star, names.iter());
-        sp.name = name;
-        names.push(&sp.name);
-        sp.load_planets();
+    for index in 0..stars.len() {
+        stars[index].name = star_name(&stars, index);
+        stars[index].load_planets();
     }
 
     Galaxy {
@@ -214,13 +362,32 @@ pub fn create_galaxy(game_desc: &GameDesc) -> Galaxy<'_> {
     }
 }
 
-pub fn find_stars(game_desc: &GameDesc, rule: &mut Box<dyn Rule + Send>) -> Vec<usize> {
-    let galaxy = Galaxy {
+/// Generates the stars of a galaxy without names. Planets are generated lazily,
+/// so callers touching them must keep the star order (see `StarWithPlanets::is_safe`).
+/// Names are available through `Galaxy::get_star_name`.
+pub fn generate_galaxy(game_desc: &GameDesc) -> Galaxy<'_> {
+    Galaxy {
         seed: game_desc.seed,
         stars: generate_stars(game_desc),
-    };
+    }
+}
 
-    let evaluation = Evaluaton::new(game_desc.star_count);
+pub fn find_stars(game_desc: &GameDesc, rule: &mut Box<dyn Rule + Send>) -> Vec<usize> {
+    let galaxy = generate_galaxy(game_desc);
 
-    rule.evaluate(&galaxy, &evaluation)
+    // Fewer stars than asked for may fit in the galaxy; sizing the evaluation
+    // to the placed ones lets it shrink past the last star still unknown.
+    let evaluation = Evaluaton::new(galaxy.stars.len());
+    let mut result = vec![];
+    rule.evaluate(&galaxy, &evaluation, &mut result);
+    result
+}
+
+/// For each of `indexes`, the planets of that star the rule is about.
+pub fn find_planets(game_desc: &GameDesc, rule: &dyn Rule, indexes: &[usize]) -> Vec<Vec<usize>> {
+    let galaxy = create_galaxy(game_desc);
+    indexes
+        .iter()
+        .map(|&index| rule.matching_planets(&galaxy.stars[index]))
+        .collect()
 }
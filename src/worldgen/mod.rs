@@ -1,2 +1,4 @@
+pub mod batch_gen;
 pub mod galaxy_gen;
-mod name_gen;
+pub mod name_gen;
+pub mod workspace;
@@ -1,6 +1,7 @@
 use crate::data::enums::StarType;
 use crate::data::random::DspRandom;
 use crate::data::star::Star;
+use crate::data::star_planets::StarWithPlanets;
 
 fn gen_random_name(seed: i32, star: &Star) -> String {
     let mut rand = DspRandom::new(seed);
@@ -28,12 +29,12 @@ fn gen_random_name(seed: i32, star: &Star) -> String {
     }
 }
 
-pub fn random_name<'a>(
-    seed: i32,
-    star: &Star,
-    mut names: impl Iterator<Item = &'a &'a str>,
-) -> String {
+/// Picks a name for `star` that differs from `names`, the names of the stars
+/// before it. As in the game, each attempt only compares with the names after
+/// the one the previous attempt matched.
+pub fn random_name(seed: i32, star: &Star, names: &[String]) -> String {
     let mut rand = DspRandom::new(seed);
+    let mut names = names.iter();
     for _ in 0..256 {
         let str = gen_random_name(rand.next_seed(), star);
         if names.all(|s| *s != str) {
@@ -43,6 +44,20 @@ pub fn random_name<'a>(
     "XStar".to_owned()
 }
 
+/// Name of the star at `index`. Names must differ from those of earlier stars,
+/// so those are generated first and kept in the generation context.
+pub fn star_name(stars: &[StarWithPlanets], index: usize) -> String {
+    let context = &stars[index].star.context;
+    let mut named = context.names().len();
+    while named <= index {
+        let star = &stars[named].star;
+        let name = random_name(star.get_name_seed(), star, &context.names());
+        context.add_name(name);
+        named += 1;
+    }
+    context.names()[index].clone()
+}
+
 fn random_giant_star_name_with_constellation_alpha(seed: i32) -> String {
     let mut rand = DspRandom::new(seed);
     let num1 = rand.next_usize();
@@ -0,0 +1,100 @@
+use super::batch_gen::generate_batch;
+use super::galaxy_gen::GalaxySlot;
+use crate::data::galaxy::Galaxy;
+use crate::data::game_desc::GameDesc;
+use crate::data::rule::{Evaluaton, Rule};
+use crate::data::vector3::Vector3;
+
+/// Memory a search worker keeps from one seed to the next. Galaxies are
+/// generated in the place of the previous ones and rules evaluated into
+/// reused buffers, so once warmed up, evaluating a seed doesn't allocate.
+/// Naming stars still does.
+pub struct Workspace<'a> {
+    game_desc: &'a GameDesc,
+    slots: Vec<GalaxySlot<'a>>,
+    drunk: Vec<Vector3>,
+    evaluation: Evaluaton,
+    result: Vec<usize>,
+}
+
+impl<'a> Workspace<'a> {
+    /// Galaxies are generated as described by `game_desc`, except for their
+    /// seed.
+    pub fn new(game_desc: &'a GameDesc) -> Self {
+        Self {
+            game_desc,
+            slots: vec![],
+            drunk: vec![],
+            evaluation: Evaluaton::default(),
+            result: vec![],
+        }
+    }
+
+    /// Generates the galaxy of `seed`, available as `galaxy(0)`.
+    pub fn generate(&mut self, seed: i32) {
+        self.generate_lanes::<1>(&[seed], None);
+    }
+
+    /// Generates the galaxies of `seeds`, at most `N`, with their random
+    /// streams in lockstep like `generate_galaxies`. The galaxy of `seeds[i]`
+    /// is `galaxy(i)`.
+    pub fn generate_batch<const N: usize>(&mut self, seeds: &[i32]) {
+        self.generate_lanes::<N>(seeds, None);
+    }
+
+    /// Same as `generate`, but leaves the galaxy without stars when `rule`
+    /// can't match it judging by its layout (see `Rule::evaluate_layout`).
+    pub fn generate_matching(&mut self, seed: i32, rule: &dyn Rule) {
+        self.generate_lanes::<1>(&[seed], Some(rule));
+    }
+
+    /// Same as `generate_batch`, but leaves the galaxies `rule` can't match
+    /// judging by their layout without stars.
+    pub fn generate_batch_matching<const N: usize>(&mut self, seeds: &[i32], rule: &dyn Rule) {
+        self.generate_lanes::<N>(seeds, Some(rule));
+    }
+
+    fn generate_lanes<const N: usize>(&mut self, seeds: &[i32], prefilter: Option<&dyn Rule>) {
+        assert!(seeds.len() <= N, "at most {} seeds at a time", N);
+        while self.slots.len() < seeds.len() {
+            self.slots.push(GalaxySlot::new());
+        }
+        if N == 1 {
+            self.slots[0].generate(self.game_desc, seeds[0], prefilter, &mut self.drunk);
+        } else {
+            let games = [self.game_desc; N];
+            generate_batch::<N>(
+                &mut self.slots,
+                &games[..seeds.len()],
+                seeds,
+                prefilter,
+                &mut self.drunk,
+            );
+        }
+    }
+
+    /// The galaxy last generated in `lane`, without stars if it was rejected
+    /// by its layout.
+    pub fn galaxy(&self, lane: usize) -> &Galaxy<'a> {
+        &self.slots[lane].galaxy
+    }
+
+    /// Whether the galaxy last generated in `lane` was left without stars
+    /// because its layout can't match the rule.
+    pub fn is_rejected(&self, lane: usize) -> bool {
+        self.slots[lane].rejected
+    }
+
+    /// Same as `find_stars` for `galaxy(lane)`. Finds nothing in rejected
+    /// galaxies.
+    pub fn find_stars(&mut self, lane: usize, rule: &dyn Rule) -> &[usize] {
+        self.result.clear();
+        if self.is_rejected(lane) {
+            return &self.result;
+        }
+        let galaxy = &self.slots[lane].galaxy;
+        self.evaluation.reset(galaxy.stars.len());
+        rule.evaluate(galaxy, &self.evaluation, &mut self.result);
+        &self.result
+    }
+}
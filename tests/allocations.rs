@@ -0,0 +1,98 @@
+use dsp_seed_finder::{parse_query, transform_rules, GameDesc, Workspace};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// Counts the allocations of the thread that asked for it, so the test harness
+// and other tests running meanwhile don't show up.
+struct CountingAllocator;
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+fn count() {
+    // Thread locals may already be gone while a thread shuts down.
+    let _ = COUNTING.try_with(|counting| {
+        if counting.get() {
+            ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        }
+    });
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations_in(f: impl FnOnce()) -> usize {
+    ALLOCATIONS.with(|allocations| allocations.set(0));
+    COUNTING.with(|counting| counting.set(true));
+    f();
+    COUNTING.with(|counting| counting.set(false));
+    ALLOCATIONS.with(|allocations| allocations.get())
+}
+
+#[test]
+fn warmed_up_workspace_evaluates_seeds_without_allocating_per_seed() {
+    let game = GameDesc {
+        seed: 0,
+        star_count: 64,
+        resource_multiplier: 1.0,
+    };
+    let rules: Vec<_> = [
+        "star where luminosity > 1 and dist(birth) < 20 or spectr in (O, B)",
+        "star where dist(X, count >= 2) < 10 or dyson_radius > 1.5",
+        "galaxy where count(spectr = O and dist(birth) < 10) >= 1",
+        "star where planets >= 4 and satellites >= 1",
+        "star where tidal_locked >= 2 or habitable_zone >= 2 or retrograde >= 1",
+        "star where rotation_period < 200 and resonance(OneToTwo) >= 1 or orbital_ratio(2)",
+        "star where theme in (8, 9) and gas(ice=false) >= 1 or ocean = Sulfur",
+        "star where vein(Mag) >= 0.5 or vein(Grat) >= 20 and gas_rate(Hydrogen) > 1",
+    ]
+    .into_iter()
+    .map(|query| transform_rules(parse_query(query).unwrap()))
+    .collect();
+
+    let mut workspace = Workspace::new(&game);
+    let mut evaluate = |seeds: &[i32]| {
+        for chunk in seeds.chunks(8) {
+            for rule in &rules {
+                if let [seed] = chunk {
+                    workspace.generate(*seed);
+                } else {
+                    workspace.generate_batch::<8>(chunk);
+                }
+                for lane in 0..chunk.len() {
+                    workspace.find_stars(lane, &**rule);
+                }
+            }
+        }
+    };
+    // Slots for stars and planets, and the vein and gas buffers of planets,
+    // grow to the most seen in their place. Seeds not seen during the warm-up
+    // still allocate when they have more of these somewhere, which becomes
+    // rarer the more seeds were seen, but nothing is allocated for each seed
+    // or star. The last seed has a chunk to itself.
+    let warm_up: Vec<i32> = (0..800).collect();
+    evaluate(&warm_up);
+    let seeds: Vec<i32> = (1_000_000..=1_000_096).collect();
+    let allocations = allocations_in(|| evaluate(&seeds));
+    assert!(allocations < seeds.len(), "{}", allocations);
+    // Seeds evaluated again find every buffer large enough.
+    assert_eq!(allocations_in(|| evaluate(&seeds)), 0);
+}
@@ -0,0 +1,276 @@
+use dsp_seed_finder::{
+    parse_query, run_worker, search, Coordinator, CoordinatorOptions, GameDesc, Rules, SearchEvent,
+    SeedSelection, Workers,
+};
+use std::net::SocketAddr;
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+fn game() -> GameDesc {
+    GameDesc {
+        seed: 0,
+        star_count: 64,
+        resource_multiplier: 1.0,
+    }
+}
+
+fn rule() -> Rules {
+    parse_query("star where luminosity > 1.5 and dist(birth) < 30").unwrap()
+}
+
+fn local_results(seeds: &SeedSelection) -> Vec<(i32, Vec<usize>)> {
+    let results = Mutex::new(vec![]);
+    search(
+        &game(),
+        &rule(),
+        seeds,
+        Workers::new(2),
+        0,
+        true,
+        &AtomicBool::new(false),
+        |event| {
+            if let SearchEvent::Result { seed, indexes } = event {
+                results.lock().unwrap().push((seed, indexes));
+            }
+        },
+    );
+    results.into_inner().unwrap()
+}
+
+async fn start_coordinator() -> (std::sync::Arc<Coordinator>, SocketAddr) {
+    Coordinator::bind(
+        "127.0.0.1:0",
+        CoordinatorOptions {
+            chunk_size: 40,
+            lease: Duration::from_secs(60),
+        },
+    )
+    .await
+    .unwrap()
+}
+
+fn start_worker(addr: SocketAddr) {
+    tokio::spawn(async move { run_worker(&addr.to_string(), Workers::new(1)).await });
+}
+
+async fn wait_for_workers(coordinator: &Coordinator, count: usize) {
+    while coordinator.worker_count() < count {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+/// Runs a job to its end, returning its results in the order they came and
+/// the end of `Done`.
+async fn run_job(
+    coordinator: &Coordinator,
+    seeds: &SeedSelection,
+    ordered: bool,
+) -> (Vec<(i32, Vec<usize>)>, usize) {
+    let mut events = coordinator.start(game(), rule(), seeds.clone(), ordered);
+    let mut results = vec![];
+    let job = async {
+        while let Some(event) = events.recv().await {
+            match event {
+                SearchEvent::Result { seed, indexes } => results.push((seed, indexes)),
+                SearchEvent::Progress { .. } => {}
+                SearchEvent::Done { end, .. } => return end,
+            }
+        }
+        panic!("no Done event");
+    };
+    let end = tokio::time::timeout(Duration::from_secs(120), job)
+        .await
+        .expect("job timed out");
+    (results, end)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn workers_on_localhost_find_the_same_seeds_as_a_local_search() {
+    let (coordinator, addr) = start_coordinator().await;
+    start_worker(addr);
+    start_worker(addr);
+    wait_for_workers(&coordinator, 2).await;
+
+    let seeds = SeedSelection::range(-100, 201);
+    let expected = local_results(&seeds);
+
+    let (mut results, end) = run_job(&coordinator, &seeds, false).await;
+    assert_eq!(end, seeds.len());
+    results.sort();
+    assert_eq!(results, expected);
+
+    // A second job goes to the same workers, with results across chunks in
+    // seed order.
+    let (results, end) = run_job(&coordinator, &seeds, true).await;
+    assert_eq!(end, seeds.len());
+    assert_eq!(results, expected);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn chunks_of_a_disconnected_worker_are_handed_to_another() {
+    let (coordinator, addr) = start_coordinator().await;
+
+    // A worker that leases a chunk, then goes away without completing it.
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    write
+        .write_all(b"{\"type\":\"Hello\",\"threads\":1}\n")
+        .await
+        .unwrap();
+    wait_for_workers(&coordinator, 1).await;
+
+    let seeds = SeedSelection::range(0, 120);
+    let expected = local_results(&seeds);
+    let job = {
+        let coordinator = coordinator.clone();
+        let seeds = seeds.clone();
+        tokio::spawn(async move { run_job(&coordinator, &seeds, false).await })
+    };
+
+    let message = lines.next_line().await.unwrap().unwrap();
+    assert!(message.contains("\"type\":\"Job\""), "{}", message);
+    write
+        .write_all(b"{\"type\":\"Request\",\"job\":1}\n")
+        .await
+        .unwrap();
+    let message = lines.next_line().await.unwrap().unwrap();
+    assert!(message.contains("\"type\":\"Chunk\""), "{}", message);
+    drop((lines, write));
+    while coordinator.worker_count() > 0 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    start_worker(addr);
+    let (mut results, end) = job.await.unwrap();
+    assert_eq!(end, seeds.len());
+    results.sort();
+    assert_eq!(results, expected);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn completions_outside_a_lease_or_its_chunk_are_dropped() {
+    let (coordinator, addr) = start_coordinator().await;
+
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    write
+        .write_all(b"{\"type\":\"Hello\",\"threads\":1}\n")
+        .await
+        .unwrap();
+    wait_for_workers(&coordinator, 1).await;
+
+    let seeds = SeedSelection::range(0, 120);
+    let expected = local_results(&seeds);
+    let job = {
+        let coordinator = coordinator.clone();
+        let seeds = seeds.clone();
+        tokio::spawn(async move { run_job(&coordinator, &seeds, false).await })
+    };
+    let message = lines.next_line().await.unwrap().unwrap();
+    assert!(message.contains("\"type\":\"Job\""), "{}", message);
+
+    // Completing a chunk that was never leased leaves it to be handed out.
+    let forged = (1_000_000, vec![0]);
+    let complete = |chunk: usize, results: &[(i32, Vec<usize>)]| {
+        let message = serde_json::json!({
+            "type": "Complete",
+            "job": 1,
+            "chunk": chunk,
+            "results": results,
+        });
+        format!("{}\n", message)
+    };
+    write
+        .write_all(complete(0, std::slice::from_ref(&forged)).as_bytes())
+        .await
+        .unwrap();
+    write
+        .write_all(b"{\"type\":\"Request\",\"job\":1}\n")
+        .await
+        .unwrap();
+    let message = lines.next_line().await.unwrap().unwrap();
+    assert!(message.contains("\"chunk\":0"), "{}", message);
+
+    // Once leased, results for seeds of other chunks are dropped.
+    let mut results = local_results(&SeedSelection::range(0, 40));
+    results.push(forged);
+    write
+        .write_all(complete(0, &results).as_bytes())
+        .await
+        .unwrap();
+
+    start_worker(addr);
+    let (mut results, end) = job.await.unwrap();
+    assert_eq!(end, seeds.len());
+    results.sort();
+    assert_eq!(results, expected);
+    drop((lines, write));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn chunks_wait_while_events_are_not_read() {
+    let (coordinator, addr) = Coordinator::bind(
+        "127.0.0.1:0",
+        CoordinatorOptions {
+            chunk_size: 1,
+            lease: Duration::from_secs(60),
+        },
+    )
+    .await
+    .unwrap();
+    let stream = TcpStream::connect(addr).await.unwrap();
+    stream.set_nodelay(true).unwrap();
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    wait_for_workers(&coordinator, 1).await;
+
+    let mut events = coordinator.start(game(), rule(), SeedSelection::range(0, 20000), false);
+    let message = lines.next_line().await.unwrap().unwrap();
+    assert!(message.contains("\"type\":\"Job\""), "{}", message);
+
+    // Every completed chunk is a progress event, none of which are read.
+    let mut completed = 0;
+    loop {
+        write
+            .write_all(b"{\"type\":\"Request\",\"job\":1}\n")
+            .await
+            .unwrap();
+        let message = lines.next_line().await.unwrap().unwrap();
+        if message.contains("\"type\":\"Wait\"") {
+            break;
+        }
+        let chunk: serde_json::Value = serde_json::from_str(&message).unwrap();
+        let complete = serde_json::json!({
+            "type": "Complete",
+            "job": 1,
+            "chunk": chunk["chunk"],
+            "results": [],
+        });
+        write
+            .write_all(format!("{}\n", complete).as_bytes())
+            .await
+            .unwrap();
+        completed += 1;
+        assert!(completed < 20000, "chunks were handed out without waiting");
+    }
+
+    let mut read = 0;
+    while read < completed {
+        match events.recv().await.unwrap() {
+            SearchEvent::Progress { end, .. } => read = end,
+            event => panic!("unexpected {:?}", event),
+        }
+    }
+    write
+        .write_all(b"{\"type\":\"Request\",\"job\":1}\n")
+        .await
+        .unwrap();
+    let message = lines.next_line().await.unwrap().unwrap();
+    assert!(message.contains("\"type\":\"Chunk\""), "{}", message);
+    coordinator.stop();
+}
@@ -0,0 +1,652 @@
+use dsp_seed_finder::{
+    create_galaxy, find_planets, find_stars, format_query, generate_galaxies, generate_galaxy,
+    normalize_rules, parse_query, rank_seeds, rank_x_stars, search, transform_rules, GameDesc,
+    Locale, Localize, Rules, SearchEvent, SeedSelection, SeedSource, Shard, StarType, Workers,
+    Workspace, XStarEntry, XStarWeights,
+};
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+
+fn game(seed: i32) -> GameDesc {
+    GameDesc {
+        seed,
+        star_count: 64,
+        resource_multiplier: 1.0,
+    }
+}
+
+fn galaxy_json(game: &GameDesc) -> String {
+    serde_json::to_string(&create_galaxy(game)).unwrap()
+}
+
+#[test]
+fn galaxy_does_not_depend_on_previous_galaxies() {
+    let fresh: Vec<String> = (0..8).map(|seed| galaxy_json(&game(seed))).collect();
+
+    let mut reused = game(100);
+    galaxy_json(&reused);
+    for seed in (0..8).rev() {
+        reused.seed = seed;
+        assert_eq!(galaxy_json(&reused), fresh[seed as usize], "seed {}", seed);
+    }
+}
+
+#[test]
+fn galaxy_is_the_same_when_generated_twice() {
+    let game = game(42);
+    assert_eq!(galaxy_json(&game), galaxy_json(&game));
+}
+
+#[test]
+fn search_does_not_depend_on_previous_seeds() {
+    let rule: Rules = serde_json::from_str(
+        r#"{"type":"Or","rules":[
+            {"type":"Spectr","spectr":["O"]},
+            {"type":"OceanType","oceanType":1000}
+        ]}"#,
+    )
+    .unwrap();
+    let mut transformed = transform_rules(rule.clone());
+    let mut reused = game(0);
+    let sequential: Vec<Vec<usize>> = (0..50)
+        .map(|seed| {
+            reused.seed = seed;
+            find_stars(&reused, &mut transformed)
+        })
+        .collect();
+    for seed in (0..50).rev() {
+        let fresh = find_stars(&game(seed), &mut transform_rules(rule.clone()));
+        assert_eq!(sequential[seed as usize], fresh, "seed {}", seed);
+    }
+}
+
+#[test]
+fn lazy_star_names_match_full_generation() {
+    for seed in 0..8 {
+        let game = game(seed);
+        let full = create_galaxy(&game);
+        let lazy = generate_galaxy(&game);
+        // Asking for a late star first must still name earlier stars in order.
+        let last = full.stars.len() - 1;
+        assert_eq!(lazy.get_star_name(last), full.stars[last].name);
+        for (index, star) in full.stars.iter().enumerate() {
+            assert_eq!(lazy.get_star_name(index), star.name, "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn star_names_keep_the_duplicates_of_the_game() {
+    // Each retry only compares with the names after the one the last attempt
+    // matched, so star 56 of seed 1 gets the name of star 35 again.
+    let game = game(1);
+    let galaxy = create_galaxy(&game);
+    assert_eq!(galaxy.stars[35].name, "ι Coronae Borealis");
+    assert_eq!(galaxy.stars[56].name, "ι Coronae Borealis");
+    assert_eq!(
+        generate_galaxy(&game).get_star_name(56),
+        "ι Coronae Borealis"
+    );
+}
+
+#[test]
+fn star_name_rule_finds_named_stars() {
+    let game = game(5);
+    let name = create_galaxy(&game).stars[6].name.clone();
+    let rule: Rules = serde_json::from_value(serde_json::json!({
+        "type": "StarName",
+        "name": name.to_uppercase(),
+        "ignoreCase": true,
+    }))
+    .unwrap();
+    assert_eq!(find_stars(&game, &mut transform_rules(rule)), vec![6]);
+
+    let invalid = r#"{"type":"StarName","name":"(","mode":"regex"}"#;
+    assert!(serde_json::from_str::<Rules>(invalid).is_err());
+}
+
+#[test]
+fn planet_rules_report_the_matching_planets() {
+    let game = game(0);
+    let rule: Rules =
+        serde_json::from_str(r#"{"type":"RetrogradeCount","condition":{"type":"Gte","value":1}}"#)
+            .unwrap();
+    let indexes = find_stars(&game, &mut transform_rules(rule.clone()));
+    assert!(!indexes.is_empty());
+    let planets = find_planets(&game, transform_rules(rule).as_ref(), &indexes);
+    let galaxy = create_galaxy(&game);
+    for (index, planets) in indexes.iter().zip(planets) {
+        let expected: Vec<usize> = galaxy.stars[*index]
+            .get_planets()
+            .filter(|planet| planet.is_retrograde())
+            .map(|planet| planet.index)
+            .collect();
+        assert!(!planets.is_empty(), "star {}", index);
+        assert_eq!(planets, expected, "star {}", index);
+    }
+}
+
+#[test]
+fn x_star_ranking_keeps_the_best_seeds() {
+    let weights = XStarWeights::default();
+    let seeds = SeedSelection::range(0, 40);
+    let best = rank_seeds(&game(0), &seeds, &weights, 5, 4);
+    assert_eq!(best, rank_seeds(&game(0), &seeds, &weights, 5, 1));
+    assert_eq!(best.len(), 5);
+    let scores: Vec<f64> = (0..40)
+        .filter_map(|seed| rank_x_stars(&game(seed), &weights).best_score())
+        .collect();
+    let max = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    assert_eq!(best[0].best_score(), Some(max));
+    for pair in best.windows(2) {
+        assert!(pair[0].best_score() >= pair[1].best_score());
+    }
+}
+
+#[test]
+fn x_stars_near_a_blue_giant_outrank_galaxies_without_one() {
+    let weights = XStarWeights {
+        giant_distance: 1.0,
+        ..XStarWeights::default()
+    };
+    let near = XStarEntry {
+        index: 60,
+        name: "Near".to_owned(),
+        star_type: StarType::NeutronStar,
+        mag: 10000.0,
+        grat: 0.0,
+        fractal: 0.0,
+        birth_distance: 20.0,
+        giant_distance: Some(60.0),
+        score: 0.0,
+    };
+    let none = XStarEntry {
+        giant_distance: None,
+        ..near.clone()
+    };
+    assert!(near.score(&weights) > none.score(&weights));
+    // Without a weight on it, the distance doesn't count.
+    let weights = XStarWeights::default();
+    assert_eq!(near.score(&weights), none.score(&weights));
+}
+
+#[test]
+fn items_are_read_by_name_or_id_and_written_by_name() {
+    let by_id: Rules = serde_json::from_str(
+        r#"{"type":"Or","rules":[
+            {"type":"GasRate","gasType":1121,"condition":{"type":"Gte","value":0.5}},
+            {"type":"OceanType","oceanType":1116}
+        ]}"#,
+    )
+    .unwrap();
+    let by_name: Rules = serde_json::from_str(
+        r#"{"type":"Or","rules":[
+            {"type":"GasRate","gasType":"Deuterium","condition":{"type":"Gte","value":0.5}},
+            {"type":"OceanType","oceanType":"Sulfur"}
+        ]}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        serde_json::to_string(&by_id).unwrap(),
+        serde_json::to_string(&by_name).unwrap()
+    );
+    for seed in 0..8 {
+        let game = game(seed);
+        assert_eq!(
+            find_stars(&game, &mut transform_rules(by_id.clone())),
+            find_stars(&game, &mut transform_rules(by_name.clone()))
+        );
+    }
+    assert!(serde_json::from_str::<Rules>(r#"{"type":"OceanType","oceanType":1001}"#).is_err());
+
+    let galaxy: serde_json::Value = serde_json::from_str(&galaxy_json(&game(0))).unwrap();
+    let gases: Vec<&serde_json::Value> = galaxy["stars"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|star| star["planets"].as_array().unwrap())
+        .flat_map(|planet| planet["gases"].as_array().unwrap())
+        .collect();
+    assert!(!gases.is_empty());
+    for gas in gases {
+        let name = gas[0].as_str().unwrap();
+        assert!(["Fireice", "Hydrogen", "Deuterium"].contains(&name));
+    }
+}
+
+#[test]
+fn every_theme_has_localized_names() {
+    for seed in 0..8 {
+        let game = game(seed);
+        let galaxy = create_galaxy(&game);
+        for sp in &galaxy.stars {
+            for planet in sp.get_planets() {
+                let theme = planet.get_theme();
+                let en = theme.localized(Locale::En);
+                let zh = theme.localized(Locale::Zh);
+                assert!(!en.is_empty() && !zh.is_empty(), "theme {}", theme.id);
+                assert_ne!(en, zh, "theme {}", theme.id);
+            }
+        }
+    }
+}
+
+#[test]
+fn queries_parse_to_rules_and_print_back() {
+    let query = "star where luminosity > 2 and gas(ice=true) >= 2 and dist(birth) < 10";
+    let json: Rules = serde_json::from_str(
+        r#"{"type":"And","rules":[
+            {"type":"Luminosity","condition":{"type":"Gt","value":2}},
+            {"type":"GasCount","ice":true,"condition":{"type":"Gte","value":2}},
+            {"type":"BirthDistance","condition":{"type":"Lt","value":10}}
+        ]}"#,
+    )
+    .unwrap();
+    let parsed = parse_query(query).unwrap();
+    assert_eq!(
+        serde_json::to_string(&parsed).unwrap(),
+        serde_json::to_string(&json).unwrap()
+    );
+    assert_eq!(format_query(&json), query);
+
+    for query in [
+        "star where spectr in (O, B) or name(ignore_case=true) ^= \"al\" and planets not in [2, 4]",
+        "star where (vein(Mag) >= 0.5 or ocean = Sulfur) and dist(X, count >= 2) in [0, 3.5]",
+        "star where theme = 8 and orbital_ratio(3, tolerance=0.02) and resonance(OneToTwo) >= 1",
+        "galaxy where count(spectr = O and dist(birth) < 10) >= 2 or count(birth) = 1",
+    ] {
+        let rule = parse_query(query).unwrap();
+        assert_eq!(format_query(&rule), query);
+        for seed in 0..4 {
+            let game = game(seed);
+            let printed = parse_query(&format_query(&rule)).unwrap();
+            assert_eq!(
+                find_stars(&game, &mut transform_rules(rule.clone())),
+                find_stars(&game, &mut transform_rules(printed))
+            );
+        }
+    }
+}
+
+#[test]
+fn query_errors_point_at_the_problem() {
+    let query = "star where luminosity > 2 and gaz(ice=true) >= 2";
+    let err = parse_query(query).unwrap_err();
+    assert_eq!(&query[err.span.clone()], "gaz");
+    assert!(err
+        .render(query)
+        .ends_with("\n                                ^^^"));
+
+    for (query, at) in [
+        ("star where luminosity >", "".to_owned()),
+        ("star where spectr = Q", "= Q".to_owned()),
+        ("star where birth and (luminosity > 1", "".to_owned()),
+        (
+            "star where dist(birth, all=true) < 3",
+            "all=true".to_owned(),
+        ),
+        (
+            "star where birth and count(birth) >= 1",
+            "count(birth) >= 1".to_owned(),
+        ),
+        ("star where name ~ \"(\"", "~ \"(\"".to_owned()),
+        ("star where name = \"abc\\", "\"abc\\".to_owned()),
+    ] {
+        let err = parse_query(query).unwrap_err();
+        assert_eq!(query[err.span.clone()], at, "{}", query);
+    }
+}
+
+#[test]
+fn comparisons_take_negative_numbers_without_a_space() {
+    for (tight, spaced) in [
+        ("star where luminosity >-1", "star where luminosity > -1"),
+        (
+            "star where vein(Iron) >-0.5",
+            "star where vein(Iron) > -0.5",
+        ),
+        ("star where luminosity <=-2", "star where luminosity <= -2"),
+        (
+            "star where luminosity ~= -1 +-0.5",
+            "star where luminosity ~= -1 +- 0.5",
+        ),
+    ] {
+        assert_eq!(
+            format_query(&parse_query(tight).unwrap()),
+            format_query(&parse_query(spaced).unwrap()),
+            "{}",
+            tight
+        );
+    }
+}
+
+#[test]
+fn normalized_rules_find_the_same_stars() {
+    for query in [
+        "star where (luminosity > 1 and (luminosity > 1.2 and dist(birth) < 30)) and luminosity <= 5",
+        "star where (spectr = O or (spectr = B or spectr = O)) and planets >= 2 and planets in [0, 6]",
+        "star where (star_type = BlackHole and gas >= 1) or satellites >= 1 or all and birth",
+        "galaxy where count(star_type = NeutronStar and planets = 2) < 1 and count(spectr = O) >= 1",
+        // Some X star is near and another far, not one distance in both.
+        "star where dist(x) < 3 and (dist(x) > 15 or luminosity > 1000000) and luminosity > 0 and luminosity >= 0",
+    ] {
+        let rule = parse_query(query).unwrap();
+        let normalized = normalize_rules(rule.clone()).unwrap();
+        assert_ne!(format_query(&normalized), format_query(&rule));
+        for seed in 0..8 {
+            let game = game(seed);
+            assert_eq!(
+                find_stars(&game, &mut transform_rules(rule.clone())),
+                find_stars(&game, &mut transform_rules(normalized.clone())),
+                "{} seed {}",
+                query,
+                seed
+            );
+        }
+    }
+    assert_eq!(
+        format_query(
+            &normalize_rules(parse_query("star where (birth and birth) or none").unwrap()).unwrap()
+        ),
+        "star where birth"
+    );
+
+    for query in [
+        "star where star_type = BlackHole and planets >= 3",
+        "star where star_type in (NeutronStar, BlackHole) and (gas >= 1 or satellites >= 1)",
+        "star where spectr = X and star_type = GiantStar",
+        "star where luminosity > 3 and dist(birth) < 10 and luminosity < 2",
+        "galaxy where count(birth) >= 1 and count(spectr in (O, B) and spectr = M) >= 1",
+    ] {
+        let rule = parse_query(query).unwrap();
+        assert!(normalize_rules(rule.clone()).is_err(), "{}", query);
+        for seed in 0..8 {
+            assert!(find_stars(&game(seed), &mut transform_rules(rule.clone())).is_empty());
+        }
+    }
+}
+
+#[test]
+fn set_tolerance_and_open_bound_conditions_match_their_expansions() {
+    let same = |a: &str, b: &str| {
+        let (a, b) = (parse_query(a).unwrap(), parse_query(b).unwrap());
+        for seed in 0..8 {
+            let game = game(seed);
+            assert_eq!(
+                find_stars(&game, &mut transform_rules(a.clone())),
+                find_stars(&game, &mut transform_rules(b.clone()))
+            );
+        }
+    };
+    same(
+        "star where planets in (1, 3)",
+        "star where planets = 1 or planets = 3",
+    );
+    same(
+        "star where satellites not in (0, 2)",
+        "star where satellites != 0 and satellites != 2",
+    );
+    same(
+        "star where luminosity ~= 1 +- 0.25",
+        "star where luminosity in [0.75, 1.25]",
+    );
+    same(
+        "star where dist(birth) in ]5, 20[",
+        "star where dist(birth) > 5 and dist(birth) < 20",
+    );
+    same(
+        "star where planets in [2, 4[",
+        "star where planets >= 2 and planets < 4",
+    );
+
+    let rule: Rules = serde_json::from_str(
+        r#"{"type":"Luminosity","condition":{"type":"Interval","value":{"min":1,"max":2,"maxOpen":true}}}"#,
+    )
+    .unwrap();
+    assert_eq!(format_query(&rule), "star where luminosity in [1, 2[");
+    for query in [
+        "star where planets in (1, 3) and gas not in (0)",
+        "star where luminosity ~= 1.5 +- 0.1 and dist(birth) in ]0, 10]",
+    ] {
+        assert_eq!(format_query(&parse_query(query).unwrap()), query);
+    }
+    assert!(
+        normalize_rules(parse_query("star where planets in (1, 2) and planets > 2").unwrap())
+            .is_err()
+    );
+    assert!(normalize_rules(
+        parse_query("star where luminosity in (1, 2) and luminosity > 2").unwrap()
+    )
+    .is_err());
+    assert!(normalize_rules(
+        parse_query("star where luminosity ~= 1 +- 0.1 and luminosity > 1.2").unwrap()
+    )
+    .is_err());
+}
+
+#[test]
+fn batched_generation_matches_single_galaxies() {
+    let games: Vec<GameDesc> = (0..21)
+        .map(|seed| GameDesc {
+            star_count: [32, 64, 48][seed as usize % 3],
+            ..game(seed * 7919 - 50000)
+        })
+        .collect();
+    let to_json = |galaxies: Vec<dsp_seed_finder::Galaxy>| -> Vec<String> {
+        galaxies
+            .iter()
+            .map(|galaxy| {
+                for sp in &galaxy.stars {
+                    sp.load_planets();
+                }
+                serde_json::to_string(galaxy).unwrap()
+            })
+            .collect()
+    };
+    let single = to_json(games.iter().map(generate_galaxy).collect());
+    assert_eq!(to_json(generate_galaxies::<4>(&games)), single);
+    assert_eq!(to_json(generate_galaxies::<8>(&games)), single);
+    assert_eq!(to_json(generate_galaxies::<16>(&games)), single);
+}
+
+#[test]
+fn stars_drawn_out_of_order_match_the_full_galaxy() {
+    for seed in [0, 42, 123456] {
+        let game = game(seed);
+        let mut galaxy = generate_galaxy(&game);
+        // Only the birth star and the last one are drawn before planets.
+        let last = galaxy.stars.len() - 1;
+        galaxy.stars[last].star.get_luminosity();
+        galaxy.stars[0].star.get_dyson_radius();
+        for sp in &galaxy.stars {
+            sp.load_planets();
+        }
+        let names: Vec<String> = (0..galaxy.stars.len())
+            .map(|index| galaxy.get_star_name(index))
+            .collect();
+        for (sp, name) in galaxy.stars.iter_mut().zip(names) {
+            sp.name = name;
+        }
+        assert_eq!(serde_json::to_string(&galaxy).unwrap(), galaxy_json(&game));
+
+        let birth = parse_query("star where birth and luminosity > 0").unwrap();
+        assert_eq!(find_stars(&game, &mut transform_rules(birth)), vec![0]);
+    }
+}
+
+#[test]
+fn parallel_search_reports_contiguous_progress() {
+    let rule = parse_query("star where luminosity > 1.5 and dist(birth) < 30").unwrap();
+    let seeds = SeedSelection::range(-200, 403);
+    let events = Mutex::new(vec![]);
+    search(
+        &game(0),
+        &rule,
+        &seeds,
+        Workers::new(8),
+        0,
+        false,
+        &AtomicBool::new(false),
+        |event| events.lock().unwrap().push(event),
+    );
+
+    let mut results = vec![];
+    let mut progress_end = 0;
+    for event in events.into_inner().unwrap() {
+        match event {
+            SearchEvent::Result { seed, indexes } => results.push((seed, indexes)),
+            SearchEvent::Progress { start, end } => {
+                assert_eq!(start, progress_end);
+                assert!(end > start);
+                progress_end = end;
+            }
+            SearchEvent::Done { start, end } => {
+                assert_eq!((start, end), (progress_end, seeds.len()));
+            }
+        }
+    }
+    results.sort();
+    let expected: Vec<_> = (-200..403)
+        .map(|seed| {
+            (
+                seed,
+                find_stars(&game(seed), &mut transform_rules(rule.clone())),
+            )
+        })
+        .filter(|(_, indexes)| !indexes.is_empty())
+        .collect();
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn layout_prefilter_keeps_every_match() {
+    let any_seed = game(0);
+    let mut batched = Workspace::new(&any_seed);
+    let mut single = Workspace::new(&any_seed);
+    for query in [
+        "star where dist(birth) > 40 and star_type = NeutronStar",
+        "star where dist(x) in [0.1, 2.05] or birth and dist(birth) > 0",
+        "star where luminosity > 1 and dist(birth) > 38",
+        "galaxy where count(spectr = O and dist(birth) in [0.1, 3]) >= 1",
+        "galaxy where count(dist(x) in [0.1, 2.5]) >= 5 and count(planets >= 5) >= 1",
+    ] {
+        let rule = parse_query(query).unwrap();
+        let transformed = transform_rules(rule.clone());
+        let seeds: Vec<i32> = (0..120).collect();
+        let mut rejected = 0;
+        let mut empty = 0;
+        for chunk in seeds.chunks(8) {
+            batched.generate_batch_matching::<8>(chunk, &*transformed);
+            for (lane, &seed) in chunk.iter().enumerate() {
+                let game = game(seed);
+                let expected = find_stars(&game, &mut transform_rules(rule.clone()));
+                if expected.is_empty() {
+                    empty += 1;
+                }
+                if batched.is_rejected(lane) {
+                    rejected += 1;
+                } else {
+                    let galaxy = generate_galaxy(&game);
+                    let luminosity = |galaxy: &dsp_seed_finder::Galaxy| -> Vec<f32> {
+                        galaxy
+                            .stars
+                            .iter()
+                            .map(|sp| sp.star.get_luminosity())
+                            .collect()
+                    };
+                    assert_eq!(luminosity(batched.galaxy(lane)), luminosity(&galaxy));
+                }
+                assert_eq!(
+                    batched.find_stars(lane, &*transformed),
+                    expected,
+                    "{} seed {}",
+                    query,
+                    seed
+                );
+
+                single.generate_matching(seed, &*transformed);
+                assert_eq!(single.is_rejected(0), batched.is_rejected(lane));
+                assert_eq!(
+                    single.find_stars(0, &*transformed),
+                    expected,
+                    "{} seed {}",
+                    query,
+                    seed
+                );
+            }
+        }
+        // Only queries mixing in more than star positions and types leave
+        // seeds to generate in vain.
+        assert!(rejected > 0 && rejected <= empty, "{}", query);
+        if transformed.is_layout_decidable() {
+            assert_eq!(rejected, empty, "{}", query);
+        }
+    }
+}
+
+#[test]
+fn ordered_search_reports_results_in_seed_order() {
+    let rule = parse_query("star where luminosity > 1.5 and dist(birth) < 30").unwrap();
+    let seeds = SeedSelection::range(-200, 403);
+    let expected: Vec<_> = (-200..403)
+        .map(|seed| {
+            (
+                seed,
+                find_stars(&game(seed), &mut transform_rules(rule.clone())),
+            )
+        })
+        .filter(|(_, indexes)| !indexes.is_empty())
+        .collect();
+
+    let events = Mutex::new(vec![]);
+    search(
+        &game(0),
+        &rule,
+        &seeds,
+        Workers::new(8),
+        0,
+        true,
+        &AtomicBool::new(false),
+        |event| events.lock().unwrap().push(event),
+    );
+    let mut results = vec![];
+    for event in events.into_inner().unwrap() {
+        match event {
+            SearchEvent::Result { seed, indexes } => results.push((seed, indexes)),
+            // Results come before the progress covering them.
+            SearchEvent::Progress { end, .. } | SearchEvent::Done { end, .. } => {
+                let covered = expected
+                    .iter()
+                    .take_while(|(seed, _)| *seed < -200 + end as i32);
+                assert_eq!(results.len(), covered.count());
+            }
+        }
+    }
+    assert_eq!(results, expected);
+
+    // Seed lists keep their own order.
+    let list = SeedSelection {
+        source: SeedSource::List {
+            seeds: (-200..403).rev().collect(),
+        },
+        shard: Shard::default(),
+    };
+    let results = Mutex::new(vec![]);
+    search(
+        &game(0),
+        &rule,
+        &list,
+        Workers::new(8),
+        0,
+        true,
+        &AtomicBool::new(false),
+        |event| {
+            if let SearchEvent::Result { seed, indexes } = event {
+                results.lock().unwrap().push((seed, indexes));
+            }
+        },
+    );
+    let mut reversed = expected;
+    reversed.reverse();
+    assert_eq!(results.into_inner().unwrap(), reversed);
+}
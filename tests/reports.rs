@@ -0,0 +1,488 @@
+use dsp_seed_finder::{
+    compare_galaxies, create_galaxy, find_stars, generate_galaxy, parse_query, render_svg, sweep,
+    transform_rules, Alignment, CompareOptions, Distribution, GameDesc, HitRate, Locale, Metric,
+    SpectrType, StarType, Statistics, SvgOptions, SvgView, VeinType,
+};
+
+fn game(seed: i32) -> GameDesc {
+    GameDesc {
+        seed,
+        star_count: 64,
+        resource_multiplier: 1.0,
+    }
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-4,
+        "{} != {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn metrics_parse_from_their_short_form() {
+    let parse = |s: &str| s.parse::<Metric>();
+    assert_eq!(parse("max-luminosity"), Ok(Metric::MaxLuminosity));
+    assert_eq!(
+        parse("max-vein:Grat"),
+        Ok(Metric::MaxAverageVein {
+            vein: VeinType::Grat
+        })
+    );
+    assert_eq!(
+        parse("nearest:BlackHole"),
+        Ok(Metric::NearestStarType {
+            star_type: StarType::BlackHole
+        })
+    );
+    assert_eq!(
+        parse("spectr-count:O:20"),
+        Ok(Metric::SpectrCount {
+            spectr: SpectrType::O,
+            within: 20.0
+        })
+    );
+    assert_eq!(
+        parse("spectr-count:B"),
+        Ok(Metric::SpectrCount {
+            spectr: SpectrType::B,
+            within: f64::INFINITY
+        })
+    );
+    assert_eq!(parse("brightest"), Err("Unknown metric: brightest".into()));
+    assert_eq!(
+        parse("vein-stars"),
+        Err("Metric vein-stars needs an argument".into())
+    );
+    assert_eq!(parse("max-vein:Gold"), Err("Unknown value: Gold".into()));
+    assert_eq!(
+        parse("spectr-count:O:far"),
+        Err("Invalid distance: far".into())
+    );
+}
+
+#[test]
+fn distributions_summarize_quantiles_and_histogram() {
+    let mut distribution = Distribution::default();
+    for value in (1..=101).rev() {
+        distribution.add(Some(value as f64));
+    }
+    distribution.add(None);
+    let summary = distribution.summarize(4);
+    assert_eq!((summary.count, summary.missing), (101, 1));
+    assert_eq!((summary.min, summary.max, summary.mean), (1.0, 101.0, 51.0));
+    let quantile = |q: f64| summary.quantiles.iter().find(|(p, _)| *p == q).unwrap().1;
+    assert_close(quantile(0.01), 2.0);
+    assert_close(quantile(0.5), 51.0);
+    assert_close(quantile(0.99), 100.0);
+    // Bins are 25 wide, and the maximum goes into the last one.
+    let counts: Vec<usize> = summary.histogram.iter().map(|b| b.count).collect();
+    assert_eq!(counts, [25, 25, 25, 26]);
+    assert_eq!(summary.histogram[0].start, 1.0);
+    assert_eq!(summary.histogram[3].end, 101.0);
+}
+
+#[test]
+fn distributions_of_equal_or_no_values() {
+    let mut equal = Distribution::default();
+    for _ in 0..3 {
+        equal.add(Some(2.5));
+    }
+    let summary = equal.summarize(0);
+    assert_eq!(summary.histogram.len(), 1);
+    assert_eq!(summary.histogram[0].count, 3);
+    assert!(summary.quantiles.iter().all(|(_, v)| *v == 2.5));
+
+    let mut missing = Distribution::default();
+    missing.add(None);
+    missing.merge(Distribution::default());
+    let summary = missing.summarize(10);
+    assert_eq!((summary.count, summary.missing), (0, 1));
+    assert!(summary.mean.is_nan());
+    assert!(summary.quantiles.is_empty() && summary.histogram.is_empty());
+}
+
+#[test]
+fn large_distributions_keep_quantiles_within_the_sketch_accuracy() {
+    let values = |range: std::ops::Range<i32>| range.map(|v| (v as f64 - 5000.0) / 10.0);
+    let mut whole = Distribution::default();
+    let (mut low, mut high) = (Distribution::default(), Distribution::default());
+    for value in values(0..20001) {
+        whole.add(Some(value));
+    }
+    for value in values(0..10000) {
+        low.add(Some(value));
+    }
+    for value in values(10000..20001) {
+        high.add(Some(value));
+    }
+    low.merge(high);
+    let summary = whole.summarize(4);
+    assert_eq!(
+        serde_json::to_value(&summary).unwrap(),
+        serde_json::to_value(low.summarize(4)).unwrap()
+    );
+
+    assert_eq!(
+        (summary.count, summary.min, summary.max),
+        (20001, -500.0, 1500.0)
+    );
+    assert_close(summary.mean, 500.0);
+    for (q, value) in summary.quantiles {
+        let expected = -500.0 + 2000.0 * q;
+        assert!(
+            (value - expected).abs() <= expected.abs() * 0.005 + 0.1,
+            "quantile {}: {} != {}",
+            q,
+            value,
+            expected
+        );
+    }
+    let counts: Vec<usize> = summary.histogram.iter().map(|b| b.count).collect();
+    assert_eq!(counts.iter().sum::<usize>(), 20001);
+    for count in counts {
+        assert!(count.abs_diff(5000) <= 50, "{}", count);
+    }
+}
+
+#[test]
+fn hit_rates_have_wilson_intervals() {
+    let estimate = HitRate::default().estimate(0.95);
+    assert_eq!(
+        (estimate.rate, estimate.low, estimate.high),
+        (0.0, 0.0, 1.0)
+    );
+
+    let half = HitRate {
+        hits: 50,
+        trials: 100,
+    }
+    .estimate(0.95);
+    assert_eq!(half.rate, 0.5);
+    assert_close(half.low, 0.4038);
+    assert_close(half.high, 0.5962);
+
+    // No hits still leave room for rare ones.
+    let none = HitRate {
+        hits: 0,
+        trials: 1000,
+    }
+    .estimate(0.95);
+    assert_eq!(none.low, 0.0);
+    assert_close(none.high, 0.0038);
+
+    let mut all = HitRate::default();
+    for _ in 0..10 {
+        all.add(true);
+    }
+    let all = all.estimate(0.99);
+    assert_eq!((all.hits, all.rate, all.high), (10, 1.0, 1.0));
+    assert!(all.low < 1.0);
+}
+
+#[test]
+fn sweep_measures_every_seed_once_whatever_the_threads() {
+    let metrics = vec![
+        Metric::MaxLuminosity,
+        Metric::NearestStarType {
+            star_type: StarType::BlackHole,
+        },
+    ];
+    let rule = parse_query("star where luminosity > 2").unwrap();
+    let seeds = 0..30;
+
+    let mut expected = Statistics::new(metrics.clone());
+    let mut transformed = transform_rules(rule.clone());
+    for seed in seeds.clone() {
+        expected.add_galaxy(&generate_galaxy(&game(seed)));
+        expected
+            .hit_rate
+            .add(!find_stars(&game(seed), &mut transformed).is_empty());
+    }
+    let expected = expected.report(seeds.len(), 5, Some(0.95), Locale::En);
+    assert_eq!(expected.hit_rate.unwrap().trials, 30);
+    assert_eq!(expected.metrics[0].distribution.count, 30);
+    let text = expected.to_text(Locale::En);
+    assert!(text.starts_with("Galaxies: 30\n"), "{}", text);
+    let hits = expected.hit_rate.unwrap().hits;
+    assert!(
+        text.contains(&format!("Rule hit rate: {} / 30", hits)),
+        "{}",
+        text
+    );
+    let text = expected.to_text(Locale::Zh);
+    assert!(text.starts_with("星系：30\n"), "{}", text);
+
+    for threads in [1, 3] {
+        let stats = sweep(&game(0), seeds.clone(), &metrics, Some(&rule), threads);
+        let report = stats.report(seeds.len(), 5, Some(0.95), Locale::En);
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            serde_json::to_value(&expected).unwrap(),
+            "{} threads",
+            threads
+        );
+    }
+}
+
+#[test]
+fn sweep_hit_rates_match_find_stars_when_metrics_load_planets() {
+    let metrics = vec![Metric::VeinStarCount {
+        vein: VeinType::Fractal,
+    }];
+    let rule = parse_query("star where theme in (8, 14) and dist(birth) < 20").unwrap();
+    let seeds = 0..30;
+    let mut transformed = transform_rules(rule.clone());
+    let hits = seeds
+        .clone()
+        .filter(|&seed| !find_stars(&game(seed), &mut transformed).is_empty())
+        .count();
+
+    let stats = sweep(&game(0), seeds, &metrics, Some(&rule), 2);
+    assert_eq!(stats.hit_rate.trials, 30);
+    assert_eq!(stats.hit_rate.hits, hits);
+    assert!(0 < hits && hits < 30, "{}", hits);
+}
+
+/// The value of `attribute` in each tag of `svg` that has it.
+fn attributes(svg: &str, attribute: &str) -> Vec<f64> {
+    let prefix = format!(" {}=\"", attribute);
+    svg.match_indices(&prefix)
+        .map(|(at, _)| {
+            let value = &svg[at + prefix.len()..];
+            value[..value.find('"').unwrap()].parse().unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn svg_maps_draw_every_star_inside_each_projection() {
+    let game = game(7);
+    let galaxy = create_galaxy(&game);
+    let stars = galaxy.stars.len();
+
+    let both = render_svg(&galaxy, &SvgOptions::default());
+    assert!(
+        both.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"960\" height=\"480\"")
+    );
+    assert!(both.ends_with("</svg>\n"));
+    assert!(both.contains("Seed 7 · 64 stars"));
+    assert_eq!(both.matches("<g class=\"top\">").count(), 1);
+    assert_eq!(both.matches("<g class=\"side\">").count(), 1);
+    assert_eq!(both.matches("<title>").count(), 2 * stars);
+    // The birth star is ringed in both projections.
+    assert_eq!(both.matches("stroke=\"#4cff8a\"").count(), 2);
+
+    let top = render_svg(
+        &galaxy,
+        &SvgOptions {
+            view: SvgView::Top,
+            size: 300.0,
+            ..SvgOptions::default()
+        },
+    );
+    assert!(top.contains("width=\"300\" height=\"300\""));
+    assert!(!top.contains("class=\"side\""));
+    assert_eq!(top.matches("<title>").count(), stars);
+    let xs = attributes(&top, "cx");
+    let ys = attributes(&top, "cy");
+    assert_eq!(xs.len(), stars + 1);
+    assert!(xs.iter().chain(&ys).all(|v| (0.0..=300.0).contains(v)));
+}
+
+#[test]
+fn svg_maps_scale_to_fit_rings_and_mark_highlights() {
+    let game = game(7);
+    let galaxy = create_galaxy(&game);
+    let svg = render_svg(
+        &galaxy,
+        &SvgOptions {
+            view: SvgView::Side,
+            size: 400.0,
+            rings: vec![10.0, 500.0],
+            highlights: vec![3, 12],
+            ..SvgOptions::default()
+        },
+    );
+    assert!(svg.contains(">10 ly</text>") && svg.contains(">500 ly</text>"));
+    // The largest ring sets the scale, staying within the margins.
+    let radii = attributes(&svg, "r");
+    let ring = radii.iter().copied().fold(0.0, f64::max);
+    assert!(ring > 150.0 && ring <= 200.0 - 24.0, "{}", ring);
+    assert_eq!(svg.matches("stroke=\"#ff4c6a\"").count(), 2);
+}
+
+#[test]
+fn svg_labels_escape_star_names() {
+    let game = game(7);
+    let mut galaxy = create_galaxy(&game);
+    let name = galaxy.stars[5].name.clone();
+    galaxy.stars[5].name = "Tom & <Jerry> \"1\"".to_owned();
+    let options = SvgOptions {
+        view: SvgView::Top,
+        ..SvgOptions::default()
+    };
+    let unlabeled = render_svg(&galaxy, &options);
+    assert!(!unlabeled.contains(&format!(">{}</text>", galaxy.stars[0].name)));
+
+    let labeled = render_svg(
+        &galaxy,
+        &SvgOptions {
+            labels: true,
+            ..options
+        },
+    );
+    assert!(labeled.contains(&format!(">{}</text>", galaxy.stars[0].name)));
+    assert!(labeled.contains(">Tom &amp; &lt;Jerry&gt; &quot;1&quot;</text>"));
+    assert!(labeled.contains("<title>Tom &amp; &lt;Jerry&gt; &quot;1&quot; ("));
+    assert!(!labeled.contains("<Jerry>") && !labeled.contains(&name));
+}
+
+#[test]
+fn comparisons_align_stars_by_distance_and_report_missing_ones() {
+    let small = GameDesc {
+        star_count: 32,
+        ..game(11)
+    };
+    let large = game(12);
+    let (small, large) = (create_galaxy(&small), create_galaxy(&large));
+    let comparison = compare_galaxies(&[&small, &large], &CompareOptions::default());
+    assert_eq!(comparison.rows.len(), 64);
+    assert_eq!(comparison.rows[0].key, "Birth");
+    assert_eq!(comparison.rows[1].key, "#1");
+
+    for (n, galaxy) in comparison.galaxies.iter().enumerate() {
+        let distances: Vec<f64> = comparison
+            .rows
+            .iter()
+            .filter_map(|row| row.stars[n])
+            .map(|i| galaxy.stars[i].distance)
+            .collect();
+        assert_eq!(distances.len(), galaxy.star_count);
+        assert_eq!(distances[0], 0.0);
+        assert!(distances.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+    for row in &comparison.rows[..32] {
+        assert!(row.stars.iter().all(Option::is_some), "{}", row.key);
+        assert!(!row.differences.contains(&"missing"), "{}", row.key);
+    }
+    for row in &comparison.rows[32..] {
+        assert_eq!(row.stars[0], None);
+        assert_eq!(row.differences, ["missing"]);
+    }
+
+    let text = comparison.to_text(Locale::En);
+    assert!(text.starts_with("Seed 11: 32 stars, "), "{}", text);
+    assert!(text.contains("\n#63 (differs: missing)\n  11: -\n  12: "));
+}
+
+#[test]
+fn comparisons_name_the_metrics_that_differ() {
+    let (first, second) = (game(11), game(12));
+    let (first, second) = (create_galaxy(&first), create_galaxy(&second));
+    let comparison = compare_galaxies(&[&first, &second], &CompareOptions::default());
+    let [a, b] = &comparison.galaxies[..] else {
+        panic!("two galaxies")
+    };
+    for row in &comparison.rows {
+        let (s1, s2) = (
+            &a.stars[row.stars[0].unwrap()],
+            &b.stars[row.stars[1].unwrap()],
+        );
+        let differs = |name: &str| row.differences.contains(&name);
+        assert_eq!(
+            differs("starType"),
+            s1.star_type != s2.star_type,
+            "{}",
+            row.key
+        );
+        assert_eq!(differs("spectr"), s1.spectr != s2.spectr, "{}", row.key);
+        assert_eq!(
+            differs("planetCount"),
+            s1.planet_count != s2.planet_count,
+            "{}",
+            row.key
+        );
+        assert_eq!(
+            differs("luminosity"),
+            (s1.luminosity - s2.luminosity).abs() > 0.005,
+            "{}",
+            row.key
+        );
+        assert_eq!(
+            differs("rareVeins"),
+            s1.rare_veins != s2.rare_veins,
+            "{}",
+            row.key
+        );
+    }
+    // Different seeds differ somewhere, the same seed nowhere.
+    for name in ["starType", "spectr", "planetCount", "luminosity"] {
+        assert!(
+            comparison
+                .rows
+                .iter()
+                .any(|row| row.differences.contains(&name)),
+            "{}",
+            name
+        );
+    }
+
+    let same = compare_galaxies(&[&first, &first], &CompareOptions::default());
+    assert!(same.rows.iter().all(|row| row.differences.is_empty()));
+}
+
+#[test]
+fn comparisons_by_star_type_only_pair_stars_of_a_group() {
+    let (first, second) = (game(11), game(12));
+    let (first, second) = (create_galaxy(&first), create_galaxy(&second));
+    let options = CompareOptions {
+        alignment: Alignment::StarType,
+        ..CompareOptions::default()
+    };
+    let comparison = compare_galaxies(&[&first, &second], &options);
+    let mut keys: Vec<&str> = comparison
+        .rows
+        .iter()
+        .map(|row| row.key.rsplit_once(" #").unwrap().0)
+        .collect();
+    assert!(keys.windows(2).all(|pair| pair[0] <= pair[1]));
+    keys.dedup();
+    for row in &comparison.rows {
+        assert!(!row.differences.contains(&"starType"), "{}", row.key);
+        assert_eq!(
+            row.differences.contains(&"missing"),
+            row.stars.contains(&None),
+            "{}",
+            row.key
+        );
+    }
+    // Each group has as many rows as the galaxy with most of its stars.
+    let in_group = |n: usize, key: &str| {
+        comparison
+            .rows
+            .iter()
+            .filter(|row| row.key.rsplit_once(" #").unwrap().0 == key)
+            .filter(|row| row.stars[n].is_some())
+            .count()
+    };
+    for key in keys {
+        let rows = comparison
+            .rows
+            .iter()
+            .filter(|row| row.key.rsplit_once(" #").unwrap().0 == key)
+            .count();
+        assert_eq!(rows, in_group(0, key).max(in_group(1, key)), "{}", key);
+    }
+
+    let truncated = compare_galaxies(
+        &[&first, &second],
+        &CompareOptions {
+            max_rows: 5,
+            ..options
+        },
+    );
+    assert_eq!(truncated.rows.len(), 5);
+}
@@ -0,0 +1,166 @@
+use dsp_seed_finder::{
+    create_galaxy, find_stars, parse_query, transform_rules, GameDesc, Rules, SpectrType, StarType,
+};
+use serde_json::json;
+
+fn game(seed: i32) -> GameDesc {
+    GameDesc {
+        seed,
+        star_count: 64,
+        resource_multiplier: 1.0,
+    }
+}
+
+/// Checks that the rule `kind` finds exactly the stars whose `values` compare
+/// so, against a value of one of the stars, where including it matters.
+fn assert_compares_at_boundaries(game: &GameDesc, kind: &str, values: &[f32]) {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    sorted.dedup();
+    assert!(sorted.len() >= 3, "{} has too few values", kind);
+    let boundary = sorted[sorted.len() / 2];
+    let expected = |matches: &dyn Fn(f32) -> bool| -> Vec<usize> {
+        (0..values.len()).filter(|&i| matches(values[i])).collect()
+    };
+    let cases: [(serde_json::Value, Vec<usize>); 6] = [
+        (
+            json!({"type": "Gte", "value": boundary}),
+            expected(&|v| v >= boundary),
+        ),
+        (
+            json!({"type": "Gt", "value": boundary}),
+            expected(&|v| v > boundary),
+        ),
+        (
+            json!({"type": "Lte", "value": boundary}),
+            expected(&|v| v <= boundary),
+        ),
+        (
+            json!({"type": "Lt", "value": boundary}),
+            expected(&|v| v < boundary),
+        ),
+        (
+            json!({"type": "Eq", "value": boundary}),
+            expected(&|v| v == boundary),
+        ),
+        (
+            json!({"type": "Between", "value": [boundary, sorted[sorted.len() - 1]]}),
+            expected(&|v| v >= boundary),
+        ),
+    ];
+    for (condition, expected) in cases {
+        let rule: Rules =
+            serde_json::from_value(json!({"type": kind, "condition": condition})).unwrap();
+        let mut found = find_stars(game, &mut transform_rules(rule));
+        found.sort_unstable();
+        assert_eq!(found, expected, "{} {}", kind, condition);
+    }
+}
+
+#[test]
+fn habitable_radius_rule_compares_at_boundaries() {
+    let game = game(3);
+    let galaxy = create_galaxy(&game);
+    let values: Vec<f32> = galaxy
+        .stars
+        .iter()
+        .map(|sp| sp.star.get_habitable_radius())
+        .collect();
+    assert_compares_at_boundaries(&game, "HabitableRadius", &values);
+}
+
+#[test]
+fn light_balance_radius_rule_compares_at_boundaries() {
+    let game = game(3);
+    let galaxy = create_galaxy(&game);
+    let values: Vec<f32> = galaxy
+        .stars
+        .iter()
+        .map(|sp| sp.star.get_light_balance_radius())
+        .collect();
+    assert_compares_at_boundaries(&game, "LightBalanceRadius", &values);
+}
+
+#[test]
+fn orbit_scaler_rule_compares_at_boundaries() {
+    let game = game(3);
+    let galaxy = create_galaxy(&game);
+    let values: Vec<f32> = galaxy
+        .stars
+        .iter()
+        .map(|sp| sp.star.get_orbit_scaler())
+        .collect();
+    assert_compares_at_boundaries(&game, "OrbitScaler", &values);
+}
+
+#[test]
+fn habitable_zone_count_rule_compares_at_boundaries() {
+    for seed in 0..4 {
+        let game = game(seed);
+        let galaxy = create_galaxy(&game);
+        let values: Vec<f32> = galaxy
+            .stars
+            .iter()
+            .map(|sp| {
+                sp.get_planets()
+                    .filter(|planet| planet.is_in_habitable_zone())
+                    .count() as f32
+            })
+            .collect();
+        assert_compares_at_boundaries(&game, "HabitableZoneCount", &values);
+    }
+}
+
+#[test]
+fn distance_rules_count_the_stars_around_each_star_across_seeds() {
+    let mut spectr = transform_rules(
+        parse_query("star where dist(O, count >= 2) < 15 or dist(B, count >= 2) < 15").unwrap(),
+    );
+    let mut x = transform_rules(parse_query("star where dist(x) < 20").unwrap());
+    // The same rules go through several seeds, as in a search.
+    let mut matched = (0, 0);
+    for seed in 0..20 {
+        let game = game(seed);
+        let galaxy = create_galaxy(&game);
+        let stars = &galaxy.stars;
+        let distance = |a: usize, b: usize| {
+            stars[a]
+                .star
+                .position
+                .distance_from(&stars[b].star.position)
+        };
+        let count_near = |star: usize, of: &dyn Fn(usize) -> bool, within: f64| {
+            (0..stars.len())
+                .filter(|&other| other != star && of(other) && distance(star, other) < within)
+                .count()
+        };
+        let is_spectr =
+            |spectr: SpectrType| move |other: usize| stars[other].star.get_spectr() == spectr;
+        let is_x = |other: usize| {
+            matches!(
+                stars[other].star.star_type,
+                StarType::BlackHole | StarType::NeutronStar
+            )
+        };
+        let expected_spectr: Vec<usize> = (0..stars.len())
+            .filter(|&star| {
+                count_near(star, &is_spectr(SpectrType::O), 15.0) >= 2
+                    || count_near(star, &is_spectr(SpectrType::B), 15.0) >= 2
+            })
+            .collect();
+        // A star of its own is at distance 0 from itself.
+        let expected_x: Vec<usize> = (0..stars.len())
+            .filter(|&star| is_x(star) || count_near(star, &is_x, 20.0) > 0)
+            .collect();
+        matched.0 += expected_spectr.len();
+        matched.1 += expected_x.len();
+
+        let mut found = find_stars(&game, &mut spectr);
+        found.sort_unstable();
+        assert_eq!(found, expected_spectr, "seed {}", seed);
+        let mut found = find_stars(&game, &mut x);
+        found.sort_unstable();
+        assert_eq!(found, expected_x, "seed {}", seed);
+    }
+    assert!(matched.0 > 0 && matched.1 > 0, "{:?}", matched);
+}
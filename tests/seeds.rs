@@ -0,0 +1,186 @@
+use dsp_seed_finder::{parse_seed_list, SeedSelection, SeedSource, Shard};
+
+fn list(seeds: Vec<i32>, shard: Shard) -> SeedSelection {
+    SeedSelection {
+        source: SeedSource::List { seeds },
+        shard,
+    }
+}
+
+fn range(start: i32, end: i32, step: i32, shard: Shard) -> SeedSelection {
+    SeedSelection {
+        source: SeedSource::Range { start, end, step },
+        shard,
+    }
+}
+
+fn shard(index: usize, count: usize) -> Shard {
+    Shard { index, count }
+}
+
+#[test]
+fn seed_lists_skip_headers_comments_and_extra_columns() {
+    let text = "seed,score\r\n# best so far\r\n\r\n\"42\",0.9\r\n-7;0.5\r\n  13\tx\r\n";
+    assert_eq!(parse_seed_list(text), Ok(vec![42, -7, 13]));
+    // Only the first line may be a header.
+    assert_eq!(
+        parse_seed_list("seed\n1\nseed\n"),
+        Err("line 3: invalid seed seed".to_owned())
+    );
+    assert_eq!(
+        parse_seed_list("1\n2.5\n"),
+        Err("line 2: invalid seed 2.5".to_owned())
+    );
+    assert_eq!(
+        parse_seed_list("1\n99999999999\n"),
+        Err("line 2: invalid seed 99999999999".to_owned())
+    );
+}
+
+#[test]
+fn empty_seed_lists_select_nothing() {
+    assert_eq!(parse_seed_list(""), Ok(vec![]));
+    assert_eq!(parse_seed_list("# nothing\n\n   \n"), Ok(vec![]));
+    assert_eq!(parse_seed_list("seed\n"), Ok(vec![]));
+
+    let empty = list(vec![], Shard::default()).resolve().unwrap();
+    assert!(empty.is_empty());
+    assert_eq!(empty.iter().count(), 0);
+    assert!(range(5, 5, 1, Shard::default()).is_empty());
+    assert!(range(5, 4, 1, Shard::default()).is_empty());
+    // More shards than seeds leave the last ones empty.
+    assert_eq!(
+        list(vec![1, 2], shard(1, 3)).iter().collect::<Vec<_>>(),
+        [2]
+    );
+    assert!(list(vec![1, 2], shard(2, 3)).is_empty());
+}
+
+#[test]
+fn shards_parse_from_one_based_k_of_n() {
+    assert_eq!("1/1".parse(), Ok(shard(0, 1)));
+    assert_eq!(" 3 / 4 ".parse(), Ok(shard(2, 4)));
+    for invalid in [
+        "0/3", "4/3", "1/0", "0/0", "3", "/", "a/2", "1/b", "-1/2", "1/2/3",
+    ] {
+        assert_eq!(
+            invalid.parse::<Shard>(),
+            Err(format!("Invalid shard {}, expected <k>/<n>", invalid)),
+        );
+    }
+}
+
+#[test]
+fn invalid_selections_are_refused() {
+    assert_eq!(
+        range(0, 10, 1, shard(2, 2)).validate(),
+        Err("Invalid shard 3 of 2".to_owned())
+    );
+    assert_eq!(
+        range(0, 10, 1, shard(0, 0)).validate(),
+        Err("Invalid shard 1 of 0".to_owned())
+    );
+    assert_eq!(
+        range(0, 10, 0, Shard::default()).validate(),
+        Err("Invalid step 0, it must be positive".to_owned())
+    );
+    assert_eq!(
+        range(0, 10, -2, Shard::default()).resolve(),
+        Err("Invalid step -2, it must be positive".to_owned())
+    );
+    let missing = SeedSelection {
+        source: SeedSource::File {
+            path: "no/such/seeds.txt".to_owned(),
+        },
+        shard: Shard::default(),
+    };
+    assert_eq!(
+        missing.validate(),
+        Err("Seed file no/such/seeds.txt is not loaded".to_owned())
+    );
+    assert!(missing
+        .resolve()
+        .unwrap_err()
+        .starts_with("Cannot read no/such/seeds.txt: "));
+}
+
+#[test]
+fn seed_files_resolve_to_lists() {
+    let path = std::env::temp_dir().join(format!("seeds-{}.csv", std::process::id()));
+    std::fs::write(&path, "seed\n10\n20\n30\n").unwrap();
+    let path = path.to_str().unwrap().to_owned();
+    let file = SeedSelection {
+        source: SeedSource::File { path: path.clone() },
+        shard: shard(1, 2),
+    };
+    let resolved = file.resolve().unwrap();
+    assert_eq!(resolved, list(vec![10, 20, 30], shard(1, 2)));
+    assert_eq!(resolved.iter().collect::<Vec<_>>(), [20]);
+
+    std::fs::write(&path, "10\nten\n").unwrap();
+    let file = SeedSelection {
+        source: SeedSource::File { path: path.clone() },
+        shard: Shard::default(),
+    };
+    assert_eq!(
+        file.resolve(),
+        Err(format!("{}: line 2: invalid seed ten", path))
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn shards_split_a_selection_without_overlap_or_gaps() {
+    let selections = [
+        range(-10, 23, 1, Shard::default()),
+        range(-10, 23, 4, Shard::default()),
+        list(vec![5, 3, 5, 9, 3, 1, 8], Shard::default()),
+    ];
+    for selection in selections {
+        let all: Vec<i32> = selection.iter().collect();
+        for count in 1..=9 {
+            let mut shards: Vec<Vec<i32>> = (0..count)
+                .map(|index| {
+                    let sharded = SeedSelection {
+                        shard: shard(index, count),
+                        ..selection.clone()
+                    };
+                    assert_eq!(sharded.validate(), Ok(()));
+                    let seeds: Vec<i32> = sharded.iter().collect();
+                    assert_eq!(seeds.len(), sharded.len());
+                    seeds
+                })
+                .collect();
+            // Shard k takes every n-th seed from the k-th on.
+            let mut merged = vec![];
+            for position in 0..all.len() {
+                merged.push(shards[position % count].remove(0));
+            }
+            assert!(shards.iter().all(Vec::is_empty), "{:?}", selection);
+            assert_eq!(merged, all, "{} shards of {:?}", count, selection);
+        }
+    }
+    // Lists keep repeated seeds, in order.
+    assert_eq!(
+        list(vec![5, 3, 5], Shard::default())
+            .iter()
+            .collect::<Vec<_>>(),
+        [5, 3, 5]
+    );
+}
+
+#[test]
+fn ranges_step_up_to_but_not_including_the_end() {
+    let stepped = range(-3, 10, 4, Shard::default());
+    assert_eq!(stepped.iter().collect::<Vec<_>>(), [-3, 1, 5, 9]);
+    assert!(!stepped.is_contiguous());
+    assert!(range(-3, 10, 1, Shard::default()).is_contiguous());
+    assert!(!range(-3, 10, 1, shard(0, 2)).is_contiguous());
+
+    // The whole seed space doesn't overflow.
+    let all = range(i32::MIN, i32::MAX, 1, Shard::default());
+    assert_eq!(all.len(), u32::MAX as usize);
+    assert_eq!(all.get(all.len() - 1), i32::MAX - 1);
+    let wide = range(i32::MIN, i32::MAX, i32::MAX, shard(1, 2));
+    assert_eq!(wide.iter().collect::<Vec<_>>(), [-1]);
+}
@@ -56,10 +56,19 @@ export enum RuleType {
     Luminosity = "Luminosity", // 20
     Spectr = "Spectr", // 21
     DysonRadius = "DysonRadius", // 22
+    StarName = "StarName", // 23
+    HabitableRadius = "HabitableRadius", // 24
+    LightBalanceRadius = "LightBalanceRadius", // 25
+    OrbitScaler = "OrbitScaler", // 26
     PlanetCount = "PlanetCount", // 30
     SatelliteCount = "SatelliteCount", // 31
     TidalLockCount = "TidalLockCount", // 33
     PlanetInDysonCount = "PlanetInDysonCount", // 34
+    HabitableZoneCount = "HabitableZoneCount", // 35
+    RotationPeriod = "RotationPeriod", // 36
+    RetrogradeCount = "RetrogradeCount", // 37
+    ResonanceCount = "ResonanceCount", // 38
+    OrbitalRatio = "OrbitalRatio", // 39
     ThemeId = "ThemeId", // 40
     GasCount = "GasCount", // 41 / 32
     OceanType = "OceanType", // 42
@@ -83,16 +92,15 @@ export enum ConditionType {
 }
 
 export enum GasType {
-    None = 0,
-    Fireice = 1011,
-    Hydrogen = 1120,
-    Deuterium = 1121,
+    Fireice = "Fireice",
+    Hydrogen = "Hydrogen",
+    Deuterium = "Deuterium",
 }
 
 export enum OceanType {
-    None = 0,
-    Ice = -2,
-    Lava = -1,
-    Water = 1000,
-    Sulfur = 1116,
+    None = "None",
+    Ice = "Ice",
+    Lava = "Lava",
+    Water = "Water",
+    Sulfur = "Sulfur",
 }
@@ -13,6 +13,7 @@ import Select from "../components/Select"
 import { IoTrash } from "solid-icons/io"
 import Button from "../components/Button"
 import NumberInput from "../components/NumberInput"
+import Input from "../components/Input"
 import { conditionTypeNames, planetTypes, veinNames } from "../util"
 import clsx from "clsx"
 
@@ -102,6 +103,67 @@ const EditLuminosity: Component<{
     )
 }
 
+const EditStarRadius: Component<{
+    value: Rule.HabitableRadius | Rule.LightBalanceRadius | Rule.OrbitScaler
+    onChange: (
+        value: Rule.HabitableRadius | Rule.LightBalanceRadius | Rule.OrbitScaler,
+    ) => void
+    unit: string
+    disabled?: boolean
+}> = (props) => {
+    const condition = () => props.value.condition
+    const setCondition = (condition: Condition) =>
+        props.onChange({ ...props.value, condition })
+    return (
+        <>
+            Is{" "}
+            <ConditionTypeSelector
+                value={condition()}
+                onChange={setCondition}
+                disabled={props.disabled}
+            />{" "}
+            <ConditionValueInput
+                class={styles.inputLuminosity}
+                value={condition()}
+                onChange={setCondition}
+                emptyValue={-1}
+                error={condition().value <= 0}
+                disabled={props.disabled}
+            />
+            {props.unit}
+        </>
+    )
+}
+
+const EditHabitableZoneCount: Component<{
+    value: Rule.HabitableZoneCount
+    onChange: (value: Rule.HabitableZoneCount) => void
+    disabled?: boolean
+}> = (props) => {
+    const condition = () => props.value.condition
+    const setCondition = (condition: Condition) =>
+        props.onChange({ ...props.value, condition })
+    return (
+        <>
+            Has{" "}
+            <ConditionTypeSelector
+                value={condition()}
+                onChange={setCondition}
+                disabled={props.disabled}
+            />{" "}
+            <ConditionValueInput
+                class={styles.inputCount}
+                value={condition()}
+                onChange={setCondition}
+                emptyValue={-1}
+                error={condition().value < 0}
+                disabled={props.disabled}
+            />{" "}
+            planet(s) within the habitable zone
+        </>
+    )
+}
+
 const EditDysonRadius: Component<{
     value: Rule.DysonRadius
     onChange: (value: Rule.DysonRadius) => void
@@ -584,6 +646,231 @@ const EditPlanetInDysonCount: Component<{
     )
 }
 
+const EditRotationPeriod: Component<{
+    value: Rule.RotationPeriod
+    onChange: (value: Rule.RotationPeriod) => void
+    disabled?: boolean
+}> = (props) => {
+    const count = () => props.value.count
+    const setCount = (count: Condition) =>
+        props.onChange({ ...props.value, count })
+    const condition = () => props.value.condition
+    const setCondition = (condition: Condition) =>
+        props.onChange({ ...props.value, condition })
+    return (
+        <>
+            Has{" "}
+            <ConditionTypeSelector
+                value={count()}
+                onChange={setCount}
+                disabled={props.disabled}
+            />{" "}
+            <ConditionValueInput
+                class={styles.inputCount}
+                value={count()}
+                onChange={setCount}
+                emptyValue={-1}
+                error={count().value < 0}
+                disabled={props.disabled}
+            />{" "}
+            planet(s) with a rotation period{" "}
+            <ConditionTypeSelector
+                value={condition()}
+                onChange={setCondition}
+                disabled={props.disabled}
+            />{" "}
+            <ConditionValueInput
+                class={styles.inputLuminosity}
+                value={condition()}
+                onChange={setCondition}
+                emptyValue={-1}
+                error={condition().value < 0}
+                disabled={props.disabled}
+            />
+            s,{" "}
+            <Select
+                class={styles.selectGasType}
+                value={props.value.includeGiant}
+                onChange={(includeGiant) =>
+                    props.onChange({ ...props.value, includeGiant })
+                }
+                options={[false, true]}
+                getLabel={(includeGiant) =>
+                    includeGiant ? "including" : "excluding"
+                }
+                disabled={props.disabled}
+            />{" "}
+            gas/ice giants.
+        </>
+    )
+}
+
+const EditRetrogradeCount: Component<{
+    value: Rule.RetrogradeCount
+    onChange: (value: Rule.RetrogradeCount) => void
+    disabled?: boolean
+}> = (props) => {
+    const condition = () => props.value.condition
+    const setCondition = (condition: Condition) =>
+        props.onChange({ ...props.value, condition })
+    return (
+        <>
+            Has{" "}
+            <ConditionTypeSelector
+                value={condition()}
+                onChange={setCondition}
+                disabled={props.disabled}
+            />{" "}
+            <ConditionValueInput
+                class={styles.inputCount}
+                value={condition()}
+                onChange={setCondition}
+                emptyValue={-1}
+                error={condition().value < 0}
+                disabled={props.disabled}
+            />{" "}
+            planet(s) with retrograde rotation
+        </>
+    )
+}
+
+const resonances: Rule.ResonanceCount["resonance"][] = [
+    "Any",
+    "TidalLock",
+    "OneToTwo",
+    "OneToFour",
+    "Eligible",
+]
+
+const resonanceNames: Record<Rule.ResonanceCount["resonance"], string> = {
+    Any: "in orbital resonance",
+    TidalLock: "tidally locked (1 : 1)",
+    OneToTwo: "in 1 : 2 resonance",
+    OneToFour: "in 1 : 4 resonance",
+    Eligible: "eligible for resonance",
+}
+
+const EditResonanceCount: Component<{
+    value: Rule.ResonanceCount
+    onChange: (value: Rule.ResonanceCount) => void
+    disabled?: boolean
+}> = (props) => {
+    const condition = () => props.value.condition
+    const setCondition = (condition: Condition) =>
+        props.onChange({ ...props.value, condition })
+    return (
+        <>
+            Has{" "}
+            <ConditionTypeSelector
+                value={condition()}
+                onChange={setCondition}
+                disabled={props.disabled}
+            />{" "}
+            <ConditionValueInput
+                class={styles.inputCount}
+                value={condition()}
+                onChange={setCondition}
+                emptyValue={-1}
+                error={condition().value < 0}
+                disabled={props.disabled}
+            />{" "}
+            planet(s){" "}
+            <Select
+                class={styles.selectResonance}
+                value={props.value.resonance}
+                onChange={(resonance) =>
+                    props.onChange({ ...props.value, resonance })
+                }
+                options={resonances}
+                getLabel={(resonance) => resonanceNames[resonance]}
+                disabled={props.disabled}
+            />
+        </>
+    )
+}
+
+const EditOrbitalRatio: Component<{
+    value: Rule.OrbitalRatio
+    onChange: (value: Rule.OrbitalRatio) => void
+    disabled?: boolean
+}> = (props) => {
+    return (
+        <>
+            Has two planets with orbital periods in a ratio of 1 :{" "}
+            <NumberInput
+                class={styles.inputLuminosity}
+                value={props.value.ratio}
+                onChange={(ratio) => props.onChange({ ...props.value, ratio })}
+                emptyValue={-1}
+                error={props.value.ratio < 1}
+                disabled={props.disabled}
+            />{" "}
+            ±{" "}
+            <NumberInput
+                class={styles.inputCount}
+                value={props.value.tolerance * 100}
+                onChange={(tolerance) =>
+                    props.onChange({
+                        ...props.value,
+                        tolerance: tolerance / 100,
+                    })
+                }
+                emptyValue={-100}
+                error={props.value.tolerance < 0}
+                disabled={props.disabled}
+            />
+            %
+        </>
+    )
+}
+
+const nameModes: Rule.StarName["mode"][] = ["exact", "prefix", "regex"]
+
+const nameModeNames: Record<Rule.StarName["mode"], string> = {
+    exact: "is",
+    prefix: "starts with",
+    regex: "matches regex",
+}
+
+const EditStarName: Component<{
+    value: Rule.StarName
+    onChange: (value: Rule.StarName) => void
+    disabled?: boolean
+}> = (props) => {
+    return (
+        <>
+            Name{" "}
+            <Select
+                class={styles.selectNameMode}
+                value={props.value.mode}
+                onChange={(mode) => props.onChange({ ...props.value, mode })}
+                options={nameModes}
+                getLabel={(mode) => nameModeNames[mode]}
+                disabled={props.disabled}
+            />{" "}
+            <Input
+                class={styles.inputStarName}
+                value={props.value.name}
+                onChange={(name) => props.onChange({ ...props.value, name })}
+                error={props.value.name.length === 0}
+                disabled={props.disabled}
+            />{" "}
+            <Select
+                class={styles.selectGasType}
+                value={props.value.ignoreCase}
+                onChange={(ignoreCase) =>
+                    props.onChange({ ...props.value, ignoreCase })
+                }
+                options={[false, true]}
+                getLabel={(ignoreCase) =>
+                    ignoreCase ? "ignoring case" : "matching case"
+                }
+                disabled={props.disabled}
+            />
+        </>
+    )
+}
+
 const themeIds = [
     16, 14, 19, 11, 7, 10, 12, 17, 24, 9, 1, 20, 23, 25, 15, 18, 22, 6, 13, 8,
 ]
@@ -677,6 +964,41 @@ const EditSimpleRule: Component<{
                     <EditPlanetInDysonCount {...props} value={value()} />
                 )}
             </Match>
+            <Match when={isType(props.value, RuleType.HabitableRadius)}>
+                {(value) => (
+                    <EditStarRadius {...props} value={value()} unit="AU" />
+                )}
+            </Match>
+            <Match when={isType(props.value, RuleType.LightBalanceRadius)}>
+                {(value) => (
+                    <EditStarRadius {...props} value={value()} unit="AU" />
+                )}
+            </Match>
+            <Match when={isType(props.value, RuleType.OrbitScaler)}>
+                {(value) => (
+                    <EditStarRadius {...props} value={value()} unit="x" />
+                )}
+            </Match>
+            <Match when={isType(props.value, RuleType.HabitableZoneCount)}>
+                {(value) => (
+                    <EditHabitableZoneCount {...props} value={value()} />
+                )}
+            </Match>
+            <Match when={isType(props.value, RuleType.RotationPeriod)}>
+                {(value) => <EditRotationPeriod {...props} value={value()} />}
+            </Match>
+            <Match when={isType(props.value, RuleType.RetrogradeCount)}>
+                {(value) => <EditRetrogradeCount {...props} value={value()} />}
+            </Match>
+            <Match when={isType(props.value, RuleType.ResonanceCount)}>
+                {(value) => <EditResonanceCount {...props} value={value()} />}
+            </Match>
+            <Match when={isType(props.value, RuleType.OrbitalRatio)}>
+                {(value) => <EditOrbitalRatio {...props} value={value()} />}
+            </Match>
+            <Match when={isType(props.value, RuleType.StarName)}>
+                {(value) => <EditStarName {...props} value={value()} />}
+            </Match>
             <Match when={isType(props.value, RuleType.ThemeId)}>
                 {(value) => <EditThemeId {...props} value={value()} />}
             </Match>
@@ -865,6 +1187,15 @@ const ruleNames: Record<RuleType, string> = {
     [RuleType.GasRate]: "Gas Rate",
     [RuleType.AverageVeinAmount]: "Vein Amount",
     [RuleType.PlanetInDysonCount]: "Planets in Dyson Sphere",
+    [RuleType.StarName]: "Star Name",
+    [RuleType.HabitableRadius]: "Habitable Zone Radius",
+    [RuleType.LightBalanceRadius]: "Light Balance Radius",
+    [RuleType.OrbitScaler]: "Orbit Scale",
+    [RuleType.HabitableZoneCount]: "Planets in Habitable Zone",
+    [RuleType.RotationPeriod]: "Rotation Period",
+    [RuleType.RetrogradeCount]: "Retrograde Planet Count",
+    [RuleType.ResonanceCount]: "Orbital Resonance",
+    [RuleType.OrbitalRatio]: "Orbital Period Ratio",
 }
 
 const rules: SimpleRule[] = [
@@ -925,6 +1256,27 @@ const rules: SimpleRule[] = [
             value: 0,
         },
     },
+    {
+        type: RuleType.HabitableRadius,
+        condition: {
+            type: ConditionType.Gte,
+            value: 1,
+        },
+    },
+    {
+        type: RuleType.LightBalanceRadius,
+        condition: {
+            type: ConditionType.Gte,
+            value: 1,
+        },
+    },
+    {
+        type: RuleType.OrbitScaler,
+        condition: {
+            type: ConditionType.Gte,
+            value: 1,
+        },
+    },
     {
         type: RuleType.OceanType,
         oceanType: OceanType.Water,
@@ -941,6 +1293,45 @@ const rules: SimpleRule[] = [
         type: RuleType.ThemeId,
         themeIds: [1],
     },
+    {
+        type: RuleType.HabitableZoneCount,
+        condition: {
+            type: ConditionType.Gte,
+            value: 1,
+        },
+    },
+    {
+        type: RuleType.RotationPeriod,
+        condition: {
+            type: ConditionType.Gte,
+            value: 0,
+        },
+        count: {
+            type: ConditionType.Gte,
+            value: 1,
+        },
+        includeGiant: false,
+    },
+    {
+        type: RuleType.RetrogradeCount,
+        condition: {
+            type: ConditionType.Gte,
+            value: 1,
+        },
+    },
+    {
+        type: RuleType.ResonanceCount,
+        resonance: "Any",
+        condition: {
+            type: ConditionType.Gte,
+            value: 1,
+        },
+    },
+    {
+        type: RuleType.OrbitalRatio,
+        ratio: 2,
+        tolerance: 0.01,
+    },
     {
         type: RuleType.PlanetInDysonCount,
         includeGiant: false,
@@ -960,6 +1351,12 @@ const rules: SimpleRule[] = [
         type: RuleType.Spectr,
         spectr: [SpectrType.O],
     },
+    {
+        type: RuleType.StarName,
+        name: "",
+        mode: "exact",
+        ignoreCase: false,
+    },
     {
         type: RuleType.Birth,
     },
@@ -1037,7 +1434,6 @@ const gasTypes: GasType[] = [
 ]
 
 const gasTypeNames: Record<GasType, string> = {
-    [GasType.None]: "",
     [GasType.Hydrogen]: "Hydrogen",
     [GasType.Deuterium]: "Deuterium",
     [GasType.Fireice]: "Fire Ice",
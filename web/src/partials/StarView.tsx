@@ -223,6 +223,24 @@ const StarDetail: Component<{
                     {toPrecision(props.star.age * props.star.lifetime, 0)} Myrs
                 </div>
             </div>
+            <div class={styles.row}>
+                <div class={styles.field}>Habitable Zone Radius</div>
+                <div class={styles.value}>
+                    {formatNumber(props.star.habitableRadius, 2)} AU
+                </div>
+            </div>
+            <div class={styles.row}>
+                <div class={styles.field}>Light Balance Radius</div>
+                <div class={styles.value}>
+                    {formatNumber(props.star.lightBalanceRadius, 2)} AU
+                </div>
+            </div>
+            <div class={styles.row}>
+                <div class={styles.field}>Orbit Scale</div>
+                <div class={styles.value}>
+                    {formatNumber(props.star.orbitScaler, 2)}x
+                </div>
+            </div>
         </Show>
     </>
 )
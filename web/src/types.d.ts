@@ -40,10 +40,13 @@ declare global {
         luminosity: float
         radius: float
         dysonRadius: float
+        habitableRadius: float
+        lightBalanceRadius: float
+        orbitScaler: float
         planets: Planet[]
     }
 
-    declare type Gas = [itemId: GasType, rate: float]
+    declare type Gas = [item: GasType, rate: float]
 
     declare interface Planet {
         index: integer
@@ -185,6 +188,53 @@ declare global {
             includeGiant: boolean
             condition: Condition
         }
+        export type HabitableRadius = {
+            type: RuleType.HabitableRadius
+            condition: Condition
+        }
+        export type LightBalanceRadius = {
+            type: RuleType.LightBalanceRadius
+            condition: Condition
+        }
+        export type OrbitScaler = {
+            type: RuleType.OrbitScaler
+            condition: Condition
+        }
+        export type HabitableZoneCount = {
+            type: RuleType.HabitableZoneCount
+            condition: Condition
+        }
+        export type RotationPeriod = {
+            type: RuleType.RotationPeriod
+            condition: Condition
+            count: Condition
+            includeGiant: boolean
+        }
+        export type RetrogradeCount = {
+            type: RuleType.RetrogradeCount
+            condition: Condition
+        }
+        export type ResonanceCount = {
+            type: RuleType.ResonanceCount
+            resonance:
+                | "Eligible"
+                | "Any"
+                | "TidalLock"
+                | "OneToTwo"
+                | "OneToFour"
+            condition: Condition
+        }
+        export type OrbitalRatio = {
+            type: RuleType.OrbitalRatio
+            ratio: number
+            tolerance: number
+        }
+        export type StarName = {
+            type: RuleType.StarName
+            name: string
+            mode: "exact" | "prefix" | "regex"
+            ignoreCase: boolean
+        }
 
         export type Composite = {
             type: CompositeRuleType.Composite
@@ -222,6 +272,15 @@ declare global {
         | Rule.SpectrDistance
         | Rule.GasRate
         | Rule.PlanetInDysonCount
+        | Rule.StarName
+        | Rule.HabitableRadius
+        | Rule.LightBalanceRadius
+        | Rule.OrbitScaler
+        | Rule.HabitableZoneCount
+        | Rule.RotationPeriod
+        | Rule.RetrogradeCount
+        | Rule.ResonanceCount
+        | Rule.OrbitalRatio
 
     declare type CompoundRule = Rule.And | Rule.Or
 
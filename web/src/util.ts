@@ -107,7 +107,6 @@ export const veinNames: Record<VeinType, string> = {
 }
 
 export const gasNames: Record<GasType, string> = {
-    [GasType.None]: "",
     [GasType.Fireice]: "Fire Ice",
     [GasType.Hydrogen]: "Hydrogen",
     [GasType.Deuterium]: "Deuterium",
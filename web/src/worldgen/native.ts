@@ -64,8 +64,14 @@ export class WorldGenNative implements WorldGen {
 
                 ws.addEventListener("message", (ev) => {
                     const msg = JSON.parse(ev.data)
-                    if (msg.type === "Result") {
-                        onResult?.({ seed: msg.seed, indexes: msg.indexes })
+                    if (msg.type === "Results") {
+                        for (const { seed, indexes } of msg.items) {
+                            onResult?.({ seed, indexes })
+                        }
+                    } else if (msg.type === "Error") {
+                        done = true
+                        onError?.(msg.message)
+                        ws.close()
                     } else {
                         onProgress?.(msg.end)
                         if (msg.type === "Done") {